@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::loaders::png_loading::load_png;
+
+
+/// Имя текстуры, которая подставляется вместо отсутствующих
+pub const MISSING_TEXTURE: &str = "missing";
+
+/// Время кадра анимации, если файл `.anim` рядом с текстурой пуст
+const DEFAULT_FRAME_TIME: f32 = 0.1;
+
+
+/// Прямоугольник текстуры в атласе в UV координатах
+#[derive(Clone, Copy, Debug)]
+pub struct UvRegion {
+    pub u1: f32,
+    pub v1: f32,
+    pub u2: f32,
+    pub v2: f32,
+}
+
+
+/// Прямоугольник в атласе в пикселях
+#[derive(Clone, Copy, Debug)]
+pub struct PixelRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+
+struct AtlasImage {
+    name: String,
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+
+struct AtlasAnimation {
+    name: String,
+    frames: Vec<AtlasImage>,
+    frame_time: f32,
+}
+
+
+/// Анимированная текстура в готовом атласе.
+///
+/// Кадры хранятся уже с полями, `region` включает поля.
+pub struct TextureAnimation {
+    pub region: PixelRegion,
+    frames: Vec<Vec<u8>>,
+    frame_time: f32,
+    current: usize,
+}
+
+
+impl TextureAnimation {
+    /// Номер кадра для момента игрового времени `time`
+    pub fn frame_at(&self, time: f64) -> usize {
+        if self.frame_time <= 0.0 {
+            return 0;
+        }
+        ((time / self.frame_time as f64).floor() as i64).rem_euclid(self.frames.len() as i64) as usize
+    }
+
+
+    #[cfg(feature = "graphics")]
+    pub fn current_frame(&self) -> &[u8] {
+        &self.frames[self.current]
+    }
+}
+
+
+/// Собирает атлас из отдельных RGBA изображений.
+///
+/// Строки изображений хранятся снизу вверх, как их отдаёт `load_png`
+/// и ожидает OpenGL.
+pub struct AtlasBuilder {
+    images: Vec<AtlasImage>,
+    animations: Vec<AtlasAnimation>,
+    padding: u32,
+}
+
+
+impl AtlasBuilder {
+    /// `padding` - ширина полей вокруг каждой текстуры, заполненных её
+    /// крайними пикселями, чтобы мипмапы не подмешивали соседей
+    pub fn new(padding: u32) -> Self {
+        Self { images: Vec::new(), animations: Vec::new(), padding }
+    }
+
+
+    pub fn add(&mut self, name: &str, data: Vec<u8>, width: u32, height: u32) {
+        self.images.push(AtlasImage { name: name.to_string(), data, width, height });
+    }
+
+
+    /// Добавляет анимацию из вертикальной полосы квадратных кадров,
+    /// первый кадр - верхний
+    pub fn add_animated(&mut self, name: &str, data: Vec<u8>, width: u32, height: u32, frame_time: f32) {
+        let count = (height / width).max(1);
+        let frame_size = (width * width * 4) as usize;
+
+        // Строки идут снизу вверх, поэтому верхний кадр лежит в конце буфера
+        let frames = (0..count)
+            .rev()
+            .map(|i| AtlasImage {
+                name: name.to_string(),
+                data: data[i as usize * frame_size..(i as usize + 1) * frame_size].to_vec(),
+                width,
+                height: width,
+            })
+            .collect();
+
+        self.animations.push(AtlasAnimation { name: name.to_string(), frames, frame_time });
+    }
+
+
+    /// Загружает все `*.png` из папки, имя текстуры - имя файла без расширения
+    pub fn add_dir(&mut self, dir: &str) -> Result<(), String> {
+        let entries = fs::read_dir(dir).map_err(|e| format!("read dir '{}': {}", dir, e))?;
+
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "png"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            self.add_file(&path)?;
+        }
+        Ok(())
+    }
+
+
+    fn add_file(&mut self, path: &Path) -> Result<(), String> {
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| format!("bad texture name '{}'", path.display()))?;
+
+        let (data, width, height) = load_png(&path.to_string_lossy())
+            .map_err(|e| format!("load '{}': {}", path.display(), e))?;
+        let (width, height) = (width as u32, height as u32);
+
+        let anim = fs::read_to_string(path.with_extension("anim")).ok();
+        match frame_time(name, width, height, anim.as_deref())? {
+            Some(frame_time) => self.add_animated(name, data, width, height, frame_time),
+            None => self.add(name, data, width, height),
+        }
+        Ok(())
+    }
+
+
+    pub fn build(&self) -> Atlas {
+        let pad = self.padding;
+
+        // Первый кадр анимации занимает в атласе обычное место текстуры
+        let images: Vec<&AtlasImage> = self.images
+            .iter()
+            .chain(self.animations.iter().map(|animation| &animation.frames[0]))
+            .collect();
+
+        // Полочная упаковка: сначала самые высокие изображения
+        let mut order: Vec<usize> = (0..images.len()).collect();
+        order.sort_by(|&a, &b| images[b].height.cmp(&images[a].height));
+
+        let area: u32 = images
+            .iter()
+            .map(|image| (image.width + pad * 2) * (image.height + pad * 2))
+            .sum();
+        let widest = images.iter().map(|image| image.width + pad * 2).max().unwrap_or(1);
+        let width = ((area as f32).sqrt().ceil() as u32).max(widest).next_power_of_two();
+
+        let mut positions = vec![(0, 0); images.len()];
+        let mut x = 0;
+        let mut y = 0;
+        let mut shelf = 0;
+        for &index in &order {
+            let image = images[index];
+            let cell_w = image.width + pad * 2;
+            let cell_h = image.height + pad * 2;
+            if x + cell_w > width {
+                x = 0;
+                y += shelf;
+                shelf = 0;
+            }
+            positions[index] = (x + pad, y + pad);
+            x += cell_w;
+            shelf = shelf.max(cell_h);
+        }
+        let height = (y + shelf).max(1).next_power_of_two();
+
+        let mut data = vec![0u8; (width * height * 4) as usize];
+        let mut names = HashMap::new();
+        let mut regions = Vec::with_capacity(images.len());
+        let mut pixels = Vec::with_capacity(images.len());
+
+        for (index, image) in images.iter().enumerate() {
+            let (px, py) = positions[index];
+            blit_padded(&mut data, width, image, px, py, pad);
+
+            names.insert(image.name.clone(), index);
+            pixels.push(PixelRegion { x: px - pad, y: py - pad, width: image.width + pad * 2, height: image.height + pad * 2 });
+            regions.push(UvRegion {
+                u1: px as f32 / width as f32,
+                v1: py as f32 / height as f32,
+                u2: (px + image.width) as f32 / width as f32,
+                v2: (py + image.height) as f32 / height as f32,
+            });
+        }
+
+        let animations = self.animations
+            .iter()
+            .map(|animation| {
+                let region = pixels[names[&animation.name]];
+                let frames = animation.frames
+                    .iter()
+                    .map(|frame| {
+                        let mut data = vec![0u8; (region.width * region.height * 4) as usize];
+                        blit_padded(&mut data, region.width, frame, pad, pad, pad);
+                        data
+                    })
+                    .collect();
+                TextureAnimation { region, frames, frame_time: animation.frame_time, current: 0 }
+            })
+            .collect();
+
+        Atlas { data, width, height, names, regions, animations }
+    }
+}
+
+
+/// Время кадра анимированной текстуры. Полосой кадров текстуру делает только
+/// файл `.anim` рядом с ней (`anim` - его текст): в нём время кадра в секундах
+/// или ничего
+fn frame_time(name: &str, width: u32, height: u32, anim: Option<&str>) -> Result<Option<f32>, String> {
+    let Some(text) = anim.map(str::trim) else {
+        return Ok(None);
+    };
+    if !height.is_multiple_of(width) {
+        return Err(format!("animated texture '{}' is not a strip of square frames", name));
+    }
+    if text.is_empty() {
+        return Ok(Some(DEFAULT_FRAME_TIME));
+    }
+    match text.parse::<f32>() {
+        Ok(frame_time) if frame_time > 0.0 && frame_time.is_finite() => Ok(Some(frame_time)),
+        Ok(_) => Err(format!("bad frame time for '{}': must be positive", name)),
+        Err(e) => Err(format!("bad frame time for '{}': {}", name, e)),
+    }
+}
+
+
+/// Копирует изображение в атлас и размазывает крайние пиксели на поля
+fn blit_padded(dest: &mut [u8], dest_width: u32, image: &AtlasImage, px: u32, py: u32, pad: u32) {
+    let (w, h) = (image.width as i64, image.height as i64);
+    let pad = pad as i64;
+    for y in -pad..h + pad {
+        for x in -pad..w + pad {
+            let sx = x.clamp(0, w - 1);
+            let sy = y.clamp(0, h - 1);
+            let src = ((sy * w + sx) * 4) as usize;
+            let dx = (px as i64 + x) as u32;
+            let dy = (py as i64 + y) as u32;
+            let dst = ((dy * dest_width + dx) * 4) as usize;
+            dest[dst..dst + 4].copy_from_slice(&image.data[src..src + 4]);
+        }
+    }
+}
+
+
+/// Готовый атлас: RGBA пиксели и области текстур по индексам
+pub struct Atlas {
+    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    names: HashMap<String, usize>,
+    regions: Vec<UvRegion>,
+    pub animations: Vec<TextureAnimation>,
+}
+
+
+impl Atlas {
+    pub fn get_index(&self, name: &str) -> Option<usize> {
+        self.names.get(name).copied()
+    }
+
+
+    /// Индекс текстуры для описаний блоков
+    pub fn index(&self, name: &str) -> Option<i32> {
+        self.get_index(name).map(|index| index as i32)
+    }
+
+
+    pub fn uv(&self, index: usize) -> UvRegion {
+        self.regions.get(index).copied().unwrap_or(UvRegion { u1: 0.0, v1: 0.0, u2: 0.0, v2: 0.0 })
+    }
+
+
+    /// Переключает анимации на кадры момента `time` и копирует их в `data`.
+    /// Возвращает индексы анимаций, у которых сменился кадр
+    pub fn animate(&mut self, time: f64) -> Vec<usize> {
+        let mut changed = Vec::new();
+        for (index, animation) in self.animations.iter_mut().enumerate() {
+            let frame = animation.frame_at(time);
+            if frame == animation.current {
+                continue;
+            }
+            animation.current = frame;
+
+            let region = animation.region;
+            let row = (region.width * 4) as usize;
+            for y in 0..region.height {
+                let src = y as usize * row;
+                let dst = (((region.y + y) * self.width + region.x) * 4) as usize;
+                self.data[dst..dst + row].copy_from_slice(&animation.frames[frame][src..src + row]);
+            }
+            changed.push(index);
+        }
+        changed
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Полоса из `count` кадров 2x2, каждый залит своим номером
+    fn strip(count: u8) -> Vec<u8> {
+        // Строки снизу вверх: первый, верхний кадр лежит в конце
+        (0..count).rev().flat_map(|frame| [frame; 2 * 2 * 4]).collect()
+    }
+
+    fn solid(value: u8, width: u32, height: u32) -> Vec<u8> {
+        vec![value; (width * height * 4) as usize]
+    }
+
+    fn pixel(atlas: &Atlas, x: u32, y: u32) -> u8 {
+        atlas.data[((y * atlas.width + x) * 4) as usize]
+    }
+
+    #[test]
+    fn shelves_are_packed_tallest_first_with_padding() {
+        let mut builder = AtlasBuilder::new(1);
+        builder.add("a", solid(1, 4, 4), 4, 4);
+        builder.add("b", solid(2, 2, 2), 2, 2);
+        builder.add("c", solid(3, 4, 2), 4, 2);
+        builder.add("d", solid(4, 8, 1), 8, 1);
+        let atlas = builder.build();
+        assert_eq!((atlas.width, atlas.height), (16, 16));
+
+        // Первая полка: a, b, c; d не помещается и уходит на вторую
+        let origin = |name: &str| {
+            let uv = atlas.uv(atlas.get_index(name).unwrap());
+            ((uv.u1 * 16.0) as u32, (uv.v1 * 16.0) as u32)
+        };
+        assert_eq!(origin("a"), (1, 1));
+        assert_eq!(origin("b"), (7, 1));
+        assert_eq!(origin("c"), (11, 1));
+        assert_eq!(origin("d"), (1, 7));
+
+        // Поля заполнены крайними пикселями своей текстуры
+        assert_eq!(pixel(&atlas, 0, 0), 1);
+        assert_eq!(pixel(&atlas, 5, 5), 1);
+        assert_eq!(pixel(&atlas, 6, 0), 2);
+        assert_eq!(pixel(&atlas, 15, 0), 3);
+        assert_eq!(pixel(&atlas, 0, 8), 4);
+    }
+
+    #[test]
+    fn unknown_names_have_no_index() {
+        let mut builder = AtlasBuilder::new(0);
+        builder.add("stone", solid(1, 2, 2), 2, 2);
+        let atlas = builder.build();
+        assert_eq!(atlas.index("stone"), Some(0));
+        assert_eq!(atlas.index("marble"), None);
+        assert_eq!(atlas.uv(7).u2, 0.0);
+    }
+
+    #[test]
+    fn frame_at_wraps_around() {
+        let mut builder = AtlasBuilder::new(0);
+        builder.add_animated("water", strip(4), 2, 8, 0.5);
+        let atlas = builder.build();
+        let animation = &atlas.animations[0];
+
+        assert_eq!(animation.frame_at(0.0), 0);
+        assert_eq!(animation.frame_at(0.49), 0);
+        assert_eq!(animation.frame_at(0.5), 1);
+        assert_eq!(animation.frame_at(1.9), 3);
+        assert_eq!(animation.frame_at(2.0), 0);
+        assert_eq!(animation.frame_at(2.6), 1);
+        assert_eq!(animation.frame_at(-0.1), 3);
+    }
+
+    #[test]
+    fn animate_copies_changed_frames() {
+        let mut builder = AtlasBuilder::new(0);
+        builder.add_animated("water", strip(3), 2, 6, 1.0);
+        let mut atlas = builder.build();
+        let pixel = |atlas: &Atlas| {
+            let region = atlas.animations[0].region;
+            atlas.data[((region.y * atlas.width + region.x) * 4) as usize]
+        };
+        assert_eq!(pixel(&atlas), 0);
+
+        assert!(atlas.animate(0.5).is_empty());
+        assert_eq!(atlas.animate(1.5), vec![0]);
+        assert_eq!(pixel(&atlas), 1);
+        assert_eq!(atlas.animate(3.5), vec![0]);
+        assert_eq!(pixel(&atlas), 0);
+    }
+
+    #[test]
+    fn only_marked_textures_are_animated() {
+        assert_eq!(frame_time("tall", 32, 256, None), Ok(None));
+        assert_eq!(frame_time("water", 32, 256, Some("0.15\n")), Ok(Some(0.15)));
+        assert_eq!(frame_time("water", 32, 256, Some("")), Ok(Some(DEFAULT_FRAME_TIME)));
+        assert!(frame_time("water", 32, 48, Some("0.1")).is_err());
+        assert!(frame_time("water", 32, 64, Some("fast")).is_err());
+        assert!(frame_time("water", 32, 64, Some("0")).is_err());
+        assert!(frame_time("water", 32, 64, Some("NaN")).is_err());
+    }
+}
@@ -1,5 +1,9 @@
 use std::io;
+use crate::fluids::{Fluid, Hardening};
+use crate::ticks::Behaviour;
 use crate::voxels::{Block, BlockRegistry};
+use super::atlas::MISSING_TEXTURE;
+use super::Atlas;
 
 pub struct BlocksController {
     pub blocks: BlockRegistry
@@ -8,7 +12,7 @@ pub struct BlocksController {
 
 impl BlocksController {
     pub fn init() -> Result<Self, io::Error> {
-        let mut blocks = BlockRegistry::new();
+        let blocks = BlockRegistry::new();
 
         Ok(Self{
             blocks
@@ -16,37 +20,114 @@ impl BlocksController {
     }
 
 
-    pub fn setup_blocks(&mut self) {
-        let mut block = Block::new(0, 0);
+    /// Регистрирует блоки. Ошибка перечисляет текстуры, которых нет в атласе
+    pub fn setup_blocks(&mut self, atlas: &Atlas) -> Result<(), String> {
+        let mut unknown = Vec::new();
+        let mut texture = |name: &str| atlas.index(name).unwrap_or_else(|| {
+            unknown.push(name.to_string());
+            0
+        });
+
+        let mut block = Block::new(0, "air", texture(MISSING_TEXTURE));
         block.draw_group = 1;
         block.light_passing = true;
+        block.obstacle = false;
         self.blocks.blocks[block.id as usize] = Some(block.clone());
 
         // STONE
-        block = Block::new(1, 2);
+        block = Block::new(1, "stone", texture("stone"));
         self.blocks.blocks[block.id as usize] = Some(block.clone());
 
         // GRASS
-        block = Block::new(2, 4);
-        block.texture_faces[2] = 2;
-        block.texture_faces[3] = 1;
+        block = Block::new(2, "grass", texture("grass_side"));
+        block.texture_faces[2] = texture("stone");
+        block.texture_faces[3] = texture("grass_top");
+        block.behaviour = Some(Behaviour::Spreads { onto: 10 });
         self.blocks.blocks[block.id as usize] = Some(block.clone());
 
         // LAMP
-        block = Block::new(3, 3);
+        block = Block::new(3, "lamp", texture("lamp"));
         block.emission[0] = 11;
         block.emission[1] = 11;
         block.emission[2] = 6;
         self.blocks.blocks[block.id as usize] = Some(block.clone());
 
         // GLASS
-        block = Block::new(4, 5);
+        block = Block::new(4, "glass", texture("glass"));
         block.draw_group = 2;
         block.light_passing = true;
         self.blocks.blocks[block.id as usize] = Some(block.clone());
 
-        // GLASS
-        block = Block::new(5, 6);
+        // PLANKS
+        block = Block::new(5, "planks", texture("planks"));
+        self.blocks.blocks[block.id as usize] = Some(block.clone());
+
+        // RED GLASS: окрашивает свет ламп, небесный проходит как есть
+        block = Block::new(6, "red_glass", texture("red_glass"));
+        block.draw_group = 2;
+        block.light_passing = true;
+        block.light_absorption = [0, 15, 15, 0];
         self.blocks.blocks[block.id as usize] = Some(block.clone());
+
+        // LEAVES
+        block = Block::new(7, "leaves", texture("leaves"));
+        block.light_passing = true;
+        block.light_absorption = [2; 4];
+        self.blocks.blocks[block.id as usize] = Some(block.clone());
+
+        // WATER
+        block = Block::new(8, "water", texture("water"));
+        block.draw_group = 3;
+        block.light_passing = true;
+        block.light_absorption = [3; 4];
+        block.obstacle = false;
+        block.fluid = Some(Fluid { step: 1, delay: 5, infinite: true, hardens: None });
+        self.blocks.blocks[block.id as usize] = Some(block.clone());
+
+        // LAVA
+        block = Block::new(9, "lava", texture("lava"));
+        block.emission = [15, 8, 2];
+        // Своя группа: соседние блоки видны над опустившейся лавой
+        block.draw_group = 4;
+        block.obstacle = false;
+        block.fluid = Some(Fluid { step: 2, delay: 30, infinite: false, hardens: Some(Hardening { touching: 8, into: 1 }) });
+        self.blocks.blocks[block.id as usize] = Some(block.clone());
+
+        // DIRT
+        block = Block::new(10, "dirt", texture("dirt"));
+        self.blocks.blocks[block.id as usize] = Some(block.clone());
+
+        // SAND
+        block = Block::new(11, "sand", texture("sand"));
+        block.behaviour = Some(Behaviour::Falls);
+        self.blocks.blocks[block.id as usize] = Some(block.clone());
+
+        // GRAVEL
+        block = Block::new(12, "gravel", texture("gravel"));
+        block.behaviour = Some(Behaviour::Falls);
+        self.blocks.blocks[block.id as usize] = Some(block.clone());
+
+        // WHEAT
+        block = Block::new(13, "wheat", texture("wheat"));
+        block.draw_group = 5;
+        block.light_passing = true;
+        block.obstacle = false;
+        block.behaviour = Some(Behaviour::Grows { stages: 4 });
+        self.blocks.blocks[block.id as usize] = Some(block.clone());
+
+        // FLASHING LAMP: горит и гаснет раз в секунду
+        block = Block::new(14, "flashing_lamp", texture("lamp"));
+        block.emission = [11, 11, 6];
+        block.behaviour = Some(Behaviour::Toggles { into: 15, period: 60 });
+        self.blocks.blocks[block.id as usize] = Some(block.clone());
+
+        block = Block::new(15, "flashing_lamp_off", texture("lamp_off"));
+        block.behaviour = Some(Behaviour::Toggles { into: 14, period: 60 });
+        self.blocks.blocks[block.id as usize] = Some(block.clone());
+
+        if !unknown.is_empty() {
+            return Err(format!("unknown textures: {}", unknown.join(", ")));
+        }
+        Ok(())
     }
-}
\ No newline at end of file
+}
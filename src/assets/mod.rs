@@ -1,6 +1,12 @@
+#[cfg(feature = "graphics")]
 mod assets_loading;
 mod blocks_controller;
+mod atlas;
 
 
+#[cfg(feature = "graphics")]
 pub use assets_loading::Assets;
-pub use blocks_controller::BlocksController;
\ No newline at end of file
+pub use blocks_controller::BlocksController;
+pub use atlas::{Atlas, AtlasBuilder};
+#[cfg(feature = "graphics")]
+pub use atlas::UvRegion;
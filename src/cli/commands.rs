@@ -0,0 +1,198 @@
+use std::fs;
+use std::io;
+use std::time::Instant;
+
+use crate::voxels::{BlockRegistry, Chunks, CHUNK_D, CHUNK_H, CHUNK_VOL, CHUNK_W};
+use glam::Vec3;
+
+use crate::assets::Atlas;
+use crate::export::{self, ExportMesh};
+use crate::graphics::rasterizer::{self, render_world};
+use crate::loaders::png_loading::{load_png, save_png};
+use crate::window::Camera;
+use crate::world::{world_file, WorldTime};
+use crate::lighting::Lighting;
+use crate::load_content;
+use super::{RenderOptions, WorldSize};
+
+
+pub fn generate(path: &str, seed: u32, size: WorldSize) -> io::Result<()> {
+    println!("generating {}x{}x{} chunks with seed {}", size.w, size.h, size.d, seed);
+    let chunks = Chunks::new(size.w, size.h, size.d, seed);
+    world_file::save(path, &chunks, false)
+}
+
+
+pub fn info(path: &str) -> io::Result<()> {
+    let (header, chunks) = world_file::load(path)?;
+    let file_size = fs::metadata(path)?.len();
+
+    println!("world:   {}", path);
+    println!("version: {}", header.version);
+    println!("chunks:  {}x{}x{} ({} total)", header.w, header.h, header.d, chunks.volume);
+    println!("voxels:  {}x{}x{}", header.w * CHUNK_W, header.h * CHUNK_H, header.d * CHUNK_D);
+    println!("seed:    {}", header.seed);
+    println!("light:   {}", if header.has_light() { "stored" } else { "not stored" });
+    println!("size:    {} bytes", file_size);
+
+    let mut counts = [0usize; 256];
+    for chunk in &chunks.chunks {
+        for voxel in &chunk.voxels {
+            counts[voxel.id as usize] += 1;
+        }
+    }
+    println!("blocks:");
+    for (id, count) in counts.iter().enumerate().filter(|(_, count)| **count > 0) {
+        println!("    {:3}: {}", id, count);
+    }
+    Ok(())
+}
+
+
+/// Старый мир - только id вокселей; размер берётся из `size`
+/// или угадывается, если мир кубический
+pub fn convert(from: &str, to: &str, size: Option<WorldSize>) -> io::Result<()> {
+    let data = fs::read(from)?;
+    if world_file::is_world_file(&data) {
+        let (header, chunks) = world_file::load(from)?;
+        println!("'{}' is already a version {} world, rewriting", from, header.version);
+        return world_file::save(to, &chunks, header.has_light());
+    }
+
+    let size = match size {
+        Some(size) => size,
+        None => guess_size(data.len()).ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("can't guess size of '{}' ({} bytes), pass --size", from, data.len()),
+        ))?,
+    };
+    let chunks = world_file::load_legacy(from, size.w, size.h, size.d)?;
+    println!("converted {}x{}x{} chunks", size.w, size.h, size.d);
+    world_file::save(to, &chunks, false)
+}
+
+
+fn guess_size(bytes: usize) -> Option<WorldSize> {
+    if bytes == 0 || !bytes.is_multiple_of(CHUNK_VOL) {
+        return None;
+    }
+    let volume = bytes / CHUNK_VOL;
+    let n = (1..=volume).find(|n| n * n * n >= volume)?;
+    (n * n * n == volume).then_some(WorldSize { w: n, h: n, d: n })
+}
+
+
+/// Считает освещение и сохраняет его в файл, чтобы игра не делала этого при загрузке
+pub fn relight(path: &str) -> io::Result<()> {
+    let (_atlas, blocks_controller) = load_content();
+    let (_, mut chunks) = world_file::load(path)?;
+
+    let start = Instant::now();
+    let mut lighting = Lighting::new();
+    lighting.clear(&mut chunks);
+    lighting.on_world_loaded(&blocks_controller.blocks, &mut chunks);
+    println!("lighting: ok in {:?}", start.elapsed());
+
+    world_file::save(path, &chunks, true)
+}
+
+
+/// Меш мира для других программ; мир без света сначала освещается
+pub fn export(path: &str, output: &str, time: Option<f32>) -> io::Result<()> {
+    let (atlas, blocks_controller) = load_content();
+    let chunks = load_lit(path, &blocks_controller.blocks)?;
+    let world_time = time_of_day(time);
+    let mesh = ExportMesh::build(&chunks, &blocks_controller.blocks, &atlas, None, world_time.sky_light());
+    let written = export::export(output, &mesh, &atlas).map_err(io::Error::other)?;
+    println!("exported {} triangles: {}", mesh.indices.len() / 3, written.join(", "));
+    Ok(())
+}
+
+
+/// Кадр мира с программного растеризатора; с эталоном - проверка, что картинка не изменилась
+pub fn render(options: &RenderOptions, fov: f32) -> io::Result<()> {
+    let (atlas, blocks_controller) = load_content();
+    let chunks = load_lit(&options.world, &blocks_controller.blocks)?;
+
+    let start = Instant::now();
+    let pixels = render_frame(options, fov, &chunks, &blocks_controller.blocks, &atlas);
+    println!("rendered {}x{} in {:?}", options.width, options.height, start.elapsed());
+    save_png(&options.output, &pixels, options.width as u32, options.height as u32).map_err(io::Error::other)?;
+
+    let Some(golden) = &options.compare else {
+        return Ok(());
+    };
+    let (expected, width, height) = load_png(golden).map_err(|e| io::Error::other(format!("can't load '{}': {}", golden, e)))?;
+    if (width as usize, height as usize) != (options.width, options.height) {
+        return Err(io::Error::other(format!(
+            "'{}' is {}x{}, the render is {}x{}",
+            golden, width, height, options.width, options.height
+        )));
+    }
+    let diff = rasterizer::compare(&pixels, &expected, options.tolerance);
+    println!("compare: {} pixels differ by more than {}, max difference {}", diff.mismatched, options.tolerance, diff.max);
+    if diff.mismatched > 0 {
+        return Err(io::Error::other(format!("'{}' doesn't match '{}'", options.output, golden)));
+    }
+    Ok(())
+}
+
+
+/// RGBA пиксели кадра с камеры из `options`
+fn render_frame(options: &RenderOptions, fov: f32, chunks: &Chunks, blocks: &BlockRegistry, atlas: &Atlas) -> Vec<u8> {
+    let size = Vec3::new((chunks.w * CHUNK_W) as f32, (chunks.h * CHUNK_H) as f32, (chunks.d * CHUNK_D) as f32);
+    let eye = options.eye.map_or(Vec3::new(-0.25, 1.0, -0.25) * size + Vec3::Y * 8.0, Vec3::from);
+    let target = options.target.map_or(size * Vec3::new(0.5, 0.25, 0.5), Vec3::from);
+    let mut camera = Camera::new(eye, options.fov.unwrap_or(fov).to_radians());
+    camera.look_at(target);
+
+    let world_time = time_of_day(options.time);
+    render_world(chunks, blocks, atlas, &camera, &world_time, options.width, options.height).pixels()
+}
+
+
+/// Мир из файла; без сохранённого света он считается заново
+fn load_lit(path: &str, blocks: &BlockRegistry) -> io::Result<Chunks> {
+    let (header, mut chunks) = world_file::load(path)?;
+    if !header.has_light() {
+        let mut lighting = Lighting::new();
+        lighting.clear(&mut chunks);
+        lighting.on_world_loaded(blocks, &mut chunks);
+    }
+    Ok(chunks)
+}
+
+
+/// Часы для света неба; без `time` - полдень
+fn time_of_day(time: Option<f32>) -> WorldTime {
+    let mut world_time = WorldTime::new(0.0);
+    if let Some(time) = time {
+        world_time.set_time_of_day(time);
+    }
+    world_time
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    /// Мир из одного чанка с постройкой из досок, лампой и красным стеклом
+    const WORLD: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/render/world.bin");
+    /// `render world.bin golden.png --resolution 160x90 --time 0.5`
+    const GOLDEN: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/render/golden.png");
+
+    #[test]
+    fn render_matches_golden() {
+        let (atlas, blocks_controller) = load_content();
+        let blocks = &blocks_controller.blocks;
+        let chunks = load_lit(WORLD, blocks).unwrap();
+        let options = RenderOptions { width: 160, height: 90, time: Some(0.5), tolerance: 2, ..Default::default() };
+        let pixels = render_frame(&options, Config::default().graphics.fov, &chunks, blocks, &atlas);
+
+        let (expected, width, height) = load_png(GOLDEN).unwrap();
+        assert_eq!((width as usize, height as usize), (options.width, options.height));
+        assert_eq!(rasterizer::compare(&pixels, &expected, options.tolerance).mismatched, 0);
+    }
+}
@@ -0,0 +1,339 @@
+use std::str::FromStr;
+
+use crate::settings::*;
+
+pub mod commands;
+
+
+pub const USAGE: &str = "\
+usage:
+    Voxel_Craft_Rust [play] [world] [--headless] [--seed N] [--size S]
+                     [--width N] [--height N] [--fov F]
+                     [--record <file> | --replay <file> | --script <file>]
+    Voxel_Craft_Rust generate <world> [--seed N] [--size S]
+    Voxel_Craft_Rust info <world>
+    Voxel_Craft_Rust convert <old> <new> [--size S]
+    Voxel_Craft_Rust relight <world>
+    Voxel_Craft_Rust export <world> <file.obj|file.gltf> [--time T]
+    Voxel_Craft_Rust render <world> <file.png> [--resolution WxH] [--eye X,Y,Z]
+                     [--target X,Y,Z] [--time T] [--fov F]
+                     [--compare <golden.png>] [--tolerance N]
+    Voxel_Craft_Rust help
+
+S - размер мира в чанках: `4` или `4x2x4` (ширина x высота x глубина)
+
+export пишет меш мира в OBJ или glTF, атлас - рядом в PNG; свет неба берётся
+для времени суток T от 0 до 1, по умолчанию полдень
+
+render рисует мир на CPU без окна и видеокарты. С --compare картинка сверяется
+с эталоном: каналы могут отличаться не больше чем на N (по умолчанию 2), иначе
+команда завершается ошибкой. Без --eye камера смотрит на мир сверху сбоку
+
+--script выполняет команды консоли из файла, по одной на строку, после загрузки
+мира; без окна мир затем сохраняется, а ошибка в скрипте завершает запуск
+
+Любой команде можно передать --config <path>; по умолчанию читается settings.cfg
+из рабочей папки. Флаги важнее файла настроек, файл важнее значений по умолчанию";
+
+
+/// Размер мира в чанках
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WorldSize {
+    pub w: usize,
+    pub h: usize,
+    pub d: usize,
+}
+
+
+impl Default for WorldSize {
+    fn default() -> Self {
+        Self { w: WORLD_W, h: WORLD_H, d: WORLD_D }
+    }
+}
+
+
+/// Параметры запуска игры. Заданные поля перекрывают файл настроек
+#[derive(Clone, Debug, Default)]
+pub struct PlayOptions {
+    pub headless: bool,
+    pub world: Option<String>,
+    pub seed: Option<u32>,
+    pub size: Option<WorldSize>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fov: Option<f32>,
+    /// Записать ввод в файл
+    pub record: Option<String>,
+    /// Воспроизвести записанный ввод вместо живого
+    pub replay: Option<String>,
+    /// Выполнить команды консоли из файла
+    pub script: Option<String>,
+}
+
+
+/// Параметры `render`; без `eye` и `target` камера ставится по размеру мира
+#[derive(Clone, Debug, Default)]
+pub struct RenderOptions {
+    pub world: String,
+    pub output: String,
+    pub width: usize,
+    pub height: usize,
+    pub eye: Option<[f32; 3]>,
+    pub target: Option<[f32; 3]>,
+    pub time: Option<f32>,
+    pub fov: Option<f32>,
+    /// Эталон для сравнения
+    pub compare: Option<String>,
+    pub tolerance: u8,
+}
+
+
+#[derive(Clone, Debug)]
+pub enum Command {
+    Play(PlayOptions),
+    /// Без `seed` и `size` берутся значения из настроек
+    Generate { world: String, seed: Option<u32>, size: Option<WorldSize> },
+    Info { world: String },
+    /// Перевод мира старого формата (без заголовка) в текущий
+    Convert { from: String, to: String, size: Option<WorldSize> },
+    Relight { world: String },
+    /// Меш мира в OBJ или glTF; `time` - время суток для света неба
+    Export { world: String, output: String, time: Option<f32> },
+    /// Кадр мира с программного растеризатора
+    Render(RenderOptions),
+    Help,
+}
+
+
+pub struct Cli {
+    pub command: Command,
+    /// Путь из `--config`
+    pub config: Option<String>,
+}
+
+
+/// Разбор аргументов командной строки без имени программы.
+/// Без подкоманды запускается игра, как и раньше
+pub fn parse(args: &[String]) -> Result<Cli, String> {
+    let mut config = None;
+    let mut args = args.to_vec();
+    if let Some(index) = args.iter().position(|arg| arg == "--config") {
+        if index + 1 >= args.len() {
+            return Err("--config expects a value".to_string());
+        }
+        config = Some(args.remove(index + 1));
+        args.remove(index);
+    }
+
+    let (command, rest) = match args.first().map(String::as_str) {
+        None => ("play", &args[..]),
+        Some(first) if first.starts_with("--") => ("play", &args[..]),
+        Some(first) => (first, &args[1..]),
+    };
+
+    let mut parser = Parser::new(rest);
+    let command = match command {
+        "play" => {
+            let mut options = PlayOptions::default();
+            while let Some(arg) = parser.next() {
+                match arg {
+                    "--headless" => options.headless = true,
+                    "--seed" => options.seed = Some(parser.seed()?),
+                    "--size" => options.size = Some(parser.size()?),
+                    "--width" => options.width = Some(parser.number("--width")?),
+                    "--height" => options.height = Some(parser.number("--height")?),
+                    "--fov" => options.fov = Some(parser.number("--fov")?),
+                    "--record" => options.record = Some(parser.value("--record")?.to_string()),
+                    "--replay" => options.replay = Some(parser.value("--replay")?.to_string()),
+                    "--script" => options.script = Some(parser.value("--script")?.to_string()),
+                    _ => options.world = Some(parser.positional(arg)?),
+                }
+            }
+            if options.record.is_some() && (options.replay.is_some() || options.headless) {
+                return Err("--record needs live input: it can't be used with --replay or --headless".to_string());
+            }
+            if options.script.is_some() && (options.record.is_some() || options.replay.is_some()) {
+                return Err("--script changes the world outside of the input: it can't be used with --record or --replay".to_string());
+            }
+            Command::Play(options)
+        }
+        "generate" => {
+            let mut seed = None;
+            let mut size = None;
+            let mut world = None;
+            while let Some(arg) = parser.next() {
+                match arg {
+                    "--seed" => seed = Some(parser.seed()?),
+                    "--size" => size = Some(parser.size()?),
+                    _ => world = Some(parser.positional(arg)?),
+                }
+            }
+            Command::Generate { world: required(world, "world")?, seed, size }
+        }
+        "info" => Command::Info { world: parser.single("world")? },
+        "relight" => Command::Relight { world: parser.single("world")? },
+        "export" => {
+            let mut time = None;
+            let mut paths = Vec::new();
+            while let Some(arg) = parser.next() {
+                match arg {
+                    "--time" => time = Some(parser.number("--time")?),
+                    _ => paths.push(parser.positional(arg)?),
+                }
+            }
+            if paths.len() != 2 {
+                return Err("export expects <world> and <file> paths".to_string());
+            }
+            if time.is_some_and(|time: f32| !(0.0..=1.0).contains(&time)) {
+                return Err("--time must be in 0..1".to_string());
+            }
+            let output = paths.pop().unwrap();
+            let world = paths.pop().unwrap();
+            Command::Export { world, output, time }
+        }
+        "render" => {
+            let mut options = RenderOptions { width: 320, height: 180, tolerance: 2, ..Default::default() };
+            let mut paths = Vec::new();
+            while let Some(arg) = parser.next() {
+                match arg {
+                    "--resolution" => {
+                        let value = parser.value("--resolution")?;
+                        let (width, height) = value
+                            .split_once('x')
+                            .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                            .filter(|(w, h)| *w > 0 && *h > 0)
+                            .ok_or_else(|| format!("invalid resolution '{}'", value))?;
+                        (options.width, options.height) = (width, height);
+                    }
+                    "--eye" => options.eye = Some(parse_point(parser.value("--eye")?)?),
+                    "--target" => options.target = Some(parse_point(parser.value("--target")?)?),
+                    "--time" => options.time = Some(parser.number("--time")?),
+                    "--fov" => options.fov = Some(parser.number("--fov")?),
+                    "--compare" => options.compare = Some(parser.value("--compare")?.to_string()),
+                    "--tolerance" => options.tolerance = parser.number("--tolerance")?,
+                    _ => paths.push(parser.positional(arg)?),
+                }
+            }
+            if paths.len() != 2 {
+                return Err("render expects <world> and <file> paths".to_string());
+            }
+            if options.time.is_some_and(|time| !(0.0..=1.0).contains(&time)) {
+                return Err("--time must be in 0..1".to_string());
+            }
+            options.output = paths.pop().unwrap();
+            options.world = paths.pop().unwrap();
+            Command::Render(options)
+        }
+        "convert" => {
+            let mut size = None;
+            let mut paths = Vec::new();
+            while let Some(arg) = parser.next() {
+                match arg {
+                    "--size" => size = Some(parser.size()?),
+                    _ => paths.push(parser.positional(arg)?),
+                }
+            }
+            if paths.len() != 2 {
+                return Err("convert expects <old> and <new> paths".to_string());
+            }
+            let to = paths.pop().unwrap();
+            let from = paths.pop().unwrap();
+            Command::Convert { from, to, size }
+        }
+        "help" | "-h" => Command::Help,
+        other => return Err(format!("unknown command '{}'", other)),
+    };
+    Ok(Cli { command, config })
+}
+
+
+/// Разбор `4` или `4x2x4`
+pub fn parse_size(value: &str) -> Result<WorldSize, String> {
+    let parts = value
+        .split('x')
+        .map(|part| part.parse::<usize>().ok().filter(|&n| n > 0))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| format!("invalid size '{}'", value))?;
+    match parts[..] {
+        [n] => Ok(WorldSize { w: n, h: n, d: n }),
+        [w, h, d] => Ok(WorldSize { w, h, d }),
+        _ => Err(format!("invalid size '{}'", value)),
+    }
+}
+
+
+/// Разбор `x,y,z`
+fn parse_point(value: &str) -> Result<[f32; 3], String> {
+    let parts = value.split(',').map(|part| part.trim().parse::<f32>().ok()).collect::<Option<Vec<_>>>();
+    match parts.as_deref() {
+        Some(&[x, y, z]) => Ok([x, y, z]),
+        _ => Err(format!("invalid point '{}', expected x,y,z", value)),
+    }
+}
+
+
+fn required(value: Option<String>, name: &str) -> Result<String, String> {
+    value.ok_or_else(|| format!("missing <{}> argument", name))
+}
+
+
+struct Parser<'a> {
+    args: &'a [String],
+    index: usize,
+}
+
+
+impl<'a> Parser<'a> {
+    fn new(args: &'a [String]) -> Self {
+        Self { args, index: 0 }
+    }
+
+
+    fn next(&mut self) -> Option<&'a str> {
+        let arg = self.args.get(self.index)?;
+        self.index += 1;
+        Some(arg.as_str())
+    }
+
+
+    fn value(&mut self, flag: &str) -> Result<&'a str, String> {
+        self.next().ok_or_else(|| format!("{} expects a value", flag))
+    }
+
+
+    fn seed(&mut self) -> Result<u32, String> {
+        self.number("--seed")
+    }
+
+
+    fn number<T: FromStr>(&mut self, flag: &str) -> Result<T, String> {
+        let value = self.value(flag)?;
+        value.parse().map_err(|_| format!("invalid value '{}' for {}", value, flag))
+    }
+
+
+    fn size(&mut self) -> Result<WorldSize, String> {
+        parse_size(self.value("--size")?)
+    }
+
+
+    fn positional(&self, arg: &str) -> Result<String, String> {
+        if arg.starts_with("--") {
+            return Err(format!("unknown option '{}'", arg));
+        }
+        Ok(arg.to_string())
+    }
+
+
+    /// Ровно один позиционный аргумент и никаких опций
+    fn single(&mut self, name: &str) -> Result<String, String> {
+        let mut value = None;
+        while let Some(arg) = self.next() {
+            if value.is_some() {
+                return Err(format!("unexpected argument '{}'", arg));
+            }
+            value = Some(self.positional(arg)?);
+        }
+        required(value, name)
+    }
+}
@@ -0,0 +1,347 @@
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::cli::{parse_size, PlayOptions, WorldSize};
+use crate::files::write_binary_file;
+use crate::input::Bindings;
+use crate::player::HOTBAR_SIZE;
+use crate::settings::*;
+use crate::voxels::DEFAULT_SEED;
+
+
+/// Файл настроек в рабочей папке, если другой не указан через `--config`
+pub const CONFIG_PATH: &str = "settings.cfg";
+
+/// Пределы размера окна
+const MIN_WIDTH: u32 = 320;
+const MIN_HEIGHT: u32 = 240;
+const MAX_WINDOW_SIZE: u32 = 16384;
+
+
+// Откуда берётся значение, от слабого к сильному:
+// 1. значения по умолчанию из `Default`;
+// 2. файл настроек (`settings.cfg` или `--config <path>`);
+// 3. флаги командной строки (`--width`, `--seed` и т.д.).
+// Флаги действуют только на текущий запуск и в файл не сохраняются,
+// а изменения, сделанные в игре, записываются обратно в файл.
+//
+// Формат файла: секции `[graphics]`, `[input]`, `[bindings]`, `[world]`,
+// `[gameplay]`, строки `key = value`. Комментарий начинается с `#` в начале
+// строки или после пробела, так что `path = worlds/#1.bin` не обрезается.
+// В `[bindings]` ключ - имя действия, значение - привязки через запятую:
+// `move_forward = W, Up`, `exit = Escape, Ctrl+Q`.
+
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphicsConfig {
+    pub width: u32,
+    pub height: u32,
+    /// Угол обзора по вертикали в градусах
+    pub fov: f32,
+    /// Размер буфера вершин для мешей чанков, в float
+    pub renderer_capacity: usize,
+    /// Масштаб интерфейса; 0 - подбирается по высоте окна
+    pub ui_scale: u32,
+    /// Во сколько раз снимок в большом разрешении больше окна
+    pub screenshot_scale: u32,
+}
+
+
+impl GraphicsConfig {
+    /// Размер окна после изменения пользователем, в пределах файла настроек
+    #[cfg(feature = "graphics")]
+    pub fn set_window_size(&mut self, width: u32, height: u32) {
+        self.width = width.clamp(MIN_WIDTH, MAX_WINDOW_SIZE);
+        self.height = height.clamp(MIN_HEIGHT, MAX_WINDOW_SIZE);
+    }
+}
+
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct InputConfig {
+    /// Множитель поворота камеры мышью
+    pub mouse_sensitivity: f32,
+    /// Мёртвая зона стиков геймпада, доля полного отклонения
+    pub gamepad_dead_zone: f32,
+    /// Скорость поворота камеры правым стиком, радиан в секунду
+    pub gamepad_look_sensitivity: f32,
+    pub bindings: Bindings,
+}
+
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorldConfig {
+    pub path: String,
+    /// Размер и зерно нового мира, если файла ещё нет
+    pub size: WorldSize,
+    pub seed: u32,
+}
+
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameplayConfig {
+    pub walk_speed: f32,
+    pub fly_speed: f32,
+    /// Длина суток в секундах
+    pub day_length: f32,
+    /// Имена блоков в слотах панели, пустая строка - пустой слот
+    pub hotbar: Vec<String>,
+}
+
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub graphics: GraphicsConfig,
+    pub input: InputConfig,
+    pub world: WorldConfig,
+    pub gameplay: GameplayConfig,
+}
+
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            graphics: GraphicsConfig {
+                width: 1280,
+                height: 720,
+                fov: 70.0,
+                renderer_capacity: 1024 * 1024 * 8,
+                ui_scale: 0,
+                screenshot_scale: 4,
+            },
+            input: InputConfig {
+                mouse_sensitivity: 2.0,
+                gamepad_dead_zone: 0.2,
+                gamepad_look_sensitivity: 3.0,
+                bindings: Bindings::default(),
+            },
+            world: WorldConfig {
+                path: WORLD_PATH.to_string(),
+                size: WorldSize::default(),
+                seed: DEFAULT_SEED,
+            },
+            gameplay: GameplayConfig {
+                walk_speed: 5.0,
+                fly_speed: 15.0,
+                day_length: DAY_LENGTH,
+                hotbar: ["stone", "grass", "lamp", "glass", "planks", "red_glass", "leaves", "water", "lava"]
+                    .map(String::from)
+                    .to_vec(),
+            },
+        }
+    }
+}
+
+
+impl Config {
+    /// Читает настройки из файла. Отсутствующий файл по умолчанию не ошибка,
+    /// а отсутствующий файл, указанный явно, - ошибка
+    pub fn load(path: Option<&str>) -> Result<Self, String> {
+        let (path, explicit) = match path {
+            Some(path) => (path, true),
+            None => (CONFIG_PATH, false),
+        };
+        if !explicit && !Path::new(path).exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(path).map_err(|e| format!("can't read config '{}': {}", path, e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut config = Self::default();
+        let mut section = String::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| format!("line {}: {}", number + 1, message);
+
+            if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                section = name.trim().to_string();
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error(format!("expected `key = value`, got '{}'", line)))?;
+            config.set(&section, key.trim(), value.trim()).map_err(error)?;
+        }
+        Ok(config)
+    }
+
+
+    fn set(&mut self, section: &str, key: &str, value: &str) -> Result<(), String> {
+        match (section, key) {
+            ("graphics", "width") => self.graphics.width = ranged(key, value, MIN_WIDTH, MAX_WINDOW_SIZE)?,
+            ("graphics", "height") => self.graphics.height = ranged(key, value, MIN_HEIGHT, MAX_WINDOW_SIZE)?,
+            ("graphics", "fov") => self.graphics.fov = ranged(key, value, 30.0, 120.0)?,
+            ("graphics", "renderer_capacity") => {
+                self.graphics.renderer_capacity = ranged(key, value, 1024, usize::MAX)?
+            }
+            ("graphics", "ui_scale") => self.graphics.ui_scale = ranged(key, value, 0, 8)?,
+            ("graphics", "screenshot_scale") => self.graphics.screenshot_scale = ranged(key, value, 1, 8)?,
+            ("input", "mouse_sensitivity") => self.input.mouse_sensitivity = ranged(key, value, 0.01, 100.0)?,
+            ("input", "gamepad_dead_zone") => self.input.gamepad_dead_zone = ranged(key, value, 0.0, 0.9)?,
+            ("input", "gamepad_look_sensitivity") => {
+                self.input.gamepad_look_sensitivity = ranged(key, value, 0.01, 100.0)?
+            }
+            ("bindings", action) => self.input.bindings.set(action, value)?,
+            ("world", "path") => {
+                if value.is_empty() {
+                    return Err("path can't be empty".to_string());
+                }
+                self.world.path = value.to_string();
+            }
+            ("world", "size") => self.world.size = parse_size(value)?,
+            ("world", "seed") => self.world.seed = ranged(key, value, 0, u32::MAX)?,
+            ("gameplay", "walk_speed") => self.gameplay.walk_speed = ranged(key, value, 0.0, 1000.0)?,
+            ("gameplay", "fly_speed") => self.gameplay.fly_speed = ranged(key, value, 0.0, 1000.0)?,
+            ("gameplay", "day_length") => self.gameplay.day_length = ranged(key, value, 1.0, 86400.0)?,
+            ("gameplay", "hotbar") => {
+                let names: Vec<String> = value.split(',').map(|name| name.trim().to_string()).collect();
+                if names.len() > HOTBAR_SIZE {
+                    return Err(format!("hotbar has {} slots, got {} blocks", HOTBAR_SIZE, names.len()));
+                }
+                self.gameplay.hotbar = names;
+            }
+            ("", _) => return Err(format!("'{}' is outside of a section", key)),
+            _ => return Err(format!("unknown setting '{}' in [{}]", key, section)),
+        }
+        Ok(())
+    }
+
+
+    /// Флаги командной строки поверх файла; проверяются так же, как значения из файла
+    pub fn apply(&mut self, options: &PlayOptions) -> Result<(), String> {
+        if let Some(path) = &options.world {
+            self.set("world", "path", path)?;
+        }
+        if let Some(size) = options.size {
+            self.world.size = size;
+        }
+        if let Some(seed) = options.seed {
+            self.world.seed = seed;
+        }
+        if let Some(width) = options.width {
+            self.set("graphics", "width", &width.to_string())?;
+        }
+        if let Some(height) = options.height {
+            self.set("graphics", "height", &height.to_string())?;
+        }
+        if let Some(fov) = options.fov {
+            self.set("graphics", "fov", &fov.to_string())?;
+        }
+        Ok(())
+    }
+
+
+    pub fn to_text(&self) -> String {
+        let size = self.world.size;
+        format!(
+            "# Настройки движка; флаги командной строки важнее значений отсюда\n\
+             \n\
+             [graphics]\n\
+             width = {}\n\
+             height = {}\n\
+             fov = {}\n\
+             renderer_capacity = {}\n\
+             ui_scale = {}\n\
+             screenshot_scale = {}\n\
+             \n\
+             [input]\n\
+             mouse_sensitivity = {}\n\
+             gamepad_dead_zone = {}\n\
+             gamepad_look_sensitivity = {}\n\
+             \n\
+             [bindings]\n\
+             {}\
+             \n\
+             [world]\n\
+             path = {}\n\
+             size = {}x{}x{}\n\
+             seed = {}\n\
+             \n\
+             [gameplay]\n\
+             walk_speed = {}\n\
+             fly_speed = {}\n\
+             day_length = {}\n\
+             hotbar = {}\n",
+            self.graphics.width, self.graphics.height, self.graphics.fov, self.graphics.renderer_capacity,
+            self.graphics.ui_scale, self.graphics.screenshot_scale,
+            self.input.mouse_sensitivity,
+            self.input.gamepad_dead_zone,
+            self.input.gamepad_look_sensitivity,
+            self.input.bindings.to_text(),
+            self.world.path, size.w, size.h, size.d, self.world.seed,
+            self.gameplay.walk_speed, self.gameplay.fly_speed, self.gameplay.day_length,
+            self.gameplay.hotbar.join(", "),
+        )
+    }
+
+
+    pub fn save(&self, path: Option<&str>) -> std::io::Result<()> {
+        write_binary_file(path.unwrap_or(CONFIG_PATH), self.to_text().as_bytes())
+    }
+}
+
+
+/// Строка без комментария
+fn strip_comment(line: &str) -> &str {
+    let start = line
+        .char_indices()
+        .find(|&(i, c)| c == '#' && line[..i].chars().next_back().is_none_or(char::is_whitespace));
+    match start {
+        Some((i, _)) => &line[..i],
+        None => line,
+    }
+}
+
+
+/// Число в допустимых пределах; NaN в них не попадает
+fn ranged<T: FromStr + PartialOrd + Display>(key: &str, value: &str, min: T, max: T) -> Result<T, String> {
+    let parsed: T = value.parse().map_err(|_| format!("invalid value '{}' for {}", value, key))?;
+    if !(parsed >= min && parsed <= max) {
+        return Err(format!("{} must be in {}..={}, got {}", key, min, max, value));
+    }
+    Ok(parsed)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_inside_value_is_kept() {
+        let config = Config::parse("[world]\npath = worlds/#1.bin # comment\n").unwrap();
+        assert_eq!(config.world.path, "worlds/#1.bin");
+    }
+
+    #[test]
+    fn comments_start_at_line_start_or_after_space() {
+        assert_eq!(strip_comment("# whole line"), "");
+        assert_eq!(strip_comment("fov = 70 # note"), "fov = 70 ");
+        assert_eq!(strip_comment("fov = 70\t#note"), "fov = 70\t");
+        assert_eq!(strip_comment("path = a#b"), "path = a#b");
+    }
+
+    #[test]
+    fn out_of_range_and_nan_are_rejected() {
+        assert_eq!(ranged("fov", "90", 30.0, 120.0), Ok(90.0));
+        assert!(ranged("fov", "150", 30.0, 120.0).is_err());
+        assert!(ranged("fov", "NaN", 30.0, 120.0).is_err());
+        assert!(Config::parse("[input]\nmouse_sensitivity = nan\n").is_err());
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let mut config = Config::default();
+        config.world.path = "worlds/#2.bin".to_string();
+        assert_eq!(Config::parse(&config.to_text()).unwrap(), config);
+    }
+}
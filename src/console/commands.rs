@@ -0,0 +1,416 @@
+use glam::Vec3;
+
+use crate::edit::{schematic, vox, Clipboard, Region, Schematic, VoxPalette, MAX_VOLUME};
+use crate::export::{self, ExportMesh};
+use crate::files::write_binary_file;
+use crate::fluids;
+use crate::settings::VOX_PALETTE_PATH;
+use crate::ticks::Behaviour;
+use crate::voxels::{Voxel, CHUNK_D, CHUNK_H, CHUNK_W};
+use crate::world::TickInput;
+use super::{Args, CommandRegistry, Context};
+
+
+/// Больше шагов симуляции за одну команду `tick` не делаем
+const MAX_TICKS: u32 = 6000;
+
+
+pub fn register(registry: &mut CommandRegistry) {
+    registry.register("tp", "<x> <y> <z>", "move the player's eyes to a point inside the world, ~ is relative", tp);
+    registry.register("setblock", "<x> <y> <z> <block>", "place a block by name or id", setblock);
+    registry.register("getblock", "[x y z]", "print a block and its state, the targeted block by default", getblock);
+    registry.register("fill", "<x1> <y1> <z1> <x2> <y2> <z2> <block>", "fill a box with a block", fill);
+    registry.register("pos1", "[x y z]", "set the first selection corner, the targeted block by default", pos1);
+    registry.register("pos2", "[x y z]", "set the second selection corner, the targeted block by default", pos2);
+    registry.register("sel", "", "print the selection", sel);
+    registry.register("set", "<block>", "fill the selection with a block", set);
+    registry.register("replace", "<from> <to>", "replace one block with another in the selection", replace);
+    registry.register("hollow", "<block>", "make the selection a hollow box of a block", hollow);
+    registry.register("copy", "", "copy the selection to the clipboard", copy);
+    registry.register("rotate", "[90|180|270]", "rotate the clipboard clockwise around the vertical axis", rotate);
+    registry.register("paste", "[x y z]", "paste the clipboard with its lowest corner at a point, in front of the target by default", paste);
+    registry.register("export", "<file>", "save the selection as a schematic", export);
+    registry.register("import", "<file> [x y z]", "place a schematic with its lowest corner at a point, in front of the target by default", import);
+    registry.register("voxexport", "<file>", "save the selection as a MagicaVoxel model", vox_export);
+    registry.register("voximport", "<file> [x y z]", "place a MagicaVoxel model with its lowest corner at a point, in front of the target by default", vox_import);
+    registry.register("meshexport", "<file.obj|file.gltf>", "save the meshed selection, or the whole world without one, with the atlas texture", mesh_export);
+    registry.register("undo", "", "revert the last edit", undo);
+    registry.register("redo", "", "repeat the last reverted edit", redo);
+    registry.register("relight", "", "recompute lighting of the whole world", relight);
+    registry.register("save", "[path]", "save the world", save);
+    registry.register("seed", "", "print the world seed", seed);
+    registry.register("time", "[0..1]", "print or set the time of day, 0.5 is noon", time);
+    registry.register("tick", "[count]", "run simulation steps without player input, 1 by default", tick);
+}
+
+
+fn tp(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    let eye = args.position(context.world.player.eye_position())?;
+    args.finish()?;
+    // Дальше границ мира не уйти: за ними нет ни чанков, ни пола
+    let chunks = &context.world.chunks;
+    let size = Vec3::new((chunks.w * CHUNK_W) as f32, (chunks.h * CHUNK_H) as f32, (chunks.d * CHUNK_D) as f32);
+    let eye = eye.clamp(Vec3::ZERO, size);
+    context.world.teleport(eye);
+    Ok(format!("teleported to {:.2} {:.2} {:.2}", eye.x, eye.y, eye.z))
+}
+
+
+/// Позиция блока, в котором находится игрок: от неё считаются `~`
+fn origin(context: &Context) -> Vec3 {
+    context.world.player.eye_position().floor()
+}
+
+
+/// Ошибка, если блок вне мира
+fn check_inside(context: &Context, [x, y, z]: [isize; 3]) -> Result<(), String> {
+    match context.world.chunks.get_voxel(x, y, z) {
+        Some(_) => Ok(()),
+        None => Err(format!("{} {} {} is outside of the world", x, y, z)),
+    }
+}
+
+
+fn setblock(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    let position = args.block_position(origin(context))?;
+    let id = args.block(context.blocks)?;
+    args.finish()?;
+    check_inside(context, position)?;
+    context.editor.apply(context.world, context.blocks, [(position, Voxel::new(id))]);
+    let [x, y, z] = position;
+    let name = context.blocks.get(id).map_or("?", |block| block.name.as_str());
+    Ok(format!("set {} {} {} to {}", x, y, z, name))
+}
+
+
+fn getblock(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    let [x, y, z] = point(context, args, false)?;
+    let voxel = *context.world.chunks.get_voxel(x, y, z).ok_or("outside of the world")?;
+    let block = context.blocks.get(voxel.id);
+    let name = block.map_or("?", |block| block.name.as_str());
+    let state = match block.and_then(|block| block.fluid) {
+        Some(_) if fluids::is_source(voxel.states) => " source".to_string(),
+        Some(_) if fluids::is_falling(voxel.states) => " falling".to_string(),
+        Some(_) => format!(" level {}", fluids::level(voxel.states)),
+        None if matches!(block.and_then(|block| block.behaviour), Some(Behaviour::Grows { .. })) => format!(" stage {}", voxel.states),
+        None if voxel.states != 0 => format!(" state {}", voxel.states),
+        None => String::new(),
+    };
+    Ok(format!("{} {} {} is {}{}", x, y, z, name, state))
+}
+
+
+fn fill(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    let from = args.block_position(origin(context))?;
+    let to = args.block_position(origin(context))?;
+    let id = args.block(context.blocks)?;
+    args.finish()?;
+    check_inside(context, from)?;
+    check_inside(context, to)?;
+    let region = checked(Region::new(from, to))?;
+    let count = context.editor.fill(context.world, context.blocks, region, id);
+    Ok(changed(count))
+}
+
+
+fn relight(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    args.finish()?;
+    context.world.relight(context.blocks);
+    Ok("lighting recomputed".to_string())
+}
+
+
+fn save(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    let path = args.optional().unwrap_or(context.world_path);
+    args.finish()?;
+    context.world.save(path).map_err(|e| format!("can't save '{}': {}", path, e))?;
+    Ok(format!("world saved on {}", path))
+}
+
+
+fn seed(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    args.finish()?;
+    Ok(context.world.chunks.seed.to_string())
+}
+
+
+fn time(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    // Без аргумента время только печатается
+    if args.finish().is_ok() {
+        return Ok(format!("{:.3}", context.world.time.time_of_day()));
+    }
+    let time: f32 = args.number("time")?;
+    args.finish()?;
+    if !(0.0..=1.0).contains(&time) {
+        return Err("time must be in 0..1".to_string());
+    }
+    context.world.time.set_time_of_day(time);
+    Ok(format!("time set to {:.3}", time))
+}
+
+
+fn tick(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    let count = match args.finish() {
+        Ok(()) => 1,
+        Err(_) => args.number::<u32>("count")?,
+    };
+    args.finish()?;
+    if count > MAX_TICKS {
+        return Err(format!("{} ticks is too many, the limit is {}", count, MAX_TICKS));
+    }
+    for _ in 0..count {
+        context.world.update(&TickInput::default(), context.blocks);
+    }
+    let world = &context.world;
+    Ok(format!(
+        "tick {}, {} fluid and {} block updates pending",
+        world.tick,
+        world.fluids.pending(),
+        world.ticks.pending()
+    ))
+}
+
+
+/// Ошибка, если правка слишком большая
+fn checked(region: Region) -> Result<Region, String> {
+    match region.volume() {
+        volume if volume > MAX_VOLUME => Err(format!("{} blocks is too many, the limit is {}", volume, MAX_VOLUME)),
+        _ => Ok(region),
+    }
+}
+
+
+fn selection(context: &Context) -> Result<Region, String> {
+    checked(context.editor.selection().ok_or("select two corners with pos1 and pos2 first")?)
+}
+
+
+fn changed(count: usize) -> String {
+    format!("{} blocks changed", count)
+}
+
+
+/// Точка из аргументов или, без них, клетка под прицелом
+fn point(context: &Context, args: &mut Args, front: bool) -> Result<[isize; 3], String> {
+    if args.finish().is_ok() {
+        let target = context.world.target(context.look).ok_or("no block targeted, give coordinates")?;
+        return Ok(if front { target.front } else { target.block });
+    }
+    let position = args.block_position(origin(context))?;
+    args.finish()?;
+    check_inside(context, position)?;
+    Ok(position)
+}
+
+
+fn select(context: &mut Context, args: &mut Args, corner: usize) -> Result<String, String> {
+    let [x, y, z] = point(context, args, false)?;
+    context.editor.select(corner, [x, y, z]);
+    let size = context.editor.selection().map_or(String::new(), |region| format!(", {} blocks selected", region.volume()));
+    Ok(format!("corner {} at {} {} {}{}", corner + 1, x, y, z, size))
+}
+
+
+fn pos1(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    select(context, args, 0)
+}
+
+
+fn pos2(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    select(context, args, 1)
+}
+
+
+fn sel(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    args.finish()?;
+    let Some(region) = context.editor.selection() else {
+        return Ok("nothing selected".to_string());
+    };
+    let ([x1, y1, z1], [x2, y2, z2]) = (region.min, region.max);
+    let [w, h, d] = region.size();
+    Ok(format!("{} {} {} - {} {} {}: {}x{}x{}, {} blocks", x1, y1, z1, x2, y2, z2, w, h, d, region.volume()))
+}
+
+
+fn set(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    let id = args.block(context.blocks)?;
+    args.finish()?;
+    let region = selection(context)?;
+    Ok(changed(context.editor.fill(context.world, context.blocks, region, id)))
+}
+
+
+fn replace(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    let from = args.block(context.blocks)?;
+    let to = args.block(context.blocks)?;
+    args.finish()?;
+    let region = selection(context)?;
+    Ok(changed(context.editor.replace(context.world, context.blocks, region, from, to)))
+}
+
+
+fn hollow(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    let id = args.block(context.blocks)?;
+    args.finish()?;
+    let region = selection(context)?;
+    Ok(changed(context.editor.hollow(context.world, context.blocks, region, id)))
+}
+
+
+fn copy(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    args.finish()?;
+    let region = selection(context)?;
+    context.editor.copy(context.world, region);
+    Ok(format!("{} blocks copied", region.volume()))
+}
+
+
+fn rotate(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    let degrees = match args.finish() {
+        Ok(()) => 90,
+        Err(_) => args.number::<u32>("degrees")?,
+    };
+    args.finish()?;
+    if degrees % 90 != 0 {
+        return Err("rotation must be a multiple of 90 degrees".to_string());
+    }
+    let clipboard = context.editor.clipboard.as_mut().ok_or("the clipboard is empty")?;
+    for _ in 0..degrees / 90 % 4 {
+        *clipboard = clipboard.rotated();
+    }
+    let [w, h, d] = clipboard.size;
+    Ok(format!("clipboard rotated by {}, now {}x{}x{}", degrees, w, h, d))
+}
+
+
+fn paste(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    let origin = point(context, args, true)?;
+    let count = context.editor.paste(context.world, context.blocks, origin).ok_or("the clipboard is empty")?;
+    Ok(changed(count))
+}
+
+
+fn export(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    let path = args.value("file")?;
+    args.finish()?;
+    let region = selection(context)?;
+    let voxel_at = |[x, y, z]: [isize; 3]| context.world.chunks.get_voxel(x, y, z).copied();
+    let schematic = Schematic::from_clipboard(&Clipboard::copy(region, voxel_at), context.blocks)?;
+    schematic::save(path, &schematic).map_err(|e| format!("can't save '{}': {}", path, e))?;
+    let [w, h, d] = schematic.size;
+    Ok(format!("{}x{}x{} schematic saved on {}", w, h, d, path))
+}
+
+
+fn import(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    let path = args.value("file")?;
+    let origin = point(context, args, true)?;
+    let schematic = schematic::load(path).map_err(|e| format!("can't load '{}': {}", path, e))?;
+    let clipboard = schematic.to_clipboard(context.blocks).map_err(|e| format!("{}: {}", path, e))?;
+    checked(Region::new([0; 3], clipboard.size.map(|side| side as isize - 1)))?;
+    Ok(changed(context.editor.apply(context.world, context.blocks, clipboard.blocks_at(origin))))
+}
+
+
+fn vox_export(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    let path = args.value("file")?;
+    args.finish()?;
+    let region = selection(context)?;
+    let palette = VoxPalette::load(VOX_PALETTE_PATH)?;
+    let voxel_at = |[x, y, z]: [isize; 3]| context.world.chunks.get_voxel(x, y, z).copied();
+    let model = palette.model_of(&Clipboard::copy(region, voxel_at), context.blocks)?;
+    let data = vox::write(&model)?;
+    write_binary_file(path, &data).map_err(|e| format!("can't save '{}': {}", path, e))?;
+    Ok(format!("{} voxels saved on {}", model.voxels.len(), path))
+}
+
+
+fn vox_import(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    let path = args.value("file")?;
+    let [x, y, z] = point(context, args, true)?;
+    let data = std::fs::read(path).map_err(|e| format!("can't load '{}': {}", path, e))?;
+    let model = vox::read(&data).map_err(|e| format!("{}: {}", path, e))?;
+    checked(Region::new([0; 3], model.size.map(|side| side as isize - 1)))?;
+    let palette = VoxPalette::load(VOX_PALETTE_PATH)?;
+    let placed = palette.blocks_of(&model, context.blocks).map_err(|e| format!("{}: {}", path, e))?;
+    let blocks_to_set = placed
+        .into_iter()
+        .map(|([dx, dy, dz], id)| ([x + dx as isize, y + dy as isize, z + dz as isize], Voxel::new(id)));
+    Ok(changed(context.editor.apply(context.world, context.blocks, blocks_to_set)))
+}
+
+
+fn mesh_export(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    let path = args.value("file")?;
+    args.finish()?;
+    let region = context.editor.selection();
+    let sky = context.world.time.sky_light();
+    let mesh = ExportMesh::build(&context.world.chunks, context.blocks, context.atlas, region, sky);
+    let written = export::export(path, &mesh, context.atlas)?;
+    Ok(format!("{} triangles saved on {}", mesh.indices.len() / 3, written.join(", ")))
+}
+
+
+fn undo(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    args.finish()?;
+    let count = context.editor.undo(context.world, context.blocks).ok_or("nothing to undo")?;
+    Ok(format!("undone, {} blocks changed", count))
+}
+
+
+fn redo(context: &mut Context, args: &mut Args) -> Result<String, String> {
+    args.finish()?;
+    let count = context.editor.redo(context.world, context.blocks).ok_or("nothing to redo")?;
+    Ok(format!("redone, {} blocks changed", count))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::AtlasBuilder;
+    use crate::edit::Editor;
+    use crate::player::Player;
+    use crate::voxels::{Block, BlockRegistry, Chunks};
+    use crate::world::{World, WorldTime};
+
+    fn run(script: &str, world: &mut World) -> Result<Vec<String>, String> {
+        let mut blocks = BlockRegistry::new();
+        blocks.blocks[0] = Some(Block::new(0, "air", 0));
+        let atlas = AtlasBuilder::new(0).build();
+        let mut editor = Editor::default();
+        let mut context = Context {
+            world,
+            blocks: &blocks,
+            atlas: &atlas,
+            world_path: "world.bin",
+            editor: &mut editor,
+            look: Vec3::NEG_Y,
+        };
+        CommandRegistry::default().run_script(script, &mut context)
+    }
+
+    fn world() -> World {
+        World::new(Chunks::filled(1, 1, 1, 0), WorldTime::new(600.0), Player::new(Vec3::splat(8.0)))
+    }
+
+    #[test]
+    fn tick_count_is_limited() {
+        let mut world = world();
+        assert!(run(&format!("tick {}", MAX_TICKS + 1), &mut world).unwrap_err().contains("too many"));
+        assert_eq!(world.tick, 0);
+        run("tick 3", &mut world).unwrap();
+        assert_eq!(world.tick, 3);
+    }
+
+    #[test]
+    fn tp_rejects_non_finite_coordinates() {
+        let mut world = world();
+        for script in ["tp nan 4 4", "tp 4 inf 4", "tp 4 4 ~1e39"] {
+            assert!(run(script, &mut world).is_err(), "{}", script);
+        }
+        assert_eq!(world.player.eye_position(), Vec3::splat(8.0));
+        run("tp 100 ~ -5", &mut world).unwrap();
+        assert_eq!(world.player.eye_position(), Vec3::new(16.0, 8.0, 0.0));
+    }
+}
@@ -0,0 +1,307 @@
+use std::str::FromStr;
+
+use glam::Vec3;
+
+use crate::assets::Atlas;
+use crate::edit::Editor;
+use crate::voxels::BlockRegistry;
+use crate::world::World;
+
+mod commands;
+
+
+/// Сколько строк вывода хранит консоль
+const OUTPUT_LINES: usize = 100;
+
+
+/// То, над чем работают команды. Окно не нужно, поэтому команды
+/// выполняются и из игры, и из скриптов без окна
+pub struct Context<'a> {
+    pub world: &'a mut World,
+    pub blocks: &'a BlockRegistry,
+    /// Для экспорта мешей
+    pub atlas: &'a Atlas,
+    /// Куда сохраняет `save` без аргумента
+    pub world_path: &'a str,
+    pub editor: &'a mut Editor,
+    /// Направление взгляда: команды без координат берут блок под прицелом
+    pub look: Vec3,
+}
+
+
+/// Команда возвращает текст для вывода или сообщение об ошибке.
+/// Все аргументы разбираются и `Args::finish` проверяется до того, как мир меняется
+pub type Handler = fn(&mut Context, &mut Args) -> Result<String, String>;
+
+
+pub struct Command {
+    pub name: &'static str,
+    /// Аргументы для подсказки: `<x> <y> <z>`
+    pub usage: &'static str,
+    pub help: &'static str,
+    handler: Handler,
+}
+
+
+/// Аргументы команды, разбираемые по порядку
+pub struct Args<'a> {
+    values: Vec<&'a str>,
+    next: usize,
+}
+
+
+impl<'a> Args<'a> {
+    pub fn new(values: Vec<&'a str>) -> Self {
+        Self { values, next: 0 }
+    }
+
+
+    pub fn optional(&mut self) -> Option<&'a str> {
+        let value = self.values.get(self.next).copied();
+        self.next += value.is_some() as usize;
+        value
+    }
+
+
+    pub fn value(&mut self, name: &str) -> Result<&'a str, String> {
+        self.optional().ok_or_else(|| format!("missing <{}>", name))
+    }
+
+
+    pub fn number<T: FromStr>(&mut self, name: &str) -> Result<T, String> {
+        let value = self.value(name)?;
+        value.parse().map_err(|_| format!("invalid <{}> '{}'", name, value))
+    }
+
+
+    /// Координата; `~` и `~5` отсчитываются от `origin`. `nan` и
+    /// бесконечности, в том числе после сложения, не принимаются
+    pub fn coordinate(&mut self, name: &str, origin: f32) -> Result<f32, String> {
+        let value = self.value(name)?;
+        let parsed = match value.strip_prefix('~') {
+            Some("") => Ok(origin),
+            Some(offset) => offset.parse::<f32>().map(|offset| origin + offset),
+            None => value.parse::<f32>(),
+        };
+        parsed
+            .ok()
+            .filter(|coordinate| coordinate.is_finite())
+            .ok_or_else(|| format!("invalid <{}> '{}'", name, value))
+    }
+
+
+    /// Три координаты точки
+    pub fn position(&mut self, origin: Vec3) -> Result<Vec3, String> {
+        Ok(Vec3::new(
+            self.coordinate("x", origin.x)?,
+            self.coordinate("y", origin.y)?,
+            self.coordinate("z", origin.z)?,
+        ))
+    }
+
+
+    /// Три координаты блока, дробные округляются вниз
+    pub fn block_position(&mut self, origin: Vec3) -> Result<[isize; 3], String> {
+        let position = self.position(origin)?.floor();
+        Ok([position.x as isize, position.y as isize, position.z as isize])
+    }
+
+
+    /// Блок по имени или числовому id
+    pub fn block(&mut self, blocks: &BlockRegistry) -> Result<u8, String> {
+        let value = self.value("block")?;
+        let id = match value.parse::<u8>() {
+            Ok(id) => blocks.get(id).map(|_| id),
+            Err(_) => blocks.find(value),
+        };
+        id.ok_or_else(|| format!("unknown block '{}'", value))
+    }
+
+
+    /// Ошибка, если остались лишние аргументы
+    pub fn finish(&self) -> Result<(), String> {
+        match self.values.get(self.next) {
+            Some(extra) => Err(format!("unexpected argument '{}'", extra)),
+            None => Ok(()),
+        }
+    }
+}
+
+
+/// Набор команд по именам
+pub struct CommandRegistry {
+    commands: Vec<Command>,
+}
+
+
+impl Default for CommandRegistry {
+    /// Встроенные команды
+    fn default() -> Self {
+        let mut registry = Self { commands: Vec::new() };
+        commands::register(&mut registry);
+        registry
+    }
+}
+
+
+impl CommandRegistry {
+    /// Добавляет команду; команда с тем же именем заменяется
+    pub fn register(&mut self, name: &'static str, usage: &'static str, help: &'static str, handler: Handler) {
+        self.commands.retain(|command| command.name != name);
+        self.commands.push(Command { name, usage, help, handler });
+    }
+
+
+    pub fn get(&self, name: &str) -> Option<&Command> {
+        self.commands.iter().find(|command| command.name == name)
+    }
+
+
+    pub fn commands(&self) -> impl Iterator<Item = &Command> {
+        self.commands.iter()
+    }
+
+
+    /// Выполняет строку `команда аргументы...`
+    pub fn execute(&self, line: &str, context: &mut Context) -> Result<String, String> {
+        let mut words = line.split_whitespace();
+        let Some(name) = words.next() else {
+            return Ok(String::new());
+        };
+        if name == "help" {
+            return Ok(self.help(words.next()));
+        }
+        let command = self.get(name).ok_or_else(|| format!("unknown command '{}', try 'help'", name))?;
+        let mut args = Args::new(words.collect());
+        (command.handler)(context, &mut args).map_err(|e| format!("{}\nusage: {} {}", e, command.name, command.usage))
+    }
+
+
+    /// Список команд или описание одной
+    pub fn help(&self, name: Option<&str>) -> String {
+        let lines: Vec<String> = self
+            .commands()
+            .filter(|command| name.is_none_or(|name| command.name == name))
+            .map(|command| format!("{} {} - {}", command.name, command.usage, command.help))
+            .collect();
+        match (lines.is_empty(), name) {
+            (true, Some(name)) => format!("unknown command '{}'", name),
+            _ => lines.join("\n"),
+        }
+    }
+
+
+    /// Выполняет команды по строке; пустые строки и `#` комментарии пропускаются.
+    /// Останавливается на первой ошибке, указывая номер строки
+    pub fn run_script(&self, script: &str, context: &mut Context) -> Result<Vec<String>, String> {
+        let mut output = Vec::new();
+        for (number, line) in script.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let result = self.execute(line, context).map_err(|e| format!("line {}: {}", number + 1, e))?;
+            output.extend(result.lines().map(String::from));
+        }
+        Ok(output)
+    }
+}
+
+
+/// Строка ввода, история и вывод игровой консоли
+pub struct Console {
+    pub open: bool,
+    pub input: String,
+    pub output: Vec<String>,
+    history: Vec<String>,
+    /// Какая строка истории сейчас во вводе; `None` - новая строка
+    browsing: Option<usize>,
+    pub registry: CommandRegistry,
+}
+
+
+impl Console {
+    pub fn new(registry: CommandRegistry) -> Self {
+        Self {
+            open: false,
+            input: String::new(),
+            output: Vec::new(),
+            history: Vec::new(),
+            browsing: None,
+            registry,
+        }
+    }
+
+
+    pub fn print(&mut self, text: &str) {
+        self.output.extend(text.lines().map(String::from));
+        let excess = self.output.len().saturating_sub(OUTPUT_LINES);
+        self.output.drain(..excess);
+    }
+
+
+    pub fn type_text(&mut self, text: &str) {
+        self.input.extend(text.chars().filter(|c| !c.is_control()));
+    }
+
+
+    pub fn backspace(&mut self) {
+        self.input.pop();
+    }
+
+
+    /// Выполняет введённую строку и запоминает её в истории
+    pub fn submit(&mut self, context: &mut Context) {
+        let line = std::mem::take(&mut self.input);
+        self.browsing = None;
+        if line.trim().is_empty() {
+            return;
+        }
+        self.print(&format!("> {}", line));
+        match self.registry.execute(&line, context) {
+            Ok(output) => self.print(&output),
+            Err(e) => self.print(&format!("error: {}", e)),
+        }
+        if self.history.last() != Some(&line) {
+            self.history.push(line);
+        }
+    }
+
+
+    /// Листает историю: `back` - к более старым строкам
+    pub fn browse(&mut self, back: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+        let last = self.history.len() - 1;
+        self.browsing = match (self.browsing, back) {
+            (None, true) => Some(last),
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index < last => Some(index + 1),
+            (_, false) => None,
+        };
+        self.input = self.browsing.map_or_else(String::new, |index| self.history[index].clone());
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coordinates() {
+        let mut args = Args::new(vec!["1.5", "~", "~-2"]);
+        assert_eq!(args.position(Vec3::new(0.0, 3.0, 4.0)), Ok(Vec3::new(1.5, 3.0, 2.0)));
+        for value in ["nan", "inf", "-infinity", "1e39", "~3e38", "x"] {
+            assert!(Args::new(vec![value]).coordinate("x", 3e38).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn positions_are_finite() {
+        assert!(Args::new(vec!["1", "nan", "2"]).position(Vec3::ZERO).is_err());
+        assert!(Args::new(vec!["~", "~", "~"]).position(Vec3::NAN).is_err());
+        assert!(Args::new(vec!["~", "1", "~2"]).position(Vec3::new(f32::INFINITY, 0.0, 0.0)).is_err());
+    }
+}
@@ -0,0 +1,426 @@
+use glam::{Quat, Vec2};
+
+use crate::assets::Atlas;
+use crate::config::Config;
+use crate::console::{CommandRegistry, Console, Context};
+use crate::edit::Editor;
+use crate::input::{Action, SLOT_COUNT};
+use crate::input::gamepad::{AXIS_LEFT_X, AXIS_LEFT_Y, AXIS_RIGHT_X, AXIS_RIGHT_Y};
+use crate::input::keys::{BACKSPACE, DOWN, ENTER, MOUSE_LEFT, UP};
+use crate::player::Hotbar;
+use crate::ui::{hotbar_slots, inventory_blocks, inventory_cells, Screen};
+use crate::voxels::BlockRegistry;
+use crate::window::{Camera, Events};
+use crate::world::{TickInput, World, TICK_DELTA};
+
+
+/// Больше этого времени за кадр не симулируем, чтобы не уйти в догонялки
+const MAX_FRAME_TIME: f32 = 0.25;
+
+
+/// Что кадр просит сделать с окном
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameResult {
+    pub exit: bool,
+    pub cursor_toggled: bool,
+    /// В слоты панели положили другие блоки
+    pub hotbar_changed: bool,
+    /// Снять кадр в это число раз больше окна; 1 - как на экране
+    pub screenshot: Option<u32>,
+}
+
+
+/// Превращает ввод кадра в поворот камеры и шаги симуляции.
+/// Не зависит от окна, поэтому один и тот же ввод даёт один и тот же мир
+/// и в игре, и при воспроизведении записи без окна
+pub struct Controls {
+    cam_x: f32,
+    cam_y: f32,
+    pub hotbar: Hotbar,
+    pub inventory_open: bool,
+    pub debug_overlay: bool,
+    pub console: Console,
+    pub editor: Editor,
+    /// Был ли захвачен курсор до открытия инвентаря или консоли
+    cursor_was_locked: bool,
+    pending: TickInput,
+    accumulator: f32,
+    view_width: f32,
+    /// Высота окна, к которой нормируется движение мыши
+    view_height: f32,
+}
+
+
+impl Controls {
+    pub fn new(view_width: u32, view_height: u32, hotbar: Hotbar) -> Self {
+        Self {
+            cam_x: 0.0,
+            cam_y: 0.0,
+            hotbar,
+            inventory_open: false,
+            debug_overlay: false,
+            console: Console::new(CommandRegistry::default()),
+            editor: Editor::default(),
+            cursor_was_locked: false,
+            pending: TickInput::default(),
+            accumulator: 0.0,
+            view_width: view_width as f32,
+            view_height: view_height as f32,
+        }
+    }
+
+
+    /// Один кадр длительностью `delta` секунд по вводу, накопленному в `events`
+    #[allow(clippy::too_many_arguments)]
+    pub fn frame(
+        &mut self,
+        delta: f32,
+        events: &mut Events,
+        config: &Config,
+        camera: &mut Camera,
+        world: &mut World,
+        blocks: &BlockRegistry,
+        atlas: &Atlas,
+    ) -> FrameResult {
+        let delta = delta.min(MAX_FRAME_TIME);
+        self.accumulator += delta;
+        if let Some((width, height)) = events.resized {
+            if width > 0 && height > 0 {
+                self.view_width = width as f32;
+                self.view_height = height as f32;
+            }
+        }
+
+        let mut result = FrameResult::default();
+        let bindings = &config.input.bindings;
+
+        // Escape сначала закрывает консоль или инвентарь и только потом игру
+        let exit = bindings.jpressed(events, Action::Exit);
+        let console_key = bindings.jpressed(events, Action::Console);
+        if self.console.open {
+            if exit || console_key {
+                self.console.open = false;
+                self.restore_cursor(events);
+                result.cursor_toggled = true;
+            } else {
+                self.type_console(events, config, camera, world, blocks, atlas);
+            }
+        } else if console_key && !self.inventory_open {
+            // Символ клавиши открытия приходит в этом же кадре и во ввод не попадает
+            self.console.open = true;
+            self.release_cursor(events);
+            result.cursor_toggled = true;
+        } else if bindings.jpressed(events, Action::Inventory) || (exit && self.inventory_open) {
+            self.toggle_inventory(events);
+            result.cursor_toggled = true;
+        } else {
+            result.exit = exit;
+        }
+        // Пока консоль открыта, клавиши набирают текст, а не управляют игрой
+        let typing = self.console.open;
+
+        if bindings.jpressed(events, Action::DebugOverlay) {
+            self.debug_overlay = !self.debug_overlay;
+        }
+        // Shift+F2 подходит и под привязку F2, поэтому большой снимок проверяется первым
+        if bindings.jpressed(events, Action::HighResScreenshot) {
+            result.screenshot = Some(config.graphics.screenshot_scale);
+        } else if bindings.jpressed(events, Action::Screenshot) {
+            result.screenshot = Some(1);
+        }
+
+        if bindings.jpressed(events, Action::ToggleCursor) && !self.inventory_open && !typing {
+            events.cursor_locked = !events.cursor_locked;
+            result.cursor_toggled = true;
+        }
+
+        if !typing {
+            self.read_slots(events, config);
+        }
+        if self.inventory_open && events.jclicked(MOUSE_LEFT) {
+            let screen = Screen::new(self.view_width, self.view_height, config.graphics.ui_scale);
+            result.hotbar_changed = self.click_inventory(events, &screen, blocks);
+        }
+
+        // Мышь и клавиатура управляют взглядом и блоками только при захваченном
+        // курсоре, иначе они заняты окном. Геймпадом можно играть и без захвата
+        let free = !self.inventory_open && !typing;
+        let locked = free && events.cursor_locked;
+        let gamepad = free && events.gamepad.connected;
+        let dead_zone = config.input.gamepad_dead_zone;
+        let look = events.gamepad.stick(AXIS_RIGHT_X, AXIS_RIGHT_Y, dead_zone);
+        let walk = events.gamepad.stick(AXIS_LEFT_X, AXIS_LEFT_Y, dead_zone);
+
+        if locked || gamepad {
+            if locked {
+                let sensitivity = config.input.mouse_sensitivity;
+                self.cam_y -= (-events.delta_y / self.view_height) * sensitivity;
+                self.cam_x += (-events.delta_x / self.view_height) * sensitivity;
+            }
+            if gamepad {
+                let turn = config.input.gamepad_look_sensitivity * delta;
+                self.cam_y += look.y * turn;
+                self.cam_x -= look.x * turn;
+            }
+
+            self.cam_y = self.cam_y.clamp(-(89.0_f32).to_radians(), (89.0_f32).to_radians());
+
+            camera.rotation = Quat::IDENTITY;
+            camera.rotate(self.cam_y, self.cam_x, 0.0);
+        }
+
+        let mut input = TickInput { look: camera.front, ..Default::default() };
+        if !self.inventory_open && !typing {
+            self.read_movement(&mut input, events, config, camera, walk);
+
+            if bindings.pressed(events, Action::TimeBackward) {
+                input.time_shift -= 0.1;
+            }
+            if bindings.pressed(events, Action::TimeForward) {
+                input.time_shift += 0.1;
+            }
+        }
+
+        if locked {
+            self.edit_region(events, config, camera, world, blocks);
+        }
+
+        let jpressed = |action| {
+            (locked && bindings.jpressed(events, action)) || (gamepad && bindings.pad_jpressed(events, action))
+        };
+        input.break_block = jpressed(Action::Break);
+        if jpressed(Action::Place) {
+            input.place_block = self.hotbar.selected_block();
+        }
+        self.pending.merge(&input);
+
+        // Симуляция идёт фиксированными шагами независимо от частоты кадров
+        while self.accumulator >= TICK_DELTA {
+            world.update(&self.pending, blocks);
+            self.pending.clear_actions();
+            self.accumulator -= TICK_DELTA;
+        }
+        let alpha = self.accumulator / TICK_DELTA;
+
+        camera.position = world.eye_position(alpha);
+        result
+    }
+
+
+    fn read_slots(&mut self, events: &Events, config: &Config) {
+        let bindings = &config.input.bindings;
+        for slot in 0..SLOT_COUNT {
+            if bindings.jpressed(events, Action::SelectSlot(slot)) {
+                self.hotbar.select(slot as usize);
+            }
+        }
+        if bindings.jpressed(events, Action::NextSlot) {
+            self.hotbar.scroll(1);
+        }
+        if bindings.jpressed(events, Action::PreviousSlot) {
+            self.hotbar.scroll(-1);
+        }
+        // Колесо вверх - к предыдущему слоту
+        if events.scroll != 0.0 {
+            self.hotbar.scroll(-events.scroll.signum() as i32);
+        }
+    }
+
+
+    fn read_movement(&self, input: &mut TickInput, events: &Events, config: &Config, camera: &Camera, walk: Vec2) {
+        let bindings = &config.input.bindings;
+        if bindings.pressed(events, Action::MoveForward) {
+            input.player.direction += camera.front;
+        }
+        if bindings.pressed(events, Action::MoveBack) {
+            input.player.direction -= camera.front;
+        }
+        if bindings.pressed(events, Action::MoveRight) {
+            input.player.direction -= camera.right;
+        }
+        if bindings.pressed(events, Action::MoveLeft) {
+            input.player.direction += camera.right;
+        }
+        input.player.direction += camera.front * -walk.y - camera.right * walk.x;
+        input.player.jump = bindings.pressed(events, Action::Jump);
+        input.player.descend = bindings.pressed(events, Action::Descend);
+        input.toggle_mode = bindings.jpressed(events, Action::ToggleFly);
+    }
+
+
+    /// Инвентарь открывается со свободным курсором, а при закрытии
+    /// курсор возвращается в прежнее состояние
+    fn toggle_inventory(&mut self, events: &mut Events) {
+        self.inventory_open = !self.inventory_open;
+        if self.inventory_open {
+            self.release_cursor(events);
+        } else {
+            self.restore_cursor(events);
+        }
+    }
+
+
+    fn release_cursor(&mut self, events: &mut Events) {
+        self.cursor_was_locked = events.cursor_locked;
+        events.cursor_locked = false;
+    }
+
+
+    fn restore_cursor(&mut self, events: &mut Events) {
+        events.cursor_locked = self.cursor_was_locked;
+    }
+
+
+    /// Набор строки консоли; Enter выполняет её над миром
+    fn type_console(
+        &mut self,
+        events: &Events,
+        config: &Config,
+        camera: &Camera,
+        world: &mut World,
+        blocks: &BlockRegistry,
+        atlas: &Atlas,
+    ) {
+        self.console.type_text(&events.text);
+        if events.jpressed(BACKSPACE) {
+            self.console.backspace();
+        }
+        if events.jpressed(UP) {
+            self.console.browse(true);
+        }
+        if events.jpressed(DOWN) {
+            self.console.browse(false);
+        }
+        if events.jpressed(ENTER) {
+            let mut context = Context {
+                world,
+                blocks,
+                atlas,
+                world_path: &config.world.path,
+                editor: &mut self.editor,
+                look: camera.front,
+            };
+            self.console.submit(&mut context);
+        }
+    }
+
+
+    /// Углы выделения и отмена правок с клавиш; итог пишется в консоль
+    fn edit_region(&mut self, events: &Events, config: &Config, camera: &Camera, world: &mut World, blocks: &BlockRegistry) {
+        let bindings = &config.input.bindings;
+        for (corner, action) in [Action::SelectFirst, Action::SelectSecond].into_iter().enumerate() {
+            if !bindings.jpressed(events, action) {
+                continue;
+            }
+            if let Some(target) = world.target(camera.front) {
+                self.editor.select(corner, target.block);
+                let [x, y, z] = target.block;
+                self.console.print(&format!("corner {} at {} {} {}", corner + 1, x, y, z));
+            }
+        }
+        if bindings.jpressed(events, Action::Undo) {
+            match self.editor.undo(world, blocks) {
+                Some(count) => self.console.print(&format!("undone, {} blocks changed", count)),
+                None => self.console.print("nothing to undo"),
+            }
+        }
+        if bindings.jpressed(events, Action::Redo) {
+            match self.editor.redo(world, blocks) {
+                Some(count) => self.console.print(&format!("redone, {} blocks changed", count)),
+                None => self.console.print("nothing to redo"),
+            }
+        }
+    }
+
+
+    /// Щелчок по слоту панели выбирает его, по блоку инвентаря - кладёт блок
+    /// в выбранный слот. Возвращает, изменилось ли содержимое панели
+    fn click_inventory(&mut self, events: &Events, screen: &Screen, blocks: &BlockRegistry) -> bool {
+        let (x, y) = events.cursor();
+        if let Some(slot) = hotbar_slots(screen).iter().position(|slot| slot.contains(x, y)) {
+            self.hotbar.select(slot);
+            return false;
+        }
+
+        let ids = inventory_blocks(blocks);
+        let cells = inventory_cells(screen, ids.len());
+        match cells.iter().position(|cell| cell.contains(x, y)) {
+            Some(index) if self.hotbar.selected_block() != Some(ids[index]) => {
+                self.hotbar.set_selected(ids[index]);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::AtlasBuilder;
+    use crate::input::gamepad::{GamepadState, AXIS_RIGHT_TRIGGER};
+    use crate::player::Player;
+    use crate::voxels::Chunks;
+    use crate::world::WorldTime;
+    use glam::Vec3;
+
+    const MOUSE: usize = 1024;
+
+    /// Кадр короче тика: ввод накапливается, но мир не обновляется
+    fn frame(controls: &mut Controls, events: &mut Events, delta: f32) -> Camera {
+        let blocks = BlockRegistry::new();
+        let mut world = World::new(Chunks::filled(1, 1, 1, 0), WorldTime::new(600.0), Player::new(Vec3::splat(8.0)));
+        let mut camera = Camera::new(Vec3::ZERO, 1.0);
+        let atlas = AtlasBuilder::new(0).build();
+        controls.frame(delta, events, &Config::default(), &mut camera, &mut world, &blocks, &atlas);
+        camera
+    }
+
+    fn controls() -> Controls {
+        Controls::new(800, 600, Hotbar::from_names(&[], &BlockRegistry::new()))
+    }
+
+    fn move_and_click(events: &mut Events) {
+        events.begin_frame();
+        events.cursor_event(0.0, 0.0);
+        events.cursor_event(200.0, 100.0);
+        events.key_event(MOUSE + MOUSE_LEFT as usize, true);
+    }
+
+    #[test]
+    fn free_cursor_ignores_mouse() {
+        let mut controls = controls();
+        let mut events = Events::new();
+        move_and_click(&mut events);
+        let camera = frame(&mut controls, &mut events, 0.0);
+        assert_eq!((controls.cam_x, controls.cam_y), (0.0, 0.0));
+        assert_eq!(camera.front, Camera::new(Vec3::ZERO, 1.0).front);
+        assert!(!controls.pending.break_block);
+    }
+
+    #[test]
+    fn locked_cursor_uses_mouse() {
+        let mut controls = controls();
+        let mut events = Events::new();
+        events.cursor_locked = true;
+        move_and_click(&mut events);
+        frame(&mut controls, &mut events, 0.0);
+        assert!(controls.cam_x != 0.0 && controls.cam_y != 0.0);
+        assert!(controls.pending.break_block);
+    }
+
+    #[test]
+    fn gamepad_works_with_free_cursor() {
+        let mut controls = controls();
+        let mut events = Events::new();
+        let mut state = GamepadState { connected: true, ..Default::default() };
+        state.axes[AXIS_RIGHT_TRIGGER] = 1.0;
+        state.axes[AXIS_RIGHT_X] = 1.0;
+        events.begin_frame();
+        events.gamepad_event(state);
+        frame(&mut controls, &mut events, TICK_DELTA * 0.5);
+        assert!(controls.pending.break_block);
+        assert!(controls.cam_x < 0.0);
+    }
+}
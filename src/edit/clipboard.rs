@@ -0,0 +1,92 @@
+use crate::voxels::Voxel;
+use super::Region;
+
+
+/// Скопированные блоки. Хранятся слоями снизу вверх, как в `Region::positions`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Clipboard {
+    /// Ширина, высота и глубина
+    pub size: [usize; 3],
+    pub voxels: Vec<Voxel>,
+}
+
+
+impl Clipboard {
+    /// Блоки области с их состояниями; клетки вне мира копируются воздухом
+    pub fn copy(region: Region, voxel_at: impl Fn([isize; 3]) -> Option<Voxel>) -> Self {
+        Self {
+            size: region.size(),
+            voxels: region.positions().map(|position| voxel_at(position).unwrap_or_default()).collect(),
+        }
+    }
+
+
+    fn index(&self, [x, y, z]: [usize; 3]) -> usize {
+        (y * self.size[2] + z) * self.size[0] + x
+    }
+
+
+    /// Поворот на четверть оборота вокруг вертикальной оси по часовой
+    /// стрелке, если смотреть сверху: ширина и глубина меняются местами
+    pub fn rotated(&self) -> Self {
+        let [w, h, d] = self.size;
+        let mut voxels = vec![Voxel::default(); self.voxels.len()];
+        let rotated_size = [d, h, w];
+        for y in 0..h {
+            for z in 0..d {
+                for x in 0..w {
+                    let (nx, nz) = (d - 1 - z, x);
+                    voxels[(y * w + nz) * d + nx] = self.voxels[self.index([x, y, z])];
+                }
+            }
+        }
+        Self { size: rotated_size, voxels }
+    }
+
+
+    /// Блоки, поставленные так, что минимальный угол попадает в `origin`
+    pub fn blocks_at(&self, origin: [isize; 3]) -> impl Iterator<Item = ([isize; 3], Voxel)> + '_ {
+        let max = [0, 1, 2].map(|axis| origin[axis] + self.size[axis] as isize - 1);
+        Region { min: origin, max }.positions().zip(self.voxels.iter().copied())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2x1x3, id блока - его номер по порядку
+    fn clipboard() -> Clipboard {
+        Clipboard { size: [2, 1, 3], voxels: (1..=6).map(Voxel::new).collect() }
+    }
+
+    fn id(clipboard: &Clipboard, position: [usize; 3]) -> u8 {
+        clipboard.voxels[clipboard.index(position)].id
+    }
+
+    #[test]
+    fn rotation_swaps_width_and_depth() {
+        let clipboard = clipboard();
+        let rotated = clipboard.rotated();
+        assert_eq!(rotated.size, [3, 1, 2]);
+        // Сверху по часовой: угол x = 0, z = 0 уходит к x = d - 1, z = 0
+        assert_eq!(id(&rotated, [2, 0, 0]), id(&clipboard, [0, 0, 0]));
+        assert_eq!(id(&rotated, [2, 0, 1]), id(&clipboard, [1, 0, 0]));
+        assert_eq!(id(&rotated, [0, 0, 0]), id(&clipboard, [0, 0, 2]));
+    }
+
+    #[test]
+    fn four_rotations_are_identity() {
+        let clipboard = clipboard();
+        let turned = clipboard.rotated().rotated().rotated().rotated();
+        assert_eq!(turned, clipboard);
+    }
+
+    #[test]
+    fn blocks_are_placed_from_origin() {
+        let placed: Vec<_> = clipboard().blocks_at([10, 20, 30]).collect();
+        assert_eq!(placed.first(), Some(&([10, 20, 30], Voxel::new(1))));
+        assert_eq!(placed.last(), Some(&([11, 20, 32], Voxel::new(6))));
+    }
+}
@@ -0,0 +1,262 @@
+mod clipboard;
+mod region;
+pub mod schematic;
+pub mod vox;
+mod vox_palette;
+
+pub use clipboard::Clipboard;
+pub use region::Region;
+pub use schematic::Schematic;
+pub use vox_palette::VoxPalette;
+
+use crate::voxels::{BlockRegistry, Voxel};
+use crate::world::{BlockChange, World};
+
+
+/// Больше блоков за одну правку не меняем
+pub const MAX_VOLUME: usize = 1 << 18;
+/// Сколько последних правок можно отменить
+const HISTORY_LENGTH: usize = 32;
+
+
+/// Правка областями в духе редакторов миров: два угла выделения, буфер
+/// обмена и история отмены. Каждая правка ставит блоки одной пачкой,
+/// поэтому свет пересчитывается один раз, а не на каждый блок
+#[derive(Default)]
+pub struct Editor {
+    pub corners: [Option<[isize; 3]>; 2],
+    pub clipboard: Option<Clipboard>,
+    undo: Vec<Vec<BlockChange>>,
+    redo: Vec<Vec<BlockChange>>,
+}
+
+
+impl Editor {
+    /// Ставит угол выделения 0 или 1
+    pub fn select(&mut self, corner: usize, position: [isize; 3]) {
+        self.corners[corner] = Some(position);
+    }
+
+
+    /// Выделенная область, когда заданы оба угла
+    pub fn selection(&self) -> Option<Region> {
+        match self.corners {
+            [Some(a), Some(b)] => Some(Region::new(a, b)),
+            _ => None,
+        }
+    }
+
+
+    /// Ставит блоки и запоминает правку для отмены; возвращает число изменённых блоков
+    pub fn apply(
+        &mut self,
+        world: &mut World,
+        blocks: &BlockRegistry,
+        blocks_to_set: impl IntoIterator<Item = ([isize; 3], Voxel)>,
+    ) -> usize {
+        let changes = world.set_blocks(blocks_to_set, blocks);
+        let count = changes.len();
+        if count > 0 {
+            push_limited(&mut self.undo, changes);
+            self.redo.clear();
+        }
+        count
+    }
+
+
+    pub fn fill(&mut self, world: &mut World, blocks: &BlockRegistry, region: Region, id: u8) -> usize {
+        self.apply(world, blocks, region.positions().map(|position| (position, Voxel::new(id))))
+    }
+
+
+    /// Заменяет в области блоки `from` на `to`
+    pub fn replace(&mut self, world: &mut World, blocks: &BlockRegistry, region: Region, from: u8, to: u8) -> usize {
+        let positions: Vec<[isize; 3]> = region
+            .positions()
+            .filter(|&[x, y, z]| world.chunks.get_voxel(x, y, z).is_some_and(|voxel| voxel.id == from))
+            .collect();
+        self.apply(world, blocks, positions.into_iter().map(|position| (position, Voxel::new(to))))
+    }
+
+
+    /// Стенки, пол и потолок области из блока `id`, внутри воздух
+    pub fn hollow(&mut self, world: &mut World, blocks: &BlockRegistry, region: Region, id: u8) -> usize {
+        let blocks_to_set = region
+            .positions()
+            .map(|position| (position, Voxel::new(if region.on_border(position) { id } else { 0 })));
+        self.apply(world, blocks, blocks_to_set)
+    }
+
+
+    pub fn copy(&mut self, world: &World, region: Region) {
+        let voxel_at = |[x, y, z]: [isize; 3]| world.chunks.get_voxel(x, y, z).copied();
+        self.clipboard = Some(Clipboard::copy(region, voxel_at));
+    }
+
+
+    /// Ставит буфер обмена минимальным углом в `origin`, вместе с воздухом
+    pub fn paste(&mut self, world: &mut World, blocks: &BlockRegistry, origin: [isize; 3]) -> Option<usize> {
+        let clipboard = self.clipboard.take()?;
+        let count = self.apply(world, blocks, clipboard.blocks_at(origin));
+        self.clipboard = Some(clipboard);
+        Some(count)
+    }
+
+
+    /// Отменяет последнюю правку; `None`, если отменять нечего
+    pub fn undo(&mut self, world: &mut World, blocks: &BlockRegistry) -> Option<usize> {
+        let changes = self.undo.pop()?;
+        let reverted = world.set_blocks(changes.iter().rev().map(|change| (change.position, change.from)), blocks);
+        let count = reverted.len();
+        push_limited(&mut self.redo, changes);
+        Some(count)
+    }
+
+
+    /// Повторяет последнюю отменённую правку
+    pub fn redo(&mut self, world: &mut World, blocks: &BlockRegistry) -> Option<usize> {
+        let changes = self.redo.pop()?;
+        let applied = world.set_blocks(changes.iter().map(|change| (change.position, change.to)), blocks);
+        let count = applied.len();
+        push_limited(&mut self.undo, changes);
+        Some(count)
+    }
+}
+
+
+fn push_limited(history: &mut Vec<Vec<BlockChange>>, changes: Vec<BlockChange>) {
+    if history.len() == HISTORY_LENGTH {
+        history.remove(0);
+    }
+    history.push(changes);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec3;
+    use crate::fluids::{self, Fluid};
+    use crate::player::Player;
+    use crate::voxels::{Block, Chunks};
+    use crate::world::WorldTime;
+
+    const AIR: u8 = 0;
+    const STONE: u8 = 1;
+    const DIRT: u8 = 2;
+    const WATER: u8 = 8;
+    const CUBE: Region = Region { min: [1, 1, 1], max: [3, 3, 3] };
+
+    fn blocks() -> BlockRegistry {
+        let mut blocks = BlockRegistry::new();
+        blocks.blocks[AIR as usize] = Some(Block::new(AIR as u32, "air", 0));
+        blocks.blocks[STONE as usize] = Some(Block::new(STONE as u32, "stone", 0));
+        blocks.blocks[DIRT as usize] = Some(Block::new(DIRT as u32, "dirt", 0));
+        let mut water = Block::new(WATER as u32, "water", 0);
+        water.fluid = Some(Fluid { step: 1, delay: 1, infinite: true, hardens: None });
+        blocks.blocks[WATER as usize] = Some(water);
+        blocks
+    }
+
+    fn world() -> World {
+        let chunks = Chunks::filled(1, 1, 1, AIR);
+        World::new(chunks, WorldTime::new(600.0), Player::new(Vec3::new(8.0, 20.0, 8.0)))
+    }
+
+    fn voxel(world: &World, [x, y, z]: [isize; 3]) -> Voxel {
+        *world.chunks.get_voxel(x, y, z).unwrap()
+    }
+
+    fn count(world: &World, region: Region, id: u8) -> usize {
+        region.positions().filter(|&position| voxel(world, position).id == id).count()
+    }
+
+    #[test]
+    fn fill_replace_and_hollow() {
+        let blocks = blocks();
+        let mut world = world();
+        let mut editor = Editor::default();
+
+        assert_eq!(editor.fill(&mut world, &blocks, CUBE, STONE), 27);
+        // Совпадающие блоки не считаются и не попадают в историю
+        assert_eq!(editor.fill(&mut world, &blocks, CUBE, STONE), 0);
+
+        let bottom = Region::new([0, 1, 0], [4, 1, 4]);
+        assert_eq!(editor.replace(&mut world, &blocks, bottom, STONE, DIRT), 9);
+        assert_eq!(count(&world, CUBE, DIRT), 9);
+        assert_eq!(count(&world, CUBE, STONE), 18);
+
+        assert_eq!(editor.hollow(&mut world, &blocks, CUBE, STONE), 10);
+        assert_eq!(count(&world, CUBE, STONE), 26);
+        assert_eq!(voxel(&world, [2, 2, 2]), Voxel::new(AIR));
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let blocks = blocks();
+        let mut world = world();
+        let mut editor = Editor::default();
+        editor.fill(&mut world, &blocks, CUBE, STONE);
+        editor.replace(&mut world, &blocks, CUBE, STONE, DIRT);
+
+        assert_eq!(editor.undo(&mut world, &blocks), Some(27));
+        assert_eq!(count(&world, CUBE, STONE), 27);
+        assert_eq!(editor.undo(&mut world, &blocks), Some(27));
+        assert_eq!(count(&world, CUBE, AIR), 27);
+        assert_eq!(editor.undo(&mut world, &blocks), None);
+
+        assert_eq!(editor.redo(&mut world, &blocks), Some(27));
+        assert_eq!(count(&world, CUBE, STONE), 27);
+        // Новая правка обрывает ветку повтора
+        editor.fill(&mut world, &blocks, Region::new([5, 5, 5], [5, 5, 5]), DIRT);
+        assert_eq!(editor.redo(&mut world, &blocks), None);
+    }
+
+    #[test]
+    fn history_forgets_oldest_edits() {
+        let blocks = blocks();
+        let mut world = world();
+        let mut editor = Editor::default();
+        for x in 0..=HISTORY_LENGTH as isize {
+            editor.apply(&mut world, &blocks, [([x % 16, 1, x / 16], Voxel::new(STONE))]);
+        }
+
+        for _ in 0..HISTORY_LENGTH {
+            assert_eq!(editor.undo(&mut world, &blocks), Some(1));
+        }
+        assert_eq!(editor.undo(&mut world, &blocks), None);
+        // Первая правка вытеснена и осталась в мире
+        assert_eq!(voxel(&world, [0, 1, 0]), Voxel::new(STONE));
+        assert_eq!(count(&world, Region::new([0, 1, 0], [15, 1, 15]), STONE), 1);
+    }
+
+    #[test]
+    fn undo_keeps_flowing_water() {
+        let blocks = blocks();
+        let mut world = world();
+        let mut editor = Editor::default();
+        let flowing = Voxel { id: WATER, states: 3 };
+        world.set_blocks([([2, 2, 2], flowing)], &blocks);
+
+        assert_eq!(editor.fill(&mut world, &blocks, Region::new([1, 1, 1], [3, 3, 3]), STONE), 27);
+        assert_eq!(editor.undo(&mut world, &blocks), Some(27));
+        assert_eq!(voxel(&world, [2, 2, 2]), flowing);
+        assert!(!fluids::is_source(voxel(&world, [2, 2, 2]).states));
+
+        assert_eq!(editor.redo(&mut world, &blocks), Some(27));
+        assert_eq!(voxel(&world, [2, 2, 2]), Voxel::new(STONE));
+    }
+
+    #[test]
+    fn paste_keeps_states() {
+        let blocks = blocks();
+        let mut world = world();
+        let mut editor = Editor::default();
+        let flowing = Voxel { id: WATER, states: 5 };
+        world.set_blocks([([1, 1, 1], flowing)], &blocks);
+
+        editor.copy(&world, Region::new([1, 1, 1], [1, 1, 1]));
+        assert_eq!(editor.paste(&mut world, &blocks, [4, 1, 4]), Some(1));
+        assert_eq!(voxel(&world, [4, 1, 4]), flowing);
+    }
+}
@@ -0,0 +1,49 @@
+/// Прямоугольная область блоков, обе границы включены
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub min: [isize; 3],
+    pub max: [isize; 3],
+}
+
+
+impl Region {
+    /// Область между двумя противоположными углами в любом порядке
+    pub fn new(a: [isize; 3], b: [isize; 3]) -> Self {
+        Self {
+            min: [0, 1, 2].map(|axis| a[axis].min(b[axis])),
+            max: [0, 1, 2].map(|axis| a[axis].max(b[axis])),
+        }
+    }
+
+
+    /// Стороны области; слишком длинные упираются в `usize::MAX`
+    pub fn size(&self) -> [usize; 3] {
+        [0, 1, 2].map(|axis| self.max[axis].abs_diff(self.min[axis]).saturating_add(1))
+    }
+
+
+    /// Число клеток; не переполняется, а упирается в `usize::MAX`
+    pub fn volume(&self) -> usize {
+        self.size().iter().fold(1, |volume, side| volume.saturating_mul(*side))
+    }
+
+
+    /// Клетки слоями снизу вверх, в слое - по `z`, затем по `x`
+    pub fn positions(&self) -> impl Iterator<Item = [isize; 3]> {
+        let (min, max) = (self.min, self.max);
+        (min[1]..=max[1]).flat_map(move |y| {
+            (min[2]..=max[2]).flat_map(move |z| (min[0]..=max[0]).map(move |x| [x, y, z]))
+        })
+    }
+
+
+    /// Лежит ли клетка на одной из граней области
+    pub fn on_border(&self, position: [isize; 3]) -> bool {
+        (0..3).any(|axis| position[axis] == self.min[axis] || position[axis] == self.max[axis])
+    }
+
+
+    pub fn contains(&self, position: [isize; 3]) -> bool {
+        (0..3).all(|axis| (self.min[axis]..=self.max[axis]).contains(&position[axis]))
+    }
+}
@@ -0,0 +1,231 @@
+use std::fs;
+use std::io;
+
+use crate::files::write_binary_file;
+use crate::voxels::{BlockRegistry, Voxel};
+use super::Clipboard;
+
+
+/// Сигнатура файла схемы
+pub const MAGIC: &[u8; 4] = b"VXCS";
+/// Текущая версия формата. Во второй версии после id идут состояния блоков
+pub const VERSION: u16 = 2;
+
+const HEADER_SIZE: usize = 4 + 2 + 3 * 4 + 2;
+
+
+/// Постройка, сохранённая отдельно от мира. Id блоков в разных мирах
+/// могут не совпадать, поэтому вместе с ними хранятся имена
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schematic {
+    /// Ширина, высота и глубина
+    pub size: [usize; 3],
+    /// Id блоков слоями снизу вверх, как в `Clipboard`
+    pub ids: Vec<u8>,
+    /// Состояния блоков в том же порядке
+    pub states: Vec<u8>,
+    /// Имена всех встречающихся id
+    pub palette: Vec<(u8, String)>,
+}
+
+
+impl Schematic {
+    /// Схема из буфера обмена с именами блоков из реестра
+    pub fn from_clipboard(clipboard: &Clipboard, blocks: &BlockRegistry) -> Result<Self, String> {
+        let mut used = [false; 256];
+        for voxel in &clipboard.voxels {
+            used[voxel.id as usize] = true;
+        }
+        let mut palette = Vec::new();
+        for id in (0..=255u8).filter(|id| used[*id as usize]) {
+            let block = blocks.get(id).ok_or_else(|| format!("block {} is not registered", id))?;
+            palette.push((id, block.name.clone()));
+        }
+        let ids = clipboard.voxels.iter().map(|voxel| voxel.id).collect();
+        let states = clipboard.voxels.iter().map(|voxel| voxel.states).collect();
+        Ok(Self { size: clipboard.size, ids, states, palette })
+    }
+
+
+    /// Буфер обмена с id блоков этого мира, сопоставленными по именам
+    pub fn to_clipboard(&self, blocks: &BlockRegistry) -> Result<Clipboard, String> {
+        let mut ids: [Option<u8>; 256] = [None; 256];
+        for (id, name) in &self.palette {
+            ids[*id as usize] = Some(blocks.find(name).ok_or_else(|| format!("unknown block '{}'", name))?);
+        }
+        let voxels = self
+            .ids
+            .iter()
+            .zip(&self.states)
+            .map(|(id, &states)| match ids[*id as usize] {
+                Some(id) => Ok(Voxel { id, states }),
+                None => Err(format!("block {} is missing from the palette", id)),
+            })
+            .collect::<Result<Vec<Voxel>, String>>()?;
+        Ok(Clipboard { size: self.size, voxels })
+    }
+
+
+    /// Все числа little-endian: `MAGIC`, версия u16, размер w, h, d u32,
+    /// число записей палитры u16, записи (id u8, длина имени u8, имя в UTF-8),
+    /// id блоков по байту и состояния блоков по байту
+    fn write(&self, dest: &mut Vec<u8>) {
+        dest.extend_from_slice(MAGIC);
+        dest.extend_from_slice(&VERSION.to_le_bytes());
+        for value in self.size {
+            dest.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        dest.extend_from_slice(&(self.palette.len() as u16).to_le_bytes());
+        for (id, name) in &self.palette {
+            dest.push(*id);
+            // Имена блоков короткие; длиннее байта не бывает
+            let name = &name.as_bytes()[..name.len().min(255)];
+            dest.push(name.len() as u8);
+            dest.extend_from_slice(name);
+        }
+        dest.extend_from_slice(&self.ids);
+        dest.extend_from_slice(&self.states);
+    }
+
+
+    fn read(source: &[u8]) -> io::Result<Self> {
+        if source.len() < HEADER_SIZE || &source[0..4] != MAGIC {
+            return Err(invalid("not a schematic file"));
+        }
+        let u16_at = |i: usize| u16::from_le_bytes([source[i], source[i + 1]]);
+        let u32_at = |i: usize| u32::from_le_bytes([source[i], source[i + 1], source[i + 2], source[i + 3]]);
+
+        let version = u16_at(4);
+        if version > VERSION {
+            return Err(invalid(&format!("unsupported schematic version {}", version)));
+        }
+        let size = [u32_at(6) as usize, u32_at(10) as usize, u32_at(14) as usize];
+        let entries = u16_at(18) as usize;
+
+        let truncated = || invalid("schematic file is truncated");
+        let mut at = HEADER_SIZE;
+        let mut palette = Vec::with_capacity(entries);
+        for _ in 0..entries {
+            let (id, length) = match source.get(at..at + 2) {
+                Some(entry) => (entry[0], entry[1] as usize),
+                None => return Err(truncated()),
+            };
+            let name = source.get(at + 2..at + 2 + length).ok_or_else(truncated)?;
+            let name = String::from_utf8(name.to_vec()).map_err(|_| invalid("block name is not UTF-8"))?;
+            palette.push((id, name));
+            at += 2 + length;
+        }
+
+        let volume = size.iter().try_fold(1usize, |volume, side| volume.checked_mul(*side)).ok_or_else(truncated)?;
+        let mut bytes = |count: usize| {
+            let read = source.get(at..at.checked_add(count)?)?.to_vec();
+            at += count;
+            Some(read)
+        };
+        let ids = bytes(volume).ok_or_else(truncated)?;
+        // Первая версия состояний не хранила
+        let states = match version {
+            1 => vec![0; volume],
+            _ => bytes(volume).ok_or_else(truncated)?,
+        };
+        Ok(Self { size, ids, states, palette })
+    }
+}
+
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+
+pub fn load(path: &str) -> io::Result<Schematic> {
+    Schematic::read(&fs::read(path)?)
+}
+
+
+pub fn save(path: &str, schematic: &Schematic) -> io::Result<()> {
+    let mut data = Vec::new();
+    schematic.write(&mut data);
+    write_binary_file(path, &data)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxels::Block;
+
+    fn registry(names: &[(u8, &str)]) -> BlockRegistry {
+        let mut blocks = BlockRegistry::new();
+        for &(id, name) in names {
+            blocks.blocks[id as usize] = Some(Block::new(id as u32, name, 0));
+        }
+        blocks
+    }
+
+    /// 2x1x2: воздух, камень, вода с уровнем 3, камень
+    fn clipboard() -> Clipboard {
+        let voxels = vec![Voxel::new(0), Voxel::new(1), Voxel { id: 8, states: 3 }, Voxel::new(1)];
+        Clipboard { size: [2, 1, 2], voxels }
+    }
+
+    fn written(schematic: &Schematic) -> Vec<u8> {
+        let mut data = Vec::new();
+        schematic.write(&mut data);
+        data
+    }
+
+    #[test]
+    fn write_read_round_trip() {
+        let blocks = registry(&[(0, "air"), (1, "stone"), (8, "water")]);
+        let schematic = Schematic::from_clipboard(&clipboard(), &blocks).unwrap();
+        assert_eq!(schematic.palette.len(), 3);
+        assert_eq!(Schematic::read(&written(&schematic)).unwrap(), schematic);
+    }
+
+    #[test]
+    fn truncated_and_foreign_files_are_rejected() {
+        let blocks = registry(&[(0, "air"), (1, "stone"), (8, "water")]);
+        let data = written(&Schematic::from_clipboard(&clipboard(), &blocks).unwrap());
+
+        for length in [0, 3, HEADER_SIZE - 1, HEADER_SIZE + 2, data.len() - 1] {
+            let error = Schematic::read(&data[..length]).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "length {}", length);
+        }
+
+        let mut foreign = data.clone();
+        foreign[0..4].copy_from_slice(b"VOX ");
+        assert!(Schematic::read(&foreign).unwrap_err().to_string().contains("not a schematic"));
+
+        let mut future = data;
+        future[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert!(Schematic::read(&future).unwrap_err().to_string().contains("version"));
+    }
+
+    #[test]
+    fn first_version_has_no_states() {
+        let blocks = registry(&[(0, "air"), (1, "stone"), (8, "water")]);
+        let schematic = Schematic::from_clipboard(&clipboard(), &blocks).unwrap();
+        let mut data = written(&schematic);
+        data.truncate(data.len() - schematic.states.len());
+        data[4..6].copy_from_slice(&1u16.to_le_bytes());
+
+        let read = Schematic::read(&data).unwrap();
+        assert_eq!(read.ids, schematic.ids);
+        assert_eq!(read.states, vec![0; 4]);
+    }
+
+    #[test]
+    fn ids_are_remapped_by_name() {
+        let source = registry(&[(0, "air"), (1, "stone"), (8, "water")]);
+        let schematic = Schematic::from_clipboard(&clipboard(), &source).unwrap();
+
+        let target = registry(&[(0, "air"), (3, "water"), (5, "stone")]);
+        let remapped = schematic.to_clipboard(&target).unwrap();
+        let expected = vec![Voxel::new(0), Voxel::new(5), Voxel { id: 3, states: 3 }, Voxel::new(5)];
+        assert_eq!(remapped, Clipboard { size: [2, 1, 2], voxels: expected });
+
+        let missing = registry(&[(0, "air"), (1, "stone")]);
+        assert!(schematic.to_clipboard(&missing).unwrap_err().contains("water"));
+    }
+}
@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+
+use super::MAX_VOLUME;
+
+
+// Формат MagicaVoxel: после сигнатуры и версии идёт чанк MAIN, дочерние
+// чанки которого - модели (SIZE и XYZI), палитра RGBA и граф сцены
+// (nTRN, nGRP, nSHP). Чанк: id из 4 байт, размер содержимого и размер
+// дочерних чанков i32, затем содержимое и дочерние чанки. Все числа
+// little-endian. В MagicaVoxel вверх направлена ось z, в движке - y.
+
+/// Сигнатура файла
+pub const MAGIC: &[u8; 4] = b"VOX ";
+/// Версия, которую пишет MagicaVoxel
+pub const VERSION: i32 = 150;
+/// Наибольшая сторона модели
+pub const MAX_SIDE: usize = 256;
+
+
+/// Модель в осях движка. Индексы цветов 1..=255, 0 - пустая клетка
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VoxModel {
+    /// Ширина, высота и глубина
+    pub size: [usize; 3],
+    pub voxels: Vec<([usize; 3], u8)>,
+    /// Цвет RGBA каждого индекса; без чанка RGBA все цвета нулевые
+    pub palette: [[u8; 4]; 256],
+}
+
+
+/// Чанк файла: id, содержимое и дочерние чанки
+type RawChunk<'a> = ([u8; 4], &'a [u8], &'a [u8]);
+
+
+/// Узел графа сцены
+enum Node {
+    Transform { child: i32, translation: [i32; 3] },
+    Group { children: Vec<i32> },
+    Shape { models: Vec<i32> },
+}
+
+
+/// Последовательное чтение little-endian значений
+struct Reader<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self.at.checked_add(count).filter(|end| *end <= self.data.len());
+        let bytes = end.map(|end| &self.data[self.at..end]).ok_or("vox file is truncated")?;
+        self.at += count;
+        Ok(bytes)
+    }
+
+
+    fn i32(&mut self) -> Result<i32, String> {
+        let bytes = self.bytes(4)?;
+        Ok(i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+
+    fn size(&mut self) -> Result<usize, String> {
+        usize::try_from(self.i32()?).map_err(|_| "negative size in vox file".to_string())
+    }
+
+
+    fn string(&mut self) -> Result<String, String> {
+        let length = self.size()?;
+        Ok(String::from_utf8_lossy(self.bytes(length)?).into_owned())
+    }
+
+
+    fn dict(&mut self) -> Result<HashMap<String, String>, String> {
+        let count = self.size()?;
+        let mut dict = HashMap::new();
+        for _ in 0..count {
+            let key = self.string()?;
+            dict.insert(key, self.string()?);
+        }
+        Ok(dict)
+    }
+
+
+    fn chunk(&mut self) -> Result<RawChunk<'a>, String> {
+        let id = self.bytes(4)?;
+        let content = self.size()?;
+        let children = self.size()?;
+        Ok(([id[0], id[1], id[2], id[3]], self.bytes(content)?, self.bytes(children)?))
+    }
+
+
+    fn done(&self) -> bool {
+        self.at >= self.data.len()
+    }
+}
+
+
+/// Модель MagicaVoxel до перевода в оси движка
+struct Model {
+    size: [i32; 3],
+    voxels: Vec<([i32; 3], u8)>,
+}
+
+
+fn parse_node(id: &[u8; 4], content: &[u8]) -> Result<(i32, Node), String> {
+    let mut reader = Reader { data: content, at: 0 };
+    let node_id = reader.i32()?;
+    reader.dict()?;
+    let node = match id {
+        b"nTRN" => {
+            let child = reader.i32()?;
+            reader.i32()?; // зарезервировано
+            reader.i32()?; // слой
+            let frames = reader.size()?;
+            let mut translation = [0; 3];
+            // Анимация не поддерживается: берётся первый кадр, поворот `_r` не учитывается
+            if frames > 0 {
+                if let Some(t) = reader.dict()?.get("_t") {
+                    let values: Vec<i32> = t.split_whitespace().filter_map(|value| value.parse().ok()).collect();
+                    if let [x, y, z] = values[..] {
+                        translation = [x, y, z];
+                    }
+                }
+            }
+            Node::Transform { child, translation }
+        }
+        b"nGRP" => {
+            let count = reader.size()?;
+            Node::Group { children: (0..count).map(|_| reader.i32()).collect::<Result<_, _>>()? }
+        }
+        _ => {
+            let count = reader.size()?;
+            let mut models = Vec::with_capacity(count);
+            for _ in 0..count {
+                models.push(reader.i32()?);
+                reader.dict()?;
+            }
+            Node::Shape { models }
+        }
+    };
+    Ok((node_id, node))
+}
+
+
+/// Наибольшая глубина графа сцены
+const MAX_DEPTH: usize = 64;
+
+
+/// Модели со сдвигами, накопленными от корня графа сцены. Сдвиги
+/// складываются в i64: сумма `MAX_DEPTH` сдвигов i32 в него помещается
+fn place(nodes: &HashMap<i32, Node>, id: i32, offset: [i64; 3], depth: usize, placed: &mut Vec<(i32, [i64; 3])>) {
+    // Граф из файла может оказаться циклическим
+    if depth > MAX_DEPTH {
+        return;
+    }
+    match nodes.get(&id) {
+        Some(Node::Transform { child, translation }) => {
+            let offset = [0, 1, 2].map(|axis| offset[axis] + translation[axis] as i64);
+            place(nodes, *child, offset, depth + 1, placed);
+        }
+        Some(Node::Group { children }) => {
+            for child in children {
+                place(nodes, *child, offset, depth + 1, placed);
+            }
+        }
+        Some(Node::Shape { models }) => placed.extend(models.iter().map(|model| (*model, offset))),
+        None => {}
+    }
+}
+
+
+/// Читает все модели файла и собирает их по графу сцены в одну
+pub fn read(data: &[u8]) -> Result<VoxModel, String> {
+    let mut reader = Reader { data, at: 0 };
+    if reader.bytes(4).ok() != Some(&MAGIC[..]) {
+        return Err("not a vox file".to_string());
+    }
+    reader.i32()?;
+    let (id, _, children) = reader.chunk()?;
+    if &id != b"MAIN" {
+        return Err("vox file has no MAIN chunk".to_string());
+    }
+
+    let mut models = Vec::new();
+    let mut size = None;
+    let mut palette = [[0; 4]; 256];
+    let mut nodes = HashMap::new();
+    let mut reader = Reader { data: children, at: 0 };
+    while !reader.done() {
+        let (id, content, _) = reader.chunk()?;
+        let mut content_reader = Reader { data: content, at: 0 };
+        match &id {
+            b"SIZE" => {
+                let model_size = [content_reader.i32()?, content_reader.i32()?, content_reader.i32()?];
+                if model_size.iter().any(|side| !(1..=MAX_SIDE as i32).contains(side)) {
+                    return Err(format!("invalid vox model size {:?}", model_size));
+                }
+                size = Some(model_size);
+            }
+            b"XYZI" => {
+                let size = size.take().ok_or("XYZI chunk without SIZE")?;
+                let count = content_reader.size()?;
+                let bytes = content_reader.bytes(count.checked_mul(4).ok_or("vox file is truncated")?)?;
+                let voxels = bytes
+                    .chunks_exact(4)
+                    .map(|v| ([v[0] as i32, v[1] as i32, v[2] as i32], v[3]))
+                    .filter(|(position, _)| (0..3).all(|axis| position[axis] < size[axis]))
+                    .collect();
+                models.push(Model { size, voxels });
+            }
+            b"RGBA" => {
+                let colors = content_reader.bytes(256 * 4)?;
+                // Цвет i-го индекса записан на месте i - 1
+                for (color, bytes) in palette[1..].iter_mut().zip(colors.chunks_exact(4)) {
+                    color.copy_from_slice(bytes);
+                }
+            }
+            b"nTRN" | b"nGRP" | b"nSHP" => {
+                let (node_id, node) = parse_node(&id, content)?;
+                nodes.insert(node_id, node);
+            }
+            _ => {}
+        }
+    }
+    if models.is_empty() {
+        return Err("vox file has no models".to_string());
+    }
+
+    // Без графа сцены модели стоят в начале координат, а в сцене MagicaVoxel
+    // сдвиг узла указывает на центр модели
+    let mut placed = Vec::new();
+    if nodes.is_empty() {
+        placed.extend((0..models.len() as i32).map(|model| (model, [0; 3])));
+    } else {
+        place(&nodes, 0, [0; 3], 0, &mut placed);
+    }
+    let placed: Vec<(&Model, [i64; 3])> = placed
+        .into_iter()
+        .filter_map(|(index, offset)| {
+            let model = models.get(usize::try_from(index).ok()?)?;
+            let corner = [0, 1, 2].map(|axis| if nodes.is_empty() { 0 } else { offset[axis] - (model.size[axis] / 2) as i64 });
+            Some((model, corner))
+        })
+        .collect();
+    if placed.is_empty() {
+        return Err("vox scene places no models".to_string());
+    }
+
+    // Оси MagicaVoxel (x, y, z) в оси движка (x, z, -y)
+    let to_engine = |[x, y, z]: [i64; 3]| [x, z, -y];
+    let mut min = [i64::MAX; 3];
+    let mut max = [i64::MIN; 3];
+    for (model, corner) in &placed {
+        let far = [0, 1, 2].map(|axis| corner[axis] + model.size[axis] as i64 - 1);
+        for point in [to_engine(*corner), to_engine(far)] {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(point[axis]);
+                max[axis] = max[axis].max(point[axis]);
+            }
+        }
+    }
+    // Модели, разнесённые сдвигами далеко друг от друга, дают огромную сцену:
+    // она отвергается до того, как собираются воксели
+    let sides = [0, 1, 2].map(|axis| max[axis] - min[axis] + 1);
+    let volume = sides.iter().try_fold(1i64, |volume, side| volume.checked_mul(*side));
+    if volume.is_none_or(|volume| volume > MAX_VOLUME as i64) {
+        return Err(format!(
+            "vox scene is {}x{}x{} blocks, more than the limit of {}",
+            sides[0], sides[1], sides[2], MAX_VOLUME,
+        ));
+    }
+    let size = sides.map(|side| side as usize);
+
+    let mut voxels = Vec::new();
+    for (model, corner) in &placed {
+        for (position, color) in &model.voxels {
+            let position = to_engine([0, 1, 2].map(|axis| corner[axis] + position[axis] as i64));
+            voxels.push(([0, 1, 2].map(|axis| (position[axis] - min[axis]) as usize), *color));
+        }
+    }
+    Ok(VoxModel { size, voxels, palette })
+}
+
+
+fn write_chunk(dest: &mut Vec<u8>, id: &[u8; 4], content: &[u8], children: &[u8]) {
+    dest.extend_from_slice(id);
+    dest.extend_from_slice(&(content.len() as i32).to_le_bytes());
+    dest.extend_from_slice(&(children.len() as i32).to_le_bytes());
+    dest.extend_from_slice(content);
+    dest.extend_from_slice(children);
+}
+
+
+/// Файл с одной моделью и её палитрой
+pub fn write(model: &VoxModel) -> Result<Vec<u8>, String> {
+    let [w, h, d] = model.size;
+    if model.size.iter().any(|side| *side == 0 || *side > MAX_SIDE) {
+        return Err(format!("a vox model must be 1..{} blocks on each side, not {}x{}x{}", MAX_SIDE, w, h, d));
+    }
+
+    let mut size = Vec::new();
+    // В осях MagicaVoxel ширина, глубина и высота
+    for side in [w, d, h] {
+        size.extend_from_slice(&(side as i32).to_le_bytes());
+    }
+    let mut xyzi = (model.voxels.len() as i32).to_le_bytes().to_vec();
+    for ([x, y, z], color) in &model.voxels {
+        xyzi.extend_from_slice(&[*x as u8, (d - 1 - z) as u8, *y as u8, *color]);
+    }
+    let mut rgba = Vec::with_capacity(256 * 4);
+    for index in 1..=256 {
+        rgba.extend_from_slice(&model.palette[index % 256]);
+    }
+
+    let mut children = Vec::new();
+    write_chunk(&mut children, b"SIZE", &size, &[]);
+    write_chunk(&mut children, b"XYZI", &xyzi, &[]);
+    write_chunk(&mut children, b"RGBA", &rgba, &[]);
+
+    let mut data = MAGIC.to_vec();
+    data.extend_from_slice(&VERSION.to_le_bytes());
+    write_chunk(&mut data, b"MAIN", &[], &children);
+    Ok(data)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model() -> VoxModel {
+        let mut palette = [[0; 4]; 256];
+        palette[1] = [10, 20, 30, 255];
+        palette[255] = [1, 2, 3, 4];
+        let voxels = vec![([0, 0, 0], 1), ([2, 1, 0], 255), ([1, 0, 4], 1), ([2, 1, 4], 7)];
+        VoxModel { size: [3, 2, 5], voxels, palette }
+    }
+
+    #[test]
+    fn write_read_non_cubic() {
+        let model = model();
+        assert_eq!(read(&write(&model).unwrap()).unwrap(), model);
+    }
+
+    #[test]
+    fn write_rejects_bad_sizes() {
+        assert!(write(&VoxModel { size: [0, 1, 1], ..model() }).is_err());
+        assert!(write(&VoxModel { size: [MAX_SIDE + 1, 1, 1], ..model() }).is_err());
+    }
+
+    fn dict(dest: &mut Vec<u8>, pairs: &[(&str, &str)]) {
+        dest.extend_from_slice(&(pairs.len() as i32).to_le_bytes());
+        for text in pairs.iter().flat_map(|(key, value)| [key, value]) {
+            dest.extend_from_slice(&(text.len() as i32).to_le_bytes());
+            dest.extend_from_slice(text.as_bytes());
+        }
+    }
+
+    fn node(dest: &mut Vec<u8>, id: &[u8; 4], node_id: i32, values: &[i32], frame: Option<&str>) {
+        let mut content = node_id.to_le_bytes().to_vec();
+        dict(&mut content, &[]);
+        for value in values {
+            content.extend_from_slice(&value.to_le_bytes());
+        }
+        if let Some(translation) = frame {
+            content.extend_from_slice(&1i32.to_le_bytes());
+            dict(&mut content, &[("_t", translation)]);
+        }
+        write_chunk(dest, id, &content, &[]);
+    }
+
+    /// Файл из модели 1x1x1 и графа сцены `nodes`
+    fn scene(nodes: impl Fn(&mut Vec<u8>)) -> Vec<u8> {
+        let mut children = Vec::new();
+        write_chunk(&mut children, b"SIZE", &[1, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0], &[]);
+        write_chunk(&mut children, b"XYZI", &[1, 0, 0, 0, 0, 0, 0, 1], &[]);
+        nodes(&mut children);
+        let mut data = MAGIC.to_vec();
+        data.extend_from_slice(&VERSION.to_le_bytes());
+        write_chunk(&mut data, b"MAIN", &[], &children);
+        data
+    }
+
+    #[test]
+    fn deep_translations_do_not_overflow() {
+        let max = format!("{0} {0} {0}", i32::MAX);
+        let data = scene(|dest| {
+            for id in 0..MAX_DEPTH as i32 {
+                // Потомок, зарезервировано, слой
+                node(dest, b"nTRN", id, &[id + 1, -1, -1], Some(&max));
+            }
+            node(dest, b"nSHP", MAX_DEPTH as i32, &[1, 0, 0], None);
+        });
+        let model = read(&data).unwrap();
+        assert_eq!(model.size, [1, 1, 1]);
+        assert_eq!(model.voxels, vec![([0, 0, 0], 1)]);
+    }
+
+    #[test]
+    fn rejects_scene_beyond_limit() {
+        let data = scene(|dest| {
+            node(dest, b"nGRP", 0, &[2, 1, 2], None);
+            node(dest, b"nTRN", 1, &[3, -1, -1], Some(&format!("{} 0 0", i32::MIN)));
+            node(dest, b"nTRN", 2, &[3, -1, -1], Some(&format!("{} 0 0", i32::MAX)));
+            node(dest, b"nSHP", 3, &[1, 0, 0], None);
+        });
+        assert!(read(&data).unwrap_err().contains("limit"));
+    }
+}
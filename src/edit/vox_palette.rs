@@ -0,0 +1,204 @@
+use std::fs;
+
+use crate::voxels::BlockRegistry;
+use super::vox::VoxModel;
+use super::Clipboard;
+
+
+/// Строка таблицы: индекс палитры MagicaVoxel и блок для него
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaletteEntry {
+    pub index: u8,
+    pub block: String,
+    /// Цвет индекса при экспорте
+    pub color: Option<[u8; 3]>,
+}
+
+
+/// Соответствие палитры MagicaVoxel блокам. Текстовый файл, строки
+/// `индекс = блок [#rrggbb]`, комментарии с `#`. При импорте индекс без
+/// строки получает блок с ближайшим цветом из таблицы, при экспорте блок
+/// пишется первым индексом, отданным ему
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VoxPalette {
+    pub entries: Vec<PaletteEntry>,
+}
+
+
+impl VoxPalette {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut entries: Vec<PaletteEntry> = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| format!("line {}: {}", number + 1, message);
+            let (index, value) = line.split_once('=').ok_or_else(|| error(format!("expected 'index = block', got '{}'", line)))?;
+            let index = match index.trim().parse::<u8>() {
+                Ok(index) if index > 0 => index,
+                _ => return Err(error(format!("invalid palette index '{}', expected 1..255", index.trim()))),
+            };
+            let mut words = value.split_whitespace();
+            let block = words.next().ok_or_else(|| error("missing block name".to_string()))?.to_string();
+            let color = match words.next() {
+                Some(color) => Some(parse_color(color).ok_or_else(|| error(format!("invalid color '{}'", color)))?),
+                None => None,
+            };
+            if let Some(extra) = words.next() {
+                return Err(error(format!("unexpected '{}'", extra)));
+            }
+            entries.retain(|entry| entry.index != index);
+            entries.push(PaletteEntry { index, block, color });
+        }
+        Ok(Self { entries })
+    }
+
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("can't read vox palette '{}': {}", path, e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+
+    /// Блок для индекса палитры модели
+    fn block_for(&self, index: u8, color: [u8; 4], blocks: &BlockRegistry) -> Result<u8, String> {
+        let distance = |entry: &&PaletteEntry| {
+            entry.color.map(|c| (0..3).map(|i| (c[i] as i32 - color[i] as i32).pow(2)).sum::<i32>())
+        };
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.index == index)
+            .or_else(|| self.entries.iter().filter(|entry| entry.color.is_some()).min_by_key(distance))
+            .ok_or_else(|| format!("palette index {} has no block", index))?;
+        blocks.find(&entry.block).ok_or_else(|| format!("unknown block '{}'", entry.block))
+    }
+
+
+    /// Непустые клетки модели как блоки этого мира
+    pub fn blocks_of(&self, model: &VoxModel, blocks: &BlockRegistry) -> Result<Vec<([usize; 3], u8)>, String> {
+        let mut ids: [Option<u8>; 256] = [None; 256];
+        let mut result = Vec::with_capacity(model.voxels.len());
+        for (position, index) in &model.voxels {
+            let id = match ids[*index as usize] {
+                Some(id) => id,
+                None => {
+                    let id = self.block_for(*index, model.palette[*index as usize], blocks)?;
+                    ids[*index as usize] = Some(id);
+                    id
+                }
+            };
+            result.push((*position, id));
+        }
+        Ok(result)
+    }
+
+
+    /// Модель из скопированных блоков; воздух остаётся пустым
+    pub fn model_of(&self, clipboard: &Clipboard, blocks: &BlockRegistry) -> Result<VoxModel, String> {
+        let mut palette = [[0; 4]; 256];
+        for entry in &self.entries {
+            let [r, g, b] = entry.color.unwrap_or([128; 3]);
+            palette[entry.index as usize] = [r, g, b, 255];
+        }
+
+        let mut indices: [Option<u8>; 256] = [None; 256];
+        let [w, _, d] = clipboard.size;
+        let mut voxels = Vec::new();
+        let ids = clipboard.voxels.iter().map(|voxel| voxel.id);
+        for (i, id) in ids.enumerate().filter(|(_, id)| *id != 0) {
+            let index = match indices[id as usize] {
+                Some(index) => index,
+                None => {
+                    let name = blocks.get(id).map_or("?", |block| block.name.as_str());
+                    let entry = self.entries.iter().find(|entry| entry.block == name);
+                    let index = entry.ok_or_else(|| format!("block '{}' has no vox palette index", name))?.index;
+                    indices[id as usize] = Some(index);
+                    index
+                }
+            };
+            voxels.push(([i % w, i / (w * d), i / w % d], index));
+        }
+        Ok(VoxModel { size: clipboard.size, voxels, palette })
+    }
+}
+
+
+fn parse_color(text: &str) -> Option<[u8; 3]> {
+    let hex = text.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    Some([(value >> 16) as u8, (value >> 8) as u8, value as u8])
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edit::vox;
+    use crate::voxels::{Block, Voxel};
+
+    const PALETTE: &str = "# comment\n1 = stone #7f7f7f\n2 = grass #5e9d34\n3 = planks\n";
+
+    fn blocks() -> BlockRegistry {
+        let mut blocks = BlockRegistry::new();
+        for (id, name) in [(0, "air"), (1, "stone"), (2, "grass"), (5, "planks")] {
+            blocks.blocks[id] = Some(Block::new(id as u32, name, 0));
+        }
+        blocks
+    }
+
+    #[test]
+    fn parses_entries() {
+        let palette = VoxPalette::parse(&format!("{}1 = planks #000000", PALETTE)).unwrap();
+        assert_eq!(palette.entries.len(), 3);
+        assert_eq!(palette.entries[1], PaletteEntry { index: 3, block: "planks".to_string(), color: None });
+        assert_eq!(palette.entries[2], PaletteEntry { index: 1, block: "planks".to_string(), color: Some([0; 3]) });
+    }
+
+    #[test]
+    fn parse_errors() {
+        for text in ["stone", "0 = stone", "256 = stone", "x = stone", "1 =", "1 = stone #12345", "1 = stone #zzzzzz", "1 = stone #7f7f7f x"] {
+            let error = VoxPalette::parse(&format!("# header\n{}", text)).unwrap_err();
+            assert!(error.starts_with("line 2:"), "{}: {}", text, error);
+        }
+    }
+
+    #[test]
+    fn unknown_index_takes_nearest_color() {
+        let palette = VoxPalette::parse(PALETTE).unwrap();
+        let mut colors = [[0; 4]; 256];
+        colors[40] = [80, 160, 40, 255];
+        colors[41] = [120, 120, 130, 255];
+        let model = VoxModel { size: [3, 1, 1], voxels: vec![([0, 0, 0], 40), ([1, 0, 0], 41), ([2, 0, 0], 3)], palette: colors };
+        let placed = palette.blocks_of(&model, &blocks()).unwrap();
+        assert_eq!(placed, vec![([0, 0, 0], 2), ([1, 0, 0], 1), ([2, 0, 0], 5)]);
+
+        // Без цветов в таблице подобрать блок не из чего
+        let plain = VoxPalette::parse("3 = planks").unwrap();
+        assert!(plain.blocks_of(&model, &blocks()).is_err());
+        let unknown = VoxPalette::parse("40 = dirt #000000").unwrap();
+        assert!(unknown.blocks_of(&model, &blocks()).unwrap_err().contains("dirt"));
+    }
+
+    #[test]
+    fn model_round_trip() {
+        let blocks = blocks();
+        let palette = VoxPalette::parse(PALETTE).unwrap();
+        // 3x2x2, слоями снизу вверх
+        let ids = [1, 0, 2, 5, 5, 0, 0, 0, 1, 2, 0, 1];
+        let clipboard = Clipboard { size: [3, 2, 2], voxels: ids.map(Voxel::new).to_vec() };
+        let model = palette.model_of(&clipboard, &blocks).unwrap();
+        let model = vox::read(&vox::write(&model).unwrap()).unwrap();
+        assert_eq!(model.size, clipboard.size);
+
+        let mut read = vec![0; ids.len()];
+        for ([x, y, z], id) in palette.blocks_of(&model, &blocks).unwrap() {
+            read[(y * 2 + z) * 3 + x] = id;
+        }
+        assert_eq!(read, ids);
+
+        let unmapped = Clipboard { size: [1, 1, 1], voxels: vec![Voxel::new(9)] };
+        assert!(palette.model_of(&unmapped, &blocks).is_err());
+    }
+}
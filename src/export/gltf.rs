@@ -0,0 +1,109 @@
+use std::io;
+
+use crate::files::write_binary_file;
+use super::{file_name, ExportMesh};
+
+
+// Коды констант glTF, совпадают с OpenGL
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const NEAREST: u32 = 9728;
+const CLAMP_TO_EDGE: u32 = 33071;
+
+
+/// glTF 2.0: JSON с описанием сцены и отдельный `.gltf.bin` с вершинами.
+/// В glTF `v` идёт сверху вниз, поэтому UV переворачиваются. Материал
+/// помечен `KHR_materials_unlit`: свет уже запечён в `COLOR_0`
+pub fn write(path: &str, binary_path: &str, texture: &str, mesh: &ExportMesh) -> io::Result<()> {
+    let mut binary = Vec::new();
+    // Все элементы по 4 байта, так что отрезки буфера выровнены сами
+    let mut views = Vec::new();
+    let mut view = |binary: &mut Vec<u8>, values: Vec<u8>, target: u32| {
+        views.push(format!(
+            "{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{},\"target\":{}}}",
+            binary.len(),
+            values.len(),
+            target
+        ));
+        binary.extend_from_slice(&values);
+    };
+    view(&mut binary, floats(mesh.positions.iter().flatten().copied()), ARRAY_BUFFER);
+    view(&mut binary, floats(mesh.uvs.iter().flat_map(|[u, v]| [*u, 1.0 - v])), ARRAY_BUFFER);
+    view(&mut binary, floats(mesh.colors.iter().flatten().copied()), ARRAY_BUFFER);
+    view(&mut binary, mesh.indices.iter().flat_map(|index| index.to_le_bytes()).collect(), ELEMENT_ARRAY_BUFFER);
+
+    // Границы POSITION обязательны по спецификации
+    let mut min = [f32::MAX; 3];
+    let mut max = [f32::MIN; 3];
+    for position in &mesh.positions {
+        for axis in 0..3 {
+            min[axis] = min[axis].min(position[axis]);
+            max[axis] = max[axis].max(position[axis]);
+        }
+    }
+    let count = mesh.positions.len();
+    let accessors = [
+        format!(
+            "{{\"bufferView\":0,\"componentType\":{},\"count\":{},\"type\":\"VEC3\",\"min\":{},\"max\":{}}}",
+            FLOAT, count, array(&min), array(&max)
+        ),
+        format!("{{\"bufferView\":1,\"componentType\":{},\"count\":{},\"type\":\"VEC2\"}}", FLOAT, count),
+        format!("{{\"bufferView\":2,\"componentType\":{},\"count\":{},\"type\":\"VEC3\"}}", FLOAT, count),
+        format!("{{\"bufferView\":3,\"componentType\":{},\"count\":{},\"type\":\"SCALAR\"}}", UNSIGNED_INT, mesh.indices.len()),
+    ];
+
+    let json = format!(
+        "{{\n\
+        \"asset\":{{\"version\":\"2.0\",\"generator\":\"Voxel_Craft_Rust\"}},\n\
+        \"extensionsUsed\":[\"KHR_materials_unlit\"],\n\
+        \"scene\":0,\n\
+        \"scenes\":[{{\"nodes\":[0]}}],\n\
+        \"nodes\":[{{\"name\":\"world\",\"mesh\":0}}],\n\
+        \"meshes\":[{{\"primitives\":[{{\"attributes\":{{\"POSITION\":0,\"TEXCOORD_0\":1,\"COLOR_0\":2}},\"indices\":3,\"material\":0}}]}}],\n\
+        \"materials\":[{{\"name\":\"atlas\",\"pbrMetallicRoughness\":{{\"baseColorTexture\":{{\"index\":0}},\"metallicFactor\":0,\"roughnessFactor\":1}},\
+        \"alphaMode\":\"MASK\",\"alphaCutoff\":0.5,\"extensions\":{{\"KHR_materials_unlit\":{{}}}}}}],\n\
+        \"textures\":[{{\"sampler\":0,\"source\":0}}],\n\
+        \"samplers\":[{{\"magFilter\":{},\"minFilter\":{},\"wrapS\":{},\"wrapT\":{}}}],\n\
+        \"images\":[{{\"uri\":{}}}],\n\
+        \"buffers\":[{{\"uri\":{},\"byteLength\":{}}}],\n\
+        \"bufferViews\":[{}],\n\
+        \"accessors\":[{}]\n\
+        }}\n",
+        NEAREST, NEAREST, CLAMP_TO_EDGE, CLAMP_TO_EDGE,
+        string(texture),
+        string(&file_name(binary_path)),
+        binary.len(),
+        views.join(","),
+        accessors.join(","),
+    );
+    write_binary_file(binary_path, &binary)?;
+    write_binary_file(path, json.as_bytes())
+}
+
+
+fn floats(values: impl Iterator<Item = f32>) -> Vec<u8> {
+    values.flat_map(f32::to_le_bytes).collect()
+}
+
+
+fn array(values: &[f32; 3]) -> String {
+    format!("[{},{},{}]", values[0], values[1], values[2])
+}
+
+
+/// Строка JSON; имена файлов бывают с кавычками и обратными слешами
+fn string(value: &str) -> String {
+    let mut result = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
@@ -0,0 +1,142 @@
+mod gltf;
+mod obj;
+
+use std::path::Path;
+
+use glam::Vec3;
+
+use crate::assets::Atlas;
+use crate::edit::Region;
+use crate::graphics::voxel_renderer::VERTEX_SIZE;
+use crate::graphics::VoxelRenderer;
+use crate::loaders::png_loading::save_png;
+use crate::voxels::{BlockRegistry, Chunk, Chunks, CHUNK_D, CHUNK_H, CHUNK_VOL, CHUNK_W};
+
+
+/// Вершин на грань блока: два треугольника без общих вершин
+const FACE_VERTICES: usize = 6;
+
+
+/// Меш мира для других программ. Координаты мировые, UV - в атласе
+/// с `v` снизу вверх, как в OpenGL, свет запечён в цвет вершин
+#[derive(Default)]
+pub struct ExportMesh {
+    pub positions: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    pub colors: Vec<[f32; 3]>,
+    /// Треугольники против часовой стрелки, если смотреть снаружи
+    pub indices: Vec<u32>,
+}
+
+
+impl ExportMesh {
+    /// Те же меши чанков, что рисует игра. С `region` остаются только грани
+    /// блоков внутри неё: грани, закрытые соседями снаружи области, в мире
+    /// не видны и в срез не попадают. `sky` - цвет света неба, как в шейдере
+    pub fn build(chunks: &Chunks, blocks: &BlockRegistry, atlas: &Atlas, region: Option<Region>, sky: Vec3) -> Self {
+        let mut mesh = Self::default();
+        let mut renderer = VoxelRenderer::new(CHUNK_VOL);
+        let mut closes: Vec<Option<Chunk>> = vec![None; 27];
+        for (i, chunk) in chunks.chunks.iter().enumerate() {
+            let origin = [chunk.x * CHUNK_W as isize, chunk.y * CHUNK_H as isize, chunk.z * CHUNK_D as isize];
+            if region.is_some_and(|region| !overlaps_chunk(&region, origin)) {
+                continue;
+            }
+            chunks.neighbours(i, &mut closes);
+            let buffer = renderer.build(chunk, &closes, blocks, atlas);
+            for face in buffer.chunks_exact(VERTEX_SIZE * FACE_VERTICES) {
+                if region.is_some_and(|region| !region.contains(face_block(face, origin))) {
+                    continue;
+                }
+                mesh.add_face(face, origin, sky);
+            }
+        }
+        mesh
+    }
+
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+
+    /// Грань из буфера `VoxelRenderer`; совпадающие вершины треугольников склеиваются
+    fn add_face(&mut self, face: &[f32], origin: [isize; 3], sky: Vec3) {
+        let first = self.positions.len();
+        let mut added: Vec<&[f32]> = Vec::with_capacity(4);
+        for vertex in face.chunks_exact(VERTEX_SIZE) {
+            if let Some(index) = added.iter().position(|other| *other == vertex) {
+                self.indices.push((first + index) as u32);
+                continue;
+            }
+            self.indices.push((first + added.len()) as u32);
+            added.push(vertex);
+
+            // Так же сдвигает чанк `draw_world`
+            self.positions.push([0, 1, 2].map(|axis| vertex[axis] + origin[axis] as f32 + 0.5));
+            self.uvs.push([vertex[3], vertex[4]]);
+            let light = Vec3::new(vertex[5], vertex[6], vertex[7]) + sky * vertex[8];
+            self.colors.push(light.min(Vec3::ONE).to_array());
+        }
+    }
+}
+
+
+fn overlaps_chunk(region: &Region, origin: [isize; 3]) -> bool {
+    let size = [CHUNK_W as isize, CHUNK_H as isize, CHUNK_D as isize];
+    (0..3).all(|axis| region.min[axis] < origin[axis] + size[axis] && region.max[axis] >= origin[axis])
+}
+
+
+/// Блок, которому принадлежит грань: от центра грани чуть внутрь, против нормали
+fn face_block(face: &[f32], origin: [isize; 3]) -> [isize; 3] {
+    let point = |i: usize| Vec3::from_slice(&face[i * VERTEX_SIZE..i * VERTEX_SIZE + 3]);
+    let (a, b, c) = (point(0), point(1), point(2));
+    let normal = (b - a).cross(c - a).normalize_or_zero();
+    let (mut min, mut max) = (a, a);
+    for i in 1..FACE_VERTICES {
+        min = min.min(point(i));
+        max = max.max(point(i));
+    }
+    let inside = (min + max) * 0.5 - normal * 0.01;
+    [0, 1, 2].map(|axis| (inside[axis] + 0.5).floor() as isize + origin[axis])
+}
+
+
+/// Пишет меш в OBJ или glTF по расширению `path`, рядом кладёт атлас в PNG.
+/// Возвращает пути записанных файлов
+pub fn export(path: &str, mesh: &ExportMesh, atlas: &Atlas) -> Result<Vec<String>, String> {
+    if mesh.is_empty() {
+        return Err("nothing to export: the mesh is empty".to_string());
+    }
+    let file = Path::new(path);
+    let extension = file.extension().and_then(|extension| extension.to_str()).unwrap_or("");
+    let sibling = |extension: &str| file.with_extension(extension).to_string_lossy().into_owned();
+    let texture = sibling("png");
+    let texture_name = file_name(&texture);
+
+    let mut written = match extension.to_ascii_lowercase().as_str() {
+        "obj" => {
+            let material = sibling("mtl");
+            obj::write(path, &material, &texture_name, mesh).map_err(|e| format!("can't save '{}': {}", path, e))?;
+            vec![path.to_string(), material]
+        }
+        "gltf" => {
+            // Не `world.bin`: так называется и сам файл мира
+            let binary = sibling("gltf.bin");
+            gltf::write(path, &binary, &texture_name, mesh).map_err(|e| format!("can't save '{}': {}", path, e))?;
+            vec![path.to_string(), binary]
+        }
+        _ => return Err(format!("unknown mesh format '{}', expected .obj or .gltf", path)),
+    };
+
+    save_png(&texture, &atlas.data, atlas.width, atlas.height).map_err(|e| format!("can't save '{}': {}", texture, e))?;
+    written.push(texture);
+    Ok(written)
+}
+
+
+/// Имя файла для ссылок между файлами экспорта, лежащими в одной папке
+fn file_name(path: &str) -> String {
+    Path::new(path).file_name().map_or_else(|| path.to_string(), |name| name.to_string_lossy().into_owned())
+}
@@ -0,0 +1,41 @@
+use std::fmt::Write;
+use std::io;
+
+use crate::files::write_binary_file;
+use super::{file_name, ExportMesh};
+
+
+/// Имя единственного материала
+const MATERIAL: &str = "atlas";
+
+
+/// Wavefront OBJ и его библиотека материалов. Цвет вершины пишется
+/// после координат (`v x y z r g b`) - это расширение понимают Blender,
+/// MeshLab и большинство других программ
+pub fn write(path: &str, material_path: &str, texture: &str, mesh: &ExportMesh) -> io::Result<()> {
+    let mut text = String::new();
+    // Запись в String не бывает неудачной
+    let _ = writeln!(text, "# Voxel Craft world mesh");
+    let _ = writeln!(text, "mtllib {}", file_name(material_path));
+    let _ = writeln!(text, "o world");
+    for ([x, y, z], [r, g, b]) in mesh.positions.iter().zip(&mesh.colors) {
+        let _ = writeln!(text, "v {} {} {} {} {} {}", x, y, z, r, g, b);
+    }
+    for [u, v] in &mesh.uvs {
+        let _ = writeln!(text, "vt {} {}", u, v);
+    }
+    let _ = writeln!(text, "usemtl {}", MATERIAL);
+    // Индексы в OBJ с единицы, у вершины и UV они общие
+    for triangle in mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0] + 1, triangle[1] + 1, triangle[2] + 1];
+        let _ = writeln!(text, "f {}/{} {}/{} {}/{}", a, a, b, b, c, c);
+    }
+    write_binary_file(path, text.as_bytes())?;
+
+    // Свет уже в цветах вершин, поэтому материал без бликов и собственного освещения
+    let material = format!(
+        "newmtl {}\nKa 1 1 1\nKd 1 1 1\nKs 0 0 0\nd 1\nillum 1\nmap_Kd {}\nmap_d {}\n",
+        MATERIAL, texture, texture
+    );
+    write_binary_file(material_path, material.as_bytes())
+}
@@ -1,6 +1,6 @@
 use std::fs;
 use std::fs::File;
-use std::io::{self, Read, Write};
+use std::io::{self, Write};
 use std::path::Path;
 
 
@@ -64,9 +64,3 @@ pub fn write_binary_file(filename: &str, data: &[u8]) -> io::Result<()> {
     }
 }
 
-
-/// Чтение бинарного файла
-pub fn read_binary_file(filename: &str, data: &mut [u8]) -> io::Result<usize> {
-    let mut input = File::open(filename)?;
-    input.read(data)
-}
\ No newline at end of file
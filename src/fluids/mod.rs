@@ -0,0 +1,428 @@
+use crate::ticks::TickQueue;
+use crate::voxels::{BlockRegistry, Chunks, Voxel, CHUNK_D, CHUNK_H, CHUNK_W};
+
+
+// Уровень жидкости хранится в `Voxel::states`: младшие три бита - уровень,
+// 0 у источника и до `MAX_LEVEL` у самого дальнего от него разлива, бит
+// `FALLING` - жидкость стекает сверху и занимает клетку целиком.
+
+pub const LEVEL_MASK: u8 = 0x07;
+pub const FALLING: u8 = 0x08;
+pub const MAX_LEVEL: u8 = 7;
+/// Больше обновлений за шаг не делаем, остальные ждут следующего шага
+const MAX_UPDATES: usize = 4096;
+
+const SIDES: [[isize; 3]; 4] = [[1, 0, 0], [-1, 0, 0], [0, 0, 1], [0, 0, -1]];
+const UP: [isize; 3] = [0, 1, 0];
+const DOWN: [isize; 3] = [0, -1, 0];
+
+
+/// Застывание жидкости рядом с другой: лава у воды становится камнем
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hardening {
+    pub touching: u8,
+    pub into: u8,
+}
+
+
+/// Свойства жидкого блока
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fluid {
+    /// На сколько растёт уровень за клетку по горизонтали
+    pub step: u8,
+    /// Шагов симуляции между изменением рядом и обновлением клетки
+    pub delay: u64,
+    /// Клетка между двумя источниками над опорой сама становится источником
+    pub infinite: bool,
+    pub hardens: Option<Hardening>,
+}
+
+
+pub fn is_source(states: u8) -> bool {
+    states & (LEVEL_MASK | FALLING) == 0
+}
+
+
+pub fn is_falling(states: u8) -> bool {
+    states & FALLING != 0
+}
+
+
+pub fn level(states: u8) -> u8 {
+    states & LEVEL_MASK
+}
+
+
+/// Высота поверхности в долях блока; падающая жидкость занимает клетку целиком
+pub fn surface_height(states: u8) -> f32 {
+    if is_falling(states) {
+        1.0
+    } else {
+        (8 - level(states)) as f32 / 9.0
+    }
+}
+
+
+fn offset([x, y, z]: [isize; 3], [dx, dy, dz]: [isize; 3]) -> [isize; 3] {
+    [x + dx, y + dy, z + dz]
+}
+
+
+/// Растекание жидкостей. Клетка обновляется, только когда рядом что-то
+/// поменялось, через `delay` шагов её жидкости
+#[derive(Default)]
+pub struct Fluids {
+    scheduled: TickQueue,
+}
+
+
+impl Fluids {
+    /// Сколько обновлений ждёт очереди
+    pub fn pending(&self) -> usize {
+        self.scheduled.len()
+    }
+
+
+    /// Ставит в очередь клетку и её соседей, если в них жидкость
+    pub fn schedule_around(&mut self, position: [isize; 3], tick: u64, chunks: &Chunks, blocks: &BlockRegistry) {
+        for direction in [[0; 3], UP, DOWN].iter().chain(&SIDES) {
+            let position = offset(position, *direction);
+            if let Some((_, fluid)) = fluid_at(position, chunks, blocks) {
+                self.scheduled.schedule(position, tick + fluid.delay);
+            }
+        }
+    }
+
+
+    /// Ставит в очередь жидкости, которым есть куда течь: после загрузки
+    /// мира они продолжают с того места, где остановились
+    pub fn schedule_unsettled(&mut self, tick: u64, chunks: &Chunks, blocks: &BlockRegistry) {
+        let size = [chunks.w * CHUNK_W, chunks.h * CHUNK_H, chunks.d * CHUNK_D];
+        for y in 0..size[1] as isize {
+            for z in 0..size[2] as isize {
+                for x in 0..size[0] as isize {
+                    let position = [x, y, z];
+                    let Some((voxel, fluid)) = fluid_at(position, chunks, blocks) else {
+                        continue;
+                    };
+                    let open = [DOWN].iter().chain(&SIDES).any(|direction| {
+                        chunks.get_voxel(x + direction[0], y + direction[1], z + direction[2]).is_some_and(|voxel| voxel.id == 0)
+                    });
+                    if open || !is_source(voxel.states) {
+                        self.scheduled.schedule(position, tick + fluid.delay);
+                    }
+                }
+            }
+        }
+    }
+
+
+    /// Обновляет клетки, чей шаг наступил. Возвращает клетки, где сменился
+    /// блок: для них нужно пересчитать свет
+    pub fn update(&mut self, tick: u64, chunks: &mut Chunks, blocks: &BlockRegistry) -> Vec<[isize; 3]> {
+        let mut changed = Vec::new();
+        for _ in 0..MAX_UPDATES {
+            match self.scheduled.pop_due(tick) {
+                Some(position) => self.update_cell(position, tick, chunks, blocks, &mut changed),
+                None => break,
+            }
+        }
+        changed
+    }
+
+
+    fn update_cell(&mut self, position: [isize; 3], tick: u64, chunks: &mut Chunks, blocks: &BlockRegistry, changed: &mut Vec<[isize; 3]>) {
+        let Some((voxel, fluid)) = fluid_at(position, chunks, blocks) else {
+            return;
+        };
+        let id = voxel.id;
+
+        if let Some(hardening) = fluid.hardens {
+            let touches = [UP].iter().chain(&SIDES).any(|direction| {
+                id_at(offset(position, *direction), chunks) == Some(hardening.touching)
+            });
+            if touches {
+                self.set(position, Voxel { id: hardening.into, states: 0 }, tick, chunks, blocks, changed);
+                return;
+            }
+        }
+
+        let mut states = voxel.states;
+        if !is_source(states) {
+            match fed_states(position, id, fluid, chunks) {
+                None => {
+                    self.set(position, Voxel { id: 0, states: 0 }, tick, chunks, blocks, changed);
+                    return;
+                }
+                Some(fed) if fed != states => {
+                    states = fed;
+                    self.set(position, Voxel { id, states }, tick, chunks, blocks, changed);
+                }
+                _ => {}
+            }
+        }
+
+        let below = offset(position, DOWN);
+        self.flow_into(below, id, FALLING, tick, chunks, blocks, changed);
+        if !rests(position, id, chunks) {
+            return;
+        }
+        let next = if is_source(states) || is_falling(states) { fluid.step } else { level(states) + fluid.step };
+        if next > MAX_LEVEL {
+            return;
+        }
+        for direction in SIDES {
+            self.flow_into(offset(position, direction), id, next, tick, chunks, blocks, changed);
+        }
+    }
+
+
+    /// Жидкость `id` с состоянием `states` заходит в клетку, если там воздух
+    /// или её же разлив ниже уровнем. Другая жидкость застывает
+    #[allow(clippy::too_many_arguments)]
+    fn flow_into(
+        &mut self,
+        position: [isize; 3],
+        id: u8,
+        states: u8,
+        tick: u64,
+        chunks: &mut Chunks,
+        blocks: &BlockRegistry,
+        changed: &mut Vec<[isize; 3]>,
+    ) {
+        let Some(&target) = chunks.get_voxel(position[0], position[1], position[2]) else {
+            return;
+        };
+        if target.id == 0 {
+            self.set(position, Voxel { id, states }, tick, chunks, blocks, changed);
+        } else if target.id == id {
+            let lower = !is_source(target.states)
+                && !is_falling(target.states)
+                && (is_falling(states) || level(target.states) > level(states));
+            if lower {
+                self.set(position, Voxel { id, states }, tick, chunks, blocks, changed);
+            }
+        } else if let Some(into) = hardening(id, target.id, blocks) {
+            self.set(position, Voxel { id: into, states: 0 }, tick, chunks, blocks, changed);
+        }
+    }
+
+
+    fn set(&mut self, position: [isize; 3], voxel: Voxel, tick: u64, chunks: &mut Chunks, blocks: &BlockRegistry, changed: &mut Vec<[isize; 3]>) {
+        let [x, y, z] = position;
+        if chunks.get_voxel(x, y, z).map(|old| old.id) != Some(voxel.id) {
+            chunks.set(x, y, z, voxel.id as i32);
+            changed.push(position);
+        }
+        chunks.set_states(x, y, z, voxel.states);
+        self.schedule_around(position, tick, chunks, blocks);
+    }
+}
+
+
+fn id_at([x, y, z]: [isize; 3], chunks: &Chunks) -> Option<u8> {
+    chunks.get_voxel(x, y, z).map(|voxel| voxel.id)
+}
+
+
+fn fluid_at([x, y, z]: [isize; 3], chunks: &Chunks, blocks: &BlockRegistry) -> Option<(Voxel, Fluid)> {
+    let voxel = *chunks.get_voxel(x, y, z)?;
+    Some((voxel, blocks.get(voxel.id)?.fluid?))
+}
+
+
+/// Блок, в который застывает встреча двух жидкостей, в любом порядке
+fn hardening(a: u8, b: u8, blocks: &BlockRegistry) -> Option<u8> {
+    let rule = |fluid: u8, other: u8| {
+        let hardening = blocks.get(fluid)?.fluid?.hardens?;
+        (hardening.touching == other).then_some(hardening.into)
+    };
+    rule(a, b).or_else(|| rule(b, a))
+}
+
+
+/// Жидкость лежит на опоре и растекается в стороны: снизу не воздух
+/// и не её же разлив, куда она стекла бы
+fn rests(position: [isize; 3], id: u8, chunks: &Chunks) -> bool {
+    let [x, y, z] = offset(position, DOWN);
+    match chunks.get_voxel(x, y, z) {
+        None => true,
+        Some(below) => below.id != 0 && (below.id != id || is_source(below.states)),
+    }
+}
+
+
+/// Состояние разлива по соседям, которые его питают; `None` - питать некому
+fn fed_states(position: [isize; 3], id: u8, fluid: Fluid, chunks: &Chunks) -> Option<u8> {
+    if id_at(offset(position, UP), chunks) == Some(id) {
+        return Some(FALLING);
+    }
+
+    let mut sources = 0;
+    let mut lowest = None;
+    for direction in SIDES {
+        let side = offset(position, direction);
+        let Some(&voxel) = chunks.get_voxel(side[0], side[1], side[2]) else {
+            continue;
+        };
+        if voxel.id != id || !rests(side, id, chunks) {
+            continue;
+        }
+        sources += is_source(voxel.states) as u32;
+        let feeding = if is_source(voxel.states) || is_falling(voxel.states) { 0 } else { level(voxel.states) };
+        lowest = Some(lowest.map_or(feeding, |lowest: u8| lowest.min(feeding)));
+    }
+
+    if fluid.infinite && sources >= 2 && rests(position, id, chunks) {
+        let below = offset(position, DOWN);
+        let on_ground = chunks.get_voxel(below[0], below[1], below[2]).is_none_or(|voxel| voxel.id != id || is_source(voxel.states));
+        if on_ground {
+            return Some(0);
+        }
+    }
+    lowest.map(|lowest| lowest + fluid.step).filter(|level| *level <= MAX_LEVEL)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxels::Block;
+
+    const AIR: u8 = 0;
+    const STONE: u8 = 1;
+    const WATER: u8 = 8;
+    const LAVA: u8 = 9;
+
+    fn blocks() -> BlockRegistry {
+        let mut blocks = BlockRegistry::new();
+        blocks.blocks[AIR as usize] = Some(Block::new(AIR as u32, "air", 0));
+        blocks.blocks[STONE as usize] = Some(Block::new(STONE as u32, "stone", 0));
+        let mut water = Block::new(WATER as u32, "water", 0);
+        water.fluid = Some(Fluid { step: 1, delay: 1, infinite: true, hardens: None });
+        blocks.blocks[WATER as usize] = Some(water);
+        let mut lava = Block::new(LAVA as u32, "lava", 0);
+        lava.fluid = Some(Fluid { step: 2, delay: 2, infinite: false, hardens: Some(Hardening { touching: WATER, into: STONE }) });
+        blocks.blocks[LAVA as usize] = Some(lava);
+        blocks
+    }
+
+    /// Чанк с каменным полом на `y = 0`
+    struct Pool {
+        chunks: Chunks,
+        blocks: BlockRegistry,
+        fluids: Fluids,
+        tick: u64,
+    }
+
+    impl Pool {
+        fn new() -> Self {
+            let mut chunks = Chunks::filled(1, 1, 1, AIR);
+            for z in 0..CHUNK_D as isize {
+                for x in 0..CHUNK_W as isize {
+                    chunks.set(x, 0, z, STONE as i32);
+                }
+            }
+            Self { chunks, blocks: blocks(), fluids: Fluids::default(), tick: 0 }
+        }
+
+        fn set(&mut self, [x, y, z]: [isize; 3], id: u8) {
+            self.chunks.set(x, y, z, id as i32);
+            self.chunks.set_states(x, y, z, 0);
+            self.fluids.schedule_around([x, y, z], self.tick, &self.chunks, &self.blocks);
+        }
+
+        fn run(&mut self, ticks: u64) {
+            for _ in 0..ticks {
+                self.tick += 1;
+                self.fluids.update(self.tick, &mut self.chunks, &self.blocks);
+            }
+        }
+
+        fn at(&self, [x, y, z]: [isize; 3]) -> Voxel {
+            *self.chunks.get_voxel(x, y, z).unwrap()
+        }
+
+        fn count(&self, id: u8) -> usize {
+            self.chunks.chunks.iter().flat_map(|chunk| chunk.voxels.iter()).filter(|voxel| voxel.id == id).count()
+        }
+    }
+
+    #[test]
+    fn spreads_by_levels() {
+        let mut pool = Pool::new();
+        pool.set([8, 1, 8], WATER);
+        pool.run(100);
+        assert_eq!(pool.fluids.pending(), 0);
+        assert!(is_source(pool.at([8, 1, 8]).states));
+        for distance in 1..=MAX_LEVEL as isize {
+            let voxel = pool.at([8 + distance, 1, 8]);
+            assert_eq!((voxel.id, level(voxel.states)), (WATER, distance as u8));
+        }
+        assert_eq!(level(pool.at([9, 1, 9]).states), 2);
+        assert_eq!(pool.at([0, 1, 8]).id, AIR);
+        assert_eq!(pool.at([8, 2, 8]).id, AIR);
+    }
+
+    #[test]
+    fn falls_and_spreads_below() {
+        let mut pool = Pool::new();
+        pool.set([8, 10, 8], WATER);
+        pool.run(100);
+        for y in 1..10 {
+            let voxel = pool.at([8, y, 8]);
+            assert!(voxel.id == WATER && is_falling(voxel.states), "y = {}", y);
+        }
+        // Сверху разлива нет: источник стоит на падающей воде
+        assert_eq!(pool.at([9, 10, 8]).id, AIR);
+        assert_eq!(level(pool.at([9, 1, 8]).states), 1);
+    }
+
+    #[test]
+    fn drains_without_source() {
+        let mut pool = Pool::new();
+        pool.set([8, 4, 8], WATER);
+        pool.run(100);
+        assert!(pool.count(WATER) > 1);
+
+        pool.set([8, 4, 8], AIR);
+        pool.run(100);
+        assert_eq!(pool.count(WATER), 0);
+        assert_eq!(pool.fluids.pending(), 0);
+    }
+
+    #[test]
+    fn two_sources_make_a_third() {
+        let mut pool = Pool::new();
+        pool.set([7, 1, 8], WATER);
+        pool.set([9, 1, 8], WATER);
+        pool.run(100);
+        assert!(is_source(pool.at([8, 1, 8]).states));
+
+        // Лава не бесконечна: между источниками остаётся разлив
+        let mut pool = Pool::new();
+        pool.set([7, 1, 8], LAVA);
+        pool.set([9, 1, 8], LAVA);
+        pool.run(100);
+        let between = pool.at([8, 1, 8]);
+        assert_eq!((between.id, level(between.states)), (LAVA, 2));
+    }
+
+    #[test]
+    fn lava_hardens_at_water() {
+        let mut pool = Pool::new();
+        pool.set([8, 1, 8], LAVA);
+        pool.set([9, 1, 8], WATER);
+        pool.run(10);
+        assert_eq!(pool.at([8, 1, 8]).id, STONE);
+
+        // Разливы встречаются между источниками и застывают там же
+        let mut pool = Pool::new();
+        pool.set([3, 1, 8], LAVA);
+        pool.set([12, 1, 8], WATER);
+        pool.run(200);
+        assert!(pool.at([3, 1, 8]).id == LAVA && pool.at([12, 1, 8]).id == WATER);
+        assert!((4..12).any(|x| pool.at([x, 1, 8]).id == STONE));
+        assert_eq!(pool.fluids.pending(), 0);
+    }
+}
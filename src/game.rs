@@ -0,0 +1,286 @@
+use crate::math::*;
+
+use crate::window::{Window, Events, Camera};
+use crate::assets::Assets;
+use crate::voxels::Chunk;
+use crate::graphics::{VoxelRenderer, LineBatch};
+use crate::graphics::screenshot::{read_pixels, save_screenshot, Framebuffer};
+use crate::world::{draw_world, world_file, REACH_DISTANCE};
+use crate::config::Config;
+use crate::console::Context;
+use crate::controls::Controls;
+use crate::edit::Region;
+use crate::input::gamepad::{GamepadSource, GlfwGamepad};
+use crate::input::recording::{world_snapshot_path, Recording, Replay, Session};
+use crate::player::Hotbar;
+use crate::settings::{FONT_PATH, SCREENSHOTS_PATH};
+use crate::stats::DebugStats;
+use crate::ui::{draw_console, draw_debug, draw_hud, Screen, UiRenderer};
+use crate::{load_content, open_world};
+
+
+/// Запуск игры с окном. Настройки, изменённые в игре, попадают и в `config`,
+/// и в `saved`, который потом записывается в файл
+pub fn play(config: &mut Config, saved: &mut Config, session: Session, script: Option<&str>) -> Result<(), String> {
+    let graphics = &config.graphics;
+    let mut window = Window::new(graphics.width, graphics.height, "Window 2.0").unwrap();
+    let mut events = Events::new();
+
+    events.initialize(&mut window);
+
+    let (mut atlas, blocks_controller) = load_content();
+
+    let mut assets = Assets::init(&atlas).expect("fail load assets");
+    println!("load assets: ok");
+
+    println!("start init renderer");
+    let mut renderer = VoxelRenderer::new(config.graphics.renderer_capacity);
+    let mut line_batch = LineBatch::new(4096);
+    let mut ui = UiRenderer::new(FONT_PATH, 4096).expect("fail load ui");
+    println!("renderer init: ok");
+
+    window.gl_setting();
+
+    let mut camera = Camera::new(Vec3::new(5.0, 5.0, 20.0), config.graphics.fov.to_radians());
+    println!("camera init: ok");
+
+    println!("start settings init");
+    let mut last_time = window.glfw.get_time();
+    let mut gamepad_source = GlfwGamepad::new(&window.glfw);
+    let hotbar = Hotbar::from_names(&config.gameplay.hotbar, &blocks_controller.blocks);
+    let mut controls = Controls::new(config.graphics.width, config.graphics.height, hotbar);
+    println!("settings init: ok");
+
+    let mut stats = DebugStats::default();
+    let mut world = open_world(config, camera.position, &blocks_controller.blocks)?;
+    let mut meshes = Vec::with_capacity(world.chunks.volume);
+    for _ in 0..world.chunks.volume {
+        meshes.push(None);
+    }
+
+    // Вывод скрипта виден в консоли
+    if let Some(script) = script {
+        let mut context = Context {
+            world: &mut world,
+            blocks: &blocks_controller.blocks,
+            atlas: &atlas,
+            world_path: &config.world.path,
+            editor: &mut controls.editor,
+            look: camera.front,
+        };
+        match controls.console.registry.run_script(script, &mut context) {
+            Ok(lines) => lines.iter().for_each(|line| controls.console.print(line)),
+            Err(e) => controls.console.print(&format!("error: {}", e)),
+        }
+    }
+
+    let replaying = matches!(session, Session::Replay(_));
+    let mut recording = None;
+    let mut replay = None;
+    match session {
+        Session::Live => {}
+        Session::Record(path) => {
+            // Запись воспроизводится с того же мира, с которого началась
+            let snapshot = world_snapshot_path(&path);
+            world_file::save(&snapshot, &world.chunks, true)
+                .map_err(|e| format!("can't record: world snapshot '{}' not saved: {}", snapshot, e))?;
+            events.start_recording();
+            recording = Some((path, Recording::new(config.to_text())));
+        }
+        Session::Replay(recorded) => replay = Some(Replay::new(recorded)),
+    }
+
+    println!("start main loop");
+    while !window.should_close() {
+        let current_time = window.glfw.get_time();
+        let mut delta = (current_time - last_time) as f32;
+        last_time = current_time;
+        stats.frame(delta);
+
+        if let Some((_, recording)) = &mut recording {
+            recording.push(delta, events.take_recorded());
+        }
+        // Пока запись не кончилась, ввод и время кадра берутся из неё
+        let (replayed, input) = match &mut replay {
+            Some(replay) => replay.input(&mut events),
+            None => (None, &mut events),
+        };
+        if let Some(replayed) = replayed {
+            delta = replayed;
+        }
+
+        let frame = controls.frame(delta, input, config, &mut camera, &mut world, &blocks_controller.blocks, &atlas);
+        if frame.exit {
+            window.close();
+        }
+        if frame.cursor_toggled {
+            window.window.set_cursor_mode(input.cursor_mode());
+        }
+        if frame.hotbar_changed && !replaying {
+            let names = controls.hotbar.names(&blocks_controller.blocks);
+            config.gameplay.hotbar = names.clone();
+            saved.gameplay.hotbar = names;
+        }
+
+        for index in atlas.animate(world.time.elapsed()) {
+            let animation = &atlas.animations[index];
+            let region = animation.region;
+            assets.texture.update_region(
+                region.x as i32,
+                region.y as i32,
+                region.width as i32,
+                region.height as i32,
+                animation.current_frame()
+            );
+        }
+
+        let mut end = glam::Vec3::default();
+        let mut norm = glam::Vec3::default();
+        let mut iend = glam::Vec3::default();
+
+        if let Some(_vox) = world.chunks.ray_cast(
+            camera.position,
+            camera.front,
+            REACH_DISTANCE,
+            &mut end,
+            &mut norm,
+            &mut iend)
+        {
+            line_batch.boxx(
+                iend.x + 0.5,
+                iend.y + 0.5,
+                iend.z + 0.5,
+                1.01,
+                1.01,
+                1.01,
+                0.0,
+                0.0,
+                0.0,
+                1.0
+            );
+        }
+
+        // Выделение области, а пока задан один угол - только он
+        let selection = controls.editor.selection().or_else(|| {
+            controls.editor.corners.iter().flatten().next().map(|&corner| Region::new(corner, corner))
+        });
+        if let Some(region) = selection {
+            let [w, h, d] = region.size().map(|size| size as f32);
+            let [x, y, z] = region.min.map(|min| min as f32);
+            line_batch.boxx(x + w * 0.5, y + h * 0.5, z + d * 0.5, w + 0.02, h + 0.02, d + 0.02, 1.0, 0.8, 0.2, 1.0);
+        }
+
+        stats.observe(&mut world, camera.position, camera.front, &blocks_controller.blocks);
+        stats.chunks_rebuilt = 0;
+
+        let chunks = &mut world.chunks;
+        let mut closes: Vec<Option<Chunk>> = vec![None; 27];
+
+        for i in 0..chunks.volume {
+            if let Some(chunk) = chunks.chunks.get_mut(i) {
+                if !chunk.modified {
+                    continue;
+                }
+                chunk.modified = false;
+            }
+            let chunk = &chunks.chunks[i];
+
+            if let Some(mesh) = meshes[i].take() {
+                // Освобождаем ресурсы меша
+                drop(mesh);
+            }
+
+            chunks.neighbours(i, &mut closes);
+            let mesh = renderer.render(chunk, &closes, &blocks_controller.blocks, &atlas);
+            meshes[i] = Some(mesh);
+            stats.chunks_rebuilt += 1;
+        }
+        stats.meshes(meshes.iter().map(|mesh| mesh.as_ref().map_or(0, |mesh| mesh.vertex_count())));
+
+
+        // Большой снимок рисуется во внеэкранный буфер, а окно получает его уменьшенную копию
+        let (width, height) = (window.width() as u32, window.height() as u32);
+        let scale = frame.screenshot.unwrap_or(1);
+        let offscreen = match scale {
+            1 => None,
+            scale => match Framebuffer::new(width * scale, height * scale) {
+                Ok(framebuffer) => Some(framebuffer),
+                Err(e) => {
+                    controls.console.print(&format!("error: high resolution screenshot: {}", e));
+                    None
+                }
+            },
+        };
+        if let Some(framebuffer) = &offscreen {
+            framebuffer.bind();
+        }
+        // Интерфейс увеличивается вместе с кадром
+        let (width, height, ui_scale) = match &offscreen {
+            Some(framebuffer) => (framebuffer.width, framebuffer.height, config.graphics.ui_scale * scale),
+            None => (width, height, config.graphics.ui_scale),
+        };
+
+        stats.chunks_drawn = draw_world(&assets, &camera, &window, &world, &meshes, &mut line_batch);
+        ui.begin(Screen::new(width as f32, height as f32, ui_scale));
+        draw_hud(&mut ui, &atlas, &blocks_controller.blocks, &controls.hotbar, controls.inventory_open);
+        if controls.debug_overlay {
+            draw_debug(&mut ui, &stats);
+        }
+        if controls.console.open {
+            draw_console(&mut ui, &controls.console);
+        }
+        ui.render(&assets);
+
+        if frame.screenshot.is_some() {
+            let message = match save_screenshot(SCREENSHOTS_PATH, &read_pixels(width, height), width, height) {
+                Ok(path) => format!("screenshot saved on {}", path),
+                Err(e) => format!("error: {}", e),
+            };
+            println!("{}", message);
+            controls.console.print(&message);
+        }
+        if let Some(framebuffer) = offscreen {
+            framebuffer.blit_to_window(window.width(), window.height());
+        }
+
+        window.swap_buffers();
+        // Во время воспроизведения из окна нужны только закрытие и размер:
+        // живой ввод копится в `events`, но в кадры записи не попадает
+        events.pull_events(&mut window);
+        if replay.as_ref().is_none_or(Replay::is_finished) {
+            events.gamepad_event(gamepad_source.poll());
+        }
+
+        if let Some((width, height)) = events.resized.filter(|_| !replaying) {
+            if width > 0 && height > 0 {
+                for graphics in [&mut config.graphics, &mut saved.graphics] {
+                    graphics.set_window_size(width as u32, height as u32);
+                }
+            }
+        }
+    }
+    println!("finish main loop");
+
+    let mut errors = Vec::new();
+    if let Some((path, recording)) = recording {
+        match recording.save(&path) {
+            Ok(()) => println!("input recorded on {} ({} frames)", path, recording.frames.len()),
+            Err(e) => errors.push(format!("input not recorded on {}: {}", path, e)),
+        }
+    }
+
+    // Воспроизведение не должно менять снимок мира, с которого идёт
+    if !replaying {
+        println!("saving world");
+        match world.save(&config.world.path) {
+            Ok(()) => println!("world saved on {}", config.world.path),
+            Err(e) => errors.push(format!("world not saved on {}: {}", config.world.path, e)),
+        }
+    }
+
+    window.terminate();
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors.join("; ")),
+    }
+}
@@ -73,6 +73,10 @@ impl Mesh {
         self.vertices = vertices;
     }
 
+    pub fn vertex_count(&self) -> usize {
+        self.vertices
+    }
+
     pub fn draw(&self, primitive: GLenum) {
         unsafe {
             gl::BindVertexArray(self.vao);
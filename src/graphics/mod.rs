@@ -1,12 +1,28 @@
+#[cfg(feature = "graphics")]
 mod shader;
+#[cfg(feature = "graphics")]
 mod texture;
+#[cfg(feature = "graphics")]
 pub mod mesh;
 pub mod voxel_renderer;
+pub mod rasterizer;
+#[cfg(feature = "graphics")]
+pub mod screenshot;
+#[cfg(feature = "graphics")]
 pub mod linebatch;
+#[cfg(feature = "graphics")]
+pub mod spritebatch;
 
+#[cfg(feature = "graphics")]
 pub use shader::load_shader;
+#[cfg(feature = "graphics")]
 pub use texture::Texture;
+#[cfg(feature = "graphics")]
 pub use shader::Shader;
 pub use voxel_renderer::VoxelRenderer;
+#[cfg(feature = "graphics")]
 pub use linebatch::LineBatch;
-pub use mesh::Mesh;
\ No newline at end of file
+#[cfg(feature = "graphics")]
+pub use spritebatch::SpriteBatch;
+#[cfg(feature = "graphics")]
+pub use mesh::Mesh;
@@ -0,0 +1,233 @@
+use glam::{Mat4, Vec2, Vec3, Vec4, Vec4Swizzles};
+
+use crate::assets::Atlas;
+use crate::voxels::{BlockRegistry, Chunk, Chunks, CHUNK_D, CHUNK_H, CHUNK_VOL, CHUNK_W};
+use crate::window::Camera;
+use crate::world::WorldTime;
+use super::voxel_renderer::VERTEX_SIZE;
+use super::VoxelRenderer;
+
+
+/// Вершина после вершинного шейдера
+#[derive(Clone, Copy)]
+struct ClipVertex {
+    position: Vec4,
+    uv: Vec2,
+    color: Vec3,
+}
+
+
+impl ClipVertex {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Self {
+            position: self.position.lerp(other.position, t),
+            uv: self.uv.lerp(other.uv, t),
+            color: self.color.lerp(other.color, t),
+        }
+    }
+}
+
+
+/// Вершина в пикселях; атрибуты заранее поделены на `w` для перспективной интерполяции
+struct ScreenVertex {
+    x: f32,
+    y: f32,
+    depth: f32,
+    inv_w: f32,
+    uv: Vec2,
+    color: Vec3,
+}
+
+
+/// Растеризация мешей чанков на CPU тем же путём, что и в `main.glslv`/`main.glslf`:
+/// тест глубины, отсечение задних граней, текстура атласа без фильтрации,
+/// отбрасывание пикселей с альфой меньше 0.5 и смешивание по альфе.
+/// Нужна, чтобы проверять картинку без видеокарты
+pub struct Rasterizer {
+    pub width: usize,
+    pub height: usize,
+    /// RGBA строками снизу вверх, как в `glReadPixels`
+    color: Vec<Vec4>,
+    depth: Vec<f32>,
+}
+
+
+impl Rasterizer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height, color: vec![Vec4::ZERO; width * height], depth: vec![1.0; width * height] }
+    }
+
+
+    pub fn clear(&mut self, color: Vec3) {
+        self.color.fill(color.extend(1.0));
+        self.depth.fill(1.0);
+    }
+
+
+    /// Рисует вершины формата `VoxelRenderer`. `transform` - произведение
+    /// `projview * model`, `sky` - цвет света неба, как `u_skyLight`
+    pub fn draw(&mut self, vertices: &[f32], transform: Mat4, sky: Vec3, atlas: &Atlas) {
+        for triangle in vertices.chunks_exact(VERTEX_SIZE * 3) {
+            let vertex = |i: usize| {
+                let v = &triangle[i * VERTEX_SIZE..(i + 1) * VERTEX_SIZE];
+                ClipVertex {
+                    position: transform * Vec4::new(v[0], v[1], v[2], 1.0),
+                    uv: Vec2::new(v[3], v[4]),
+                    color: Vec3::new(v[5], v[6], v[7]) + sky * v[8],
+                }
+            };
+            let polygon = clip_near([vertex(0), vertex(1), vertex(2)]);
+            for i in 1..polygon.len().saturating_sub(1) {
+                self.fill(&polygon[0], &polygon[i], &polygon[i + 1], atlas);
+            }
+        }
+    }
+
+
+    fn to_screen(&self, vertex: &ClipVertex) -> ScreenVertex {
+        let inv_w = 1.0 / vertex.position.w;
+        let ndc = vertex.position.xyz() * inv_w;
+        ScreenVertex {
+            x: (ndc.x * 0.5 + 0.5) * self.width as f32,
+            y: (ndc.y * 0.5 + 0.5) * self.height as f32,
+            depth: ndc.z,
+            inv_w,
+            uv: vertex.uv * inv_w,
+            color: vertex.color * inv_w,
+        }
+    }
+
+
+    fn fill(&mut self, a: &ClipVertex, b: &ClipVertex, c: &ClipVertex, atlas: &Atlas) {
+        let v = [self.to_screen(a), self.to_screen(b), self.to_screen(c)];
+        let edge = |p: &ScreenVertex, q: &ScreenVertex, x: f32, y: f32| (q.x - p.x) * (y - p.y) - (q.y - p.y) * (x - p.x);
+        let area = edge(&v[0], &v[1], v[2].x, v[2].y);
+        // Лицевые грани идут против часовой стрелки, как по умолчанию в OpenGL
+        if area <= 0.0 {
+            return;
+        }
+
+        let min_x = v.iter().map(|v| v.x).fold(f32::MAX, f32::min).floor().max(0.0) as usize;
+        let min_y = v.iter().map(|v| v.y).fold(f32::MAX, f32::min).floor().max(0.0) as usize;
+        let max_x = (v.iter().map(|v| v.x).fold(f32::MIN, f32::max).ceil() as usize).min(self.width);
+        let max_y = (v.iter().map(|v| v.y).fold(f32::MIN, f32::max).ceil() as usize).min(self.height);
+
+        // Правило верхнего левого ребра: пиксель на общем ребре рисуется одним треугольником
+        let top_left = |p: &ScreenVertex, q: &ScreenVertex| (p.y == q.y && q.x < p.x) || q.y > p.y;
+        let owns = [top_left(&v[1], &v[2]), top_left(&v[2], &v[0]), top_left(&v[0], &v[1])];
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let weights = [edge(&v[1], &v[2], px, py), edge(&v[2], &v[0], px, py), edge(&v[0], &v[1], px, py)];
+                if (0..3).any(|i| weights[i] < 0.0 || (weights[i] == 0.0 && !owns[i])) {
+                    continue;
+                }
+                let [w0, w1, w2] = weights.map(|w| w / area);
+
+                let index = y * self.width + x;
+                let depth = w0 * v[0].depth + w1 * v[1].depth + w2 * v[2].depth;
+                if !(0.0..self.depth[index]).contains(&depth) {
+                    continue;
+                }
+                let inv_w = w0 * v[0].inv_w + w1 * v[1].inv_w + w2 * v[2].inv_w;
+                let uv = (v[0].uv * w0 + v[1].uv * w1 + v[2].uv * w2) / inv_w;
+                let texel = sample(atlas, uv);
+                if texel.w < 0.5 {
+                    continue;
+                }
+                let color = (v[0].color * w0 + v[1].color * w1 + v[2].color * w2) / inv_w;
+                let source = (color * texel.xyz()).extend(texel.w);
+                let destination = self.color[index];
+                self.color[index] = source * source.w + destination * (1.0 - source.w);
+                self.depth[index] = depth;
+            }
+        }
+    }
+
+
+    /// RGBA байты строками снизу вверх, как их принимает `save_png`
+    pub fn pixels(&self) -> Vec<u8> {
+        self.color
+            .iter()
+            .flat_map(|color| color.to_array().map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8))
+            .collect()
+    }
+}
+
+
+/// Отсекает треугольник плоскостью near (`z >= 0` после `perspective_rh`);
+/// остаётся от нуля до четырёх вершин
+fn clip_near(triangle: [ClipVertex; 3]) -> Vec<ClipVertex> {
+    let mut polygon = Vec::with_capacity(4);
+    for i in 0..3 {
+        let (current, next) = (triangle[i], triangle[(i + 1) % 3]);
+        let (d1, d2) = (current.position.z, next.position.z);
+        if d1 >= 0.0 {
+            polygon.push(current);
+        }
+        if (d1 >= 0.0) != (d2 >= 0.0) {
+            polygon.push(current.lerp(next, d1 / (d1 - d2)));
+        }
+    }
+    polygon
+}
+
+
+/// Ближайший тексель атласа, как `GL_NEAREST`
+fn sample(atlas: &Atlas, uv: Vec2) -> Vec4 {
+    let x = ((uv.x * atlas.width as f32) as usize).min(atlas.width as usize - 1);
+    let y = ((uv.y * atlas.height as f32) as usize).min(atlas.height as usize - 1);
+    let at = (y * atlas.width as usize + x) * 4;
+    Vec4::from_array([0, 1, 2, 3].map(|i| atlas.data[at + i] as f32 / 255.0))
+}
+
+
+/// Кадр мира с камеры, как его рисует `draw_world`; небо и его свет берутся из `time`
+pub fn render_world(
+    chunks: &Chunks,
+    blocks: &BlockRegistry,
+    atlas: &Atlas,
+    camera: &Camera,
+    time: &WorldTime,
+    width: usize,
+    height: usize,
+) -> Rasterizer {
+    let mut rasterizer = Rasterizer::new(width, height);
+    rasterizer.clear(time.clear_color());
+    let sky = time.sky_light();
+    let projview = camera.get_projection(width as f32, height as f32) * camera.get_view();
+    let mut renderer = VoxelRenderer::new(CHUNK_VOL);
+    let mut closes: Vec<Option<Chunk>> = vec![None; 27];
+    for (i, chunk) in chunks.chunks.iter().enumerate() {
+        chunks.neighbours(i, &mut closes);
+        let model = Mat4::from_translation(Vec3::new(
+            (chunk.x * CHUNK_W as isize) as f32 + 0.5,
+            (chunk.y * CHUNK_H as isize) as f32 + 0.5,
+            (chunk.z * CHUNK_D as isize) as f32 + 0.5,
+        ));
+        rasterizer.draw(renderer.build(chunk, &closes, blocks, atlas), projview * model, sky, atlas);
+    }
+    rasterizer
+}
+
+
+/// Отличие картинки от эталона
+pub struct ImageDiff {
+    /// Пиксели, у которых хоть один канал отличается больше допуска
+    pub mismatched: usize,
+    /// Наибольшая разница канала
+    pub max: u8,
+}
+
+
+/// Сравнивает RGBA картинки одного размера с допуском по каналу
+pub fn compare(actual: &[u8], expected: &[u8], tolerance: u8) -> ImageDiff {
+    let mut diff = ImageDiff { mismatched: 0, max: 0 };
+    for (a, e) in actual.chunks_exact(4).zip(expected.chunks_exact(4)) {
+        let max = (0..4).map(|i| a[i].abs_diff(e[i])).max().unwrap_or(0);
+        diff.max = diff.max.max(max);
+        diff.mismatched += (max > tolerance) as usize;
+    }
+    diff
+}
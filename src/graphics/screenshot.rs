@@ -0,0 +1,160 @@
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use gl::types::*;
+use std::os::raw::c_void;
+
+use crate::loaders::png_loading::save_png;
+
+
+/// Читает RGBA пиксели привязанного буфера кадра, строки снизу вверх.
+/// Альфа после смешивания бывает меньше единицы, а в снимке она не нужна
+pub fn read_pixels(width: u32, height: u32) -> Vec<u8> {
+    let mut data = vec![0u8; width as usize * height as usize * 4];
+    unsafe {
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(
+            0,
+            0,
+            width as GLsizei,
+            height as GLsizei,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            data.as_mut_ptr() as *mut c_void
+        );
+    }
+    for pixel in data.chunks_exact_mut(4) {
+        pixel[3] = 255;
+    }
+    data
+}
+
+
+/// Внеэкранный буфер кадра для снимков больше окна
+pub struct Framebuffer {
+    id: GLuint,
+    color: GLuint,
+    depth: GLuint,
+    pub width: u32,
+    pub height: u32,
+}
+
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Result<Self, String> {
+        let mut max_size: GLint = 0;
+        unsafe {
+            gl::GetIntegerv(gl::MAX_RENDERBUFFER_SIZE, &mut max_size);
+        }
+        if width.max(height) > max_size as u32 {
+            return Err(format!("{}x{} is larger than the GPU allows ({})", width, height, max_size));
+        }
+
+        let mut framebuffer = Self { id: 0, color: 0, depth: 0, width, height };
+        unsafe {
+            gl::GenFramebuffers(1, &mut framebuffer.id);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer.id);
+
+            gl::GenRenderbuffers(1, &mut framebuffer.color);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, framebuffer.color);
+            gl::RenderbufferStorage(gl::RENDERBUFFER, gl::RGBA8, width as GLsizei, height as GLsizei);
+            gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::RENDERBUFFER, framebuffer.color);
+
+            gl::GenRenderbuffers(1, &mut framebuffer.depth);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, framebuffer.depth);
+            gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH_COMPONENT24, width as GLsizei, height as GLsizei);
+            gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, gl::RENDERBUFFER, framebuffer.depth);
+
+            gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                // Ресурсы освободит drop
+                return Err(format!("framebuffer is incomplete (status {:#x})", status));
+            }
+        }
+        Ok(framebuffer)
+    }
+
+
+    /// Рисование дальше идёт в этот буфер
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
+            gl::Viewport(0, 0, self.width as GLsizei, self.height as GLsizei);
+        }
+    }
+
+
+    /// Копирует картинку в окно с уменьшением и возвращает рисование в окно
+    pub fn blit_to_window(&self, width: i32, height: i32) {
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.id);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0);
+            gl::BlitFramebuffer(
+                0,
+                0,
+                self.width as GLint,
+                self.height as GLint,
+                0,
+                0,
+                width,
+                height,
+                gl::COLOR_BUFFER_BIT,
+                gl::LINEAR
+            );
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::Viewport(0, 0, width, height);
+        }
+    }
+}
+
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteRenderbuffers(1, &self.color);
+            gl::DeleteRenderbuffers(1, &self.depth);
+            gl::DeleteFramebuffers(1, &self.id);
+        }
+    }
+}
+
+
+/// Пишет снимок в `dir` под именем с датой и временем по UTC; несколько
+/// снимков за секунду получают суффикс. Возвращает путь файла
+pub fn save_screenshot(dir: &str, data: &[u8], width: u32, height: u32) -> Result<String, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("can't create '{}': {}", dir, e))?;
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+    let name = timestamp(seconds);
+    let path = (0..)
+        .map(|index| match index {
+            0 => format!("{}/{}.png", dir, name),
+            index => format!("{}/{}_{}.png", dir, name, index),
+        })
+        .find(|path| !Path::new(path).exists())
+        .unwrap();
+    save_png(&path, data, width, height).map_err(|e| format!("can't save '{}': {}", path, e))?;
+    Ok(path)
+}
+
+
+/// `2024-03-09_18-05-42` из секунд с начала эпохи Unix
+fn timestamp(seconds: u64) -> String {
+    let (days, time) = (seconds / 86400, seconds % 86400);
+    // Дата по дням от 1970-01-01, алгоритм civil_from_days Говарда Хиннанта
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+        year, month, day, time / 3600, time % 3600 / 60, time % 60
+    )
+}
@@ -22,6 +22,13 @@ impl Shader {
             gl::UniformMatrix4fv(transform_loc, 1, gl::FALSE, matrix.as_ref().as_ptr());
         }
     }
+    pub fn uniform_vec3(&self, name: &str, value: glam::Vec3) {
+        unsafe {
+            let c_name = CString::new(name).expect("CString::new failed");
+            let location = gl::GetUniformLocation(self.id, c_name.as_ptr());
+            gl::Uniform3f(location, value.x, value.y, value.z);
+        }
+    }
     pub fn use_shader(&self) {
         unsafe {
             gl::UseProgram(self.id);
@@ -0,0 +1,77 @@
+use glam::{Vec2, Vec4};
+
+use crate::assets::UvRegion;
+use super::mesh::Mesh;
+
+/// Двумерные четырёхугольники в пикселях экрана: цветные и с текстурой атласа.
+/// Как и `LineBatch`, копит вершины за кадр и рисует их одним вызовом
+pub struct SpriteBatch {
+    capacity: usize,
+    buffer: Vec<f32>,
+    mesh: Mesh,
+}
+
+const SB_VERTEX_SIZE: usize = 8;
+
+/// Текстурные координаты, по которым шейдер понимает, что текстура не нужна
+const NO_TEXTURE: UvRegion = UvRegion { u1: -1.0, v1: -1.0, u2: -1.0, v2: -1.0 };
+
+impl SpriteBatch {
+    /// `capacity` - число четырёхугольников за кадр
+    pub fn new(capacity: usize) -> Self {
+        let buffer = Vec::with_capacity(capacity * SB_VERTEX_SIZE * 6);
+        let attrs = [2, 2, 4, 0];
+        let mesh = Mesh::new(buffer.as_ptr(), 0, attrs.as_ptr());
+        Self {
+            capacity,
+            buffer,
+            mesh,
+        }
+    }
+
+    fn vertex(&mut self, position: Vec2, u: f32, v: f32, color: Vec4) {
+        self.buffer.extend_from_slice(&[position.x, position.y, u, v, color.x, color.y, color.z, color.w]);
+    }
+
+    /// Четырёхугольник по углам: левый верхний, правый верхний, правый нижний, левый нижний.
+    /// Строки текстур хранятся снизу вверх, поэтому верх картинки - `v2`
+    pub fn quad(&mut self, corners: [Vec2; 4], uv: UvRegion, color: Vec4) {
+        if self.buffer.len() >= self.capacity * SB_VERTEX_SIZE * 6 {
+            return;
+        }
+        let [a, b, c, d] = corners;
+        self.vertex(a, uv.u1, uv.v2, color);
+        self.vertex(b, uv.u2, uv.v2, color);
+        self.vertex(c, uv.u2, uv.v1, color);
+
+        self.vertex(a, uv.u1, uv.v2, color);
+        self.vertex(c, uv.u2, uv.v1, color);
+        self.vertex(d, uv.u1, uv.v1, color);
+    }
+
+    pub fn sprite(&mut self, x: f32, y: f32, w: f32, h: f32, uv: UvRegion, color: Vec4) {
+        let corners = [Vec2::new(x, y), Vec2::new(x + w, y), Vec2::new(x + w, y + h), Vec2::new(x, y + h)];
+        self.quad(corners, uv, color);
+    }
+
+    pub fn rect(&mut self, x: f32, y: f32, w: f32, h: f32, color: Vec4) {
+        self.sprite(x, y, w, h, NO_TEXTURE, color);
+    }
+
+    /// Рамка толщиной `border` внутрь прямоугольника
+    pub fn frame(&mut self, x: f32, y: f32, w: f32, h: f32, border: f32, color: Vec4) {
+        self.rect(x, y, w, border, color);
+        self.rect(x, y + h - border, w, border, color);
+        self.rect(x, y + border, border, h - border * 2.0, color);
+        self.rect(x + w - border, y + border, border, h - border * 2.0, color);
+    }
+
+    pub fn render(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        self.mesh.reload(self.buffer.as_ptr(), self.buffer.len() / SB_VERTEX_SIZE);
+        self.mesh.draw(gl::TRIANGLES);
+        self.buffer.clear();
+    }
+}
@@ -1,8 +1,14 @@
+use crate::assets::Atlas;
 use crate::voxels::{ BlockRegistry, Chunk, Voxel, CHUNK_D, CHUNK_H, CHUNK_W };
 
+#[cfg(feature = "graphics")]
 use super::mesh::Mesh;
 
-const VERTEX_SIZE: usize = 3 +2 + 4;
+/// Вершина меша чанка: позиция (3), UV (2), свет R, G, B, S (4)
+pub const VERTEX_SIZE: usize = 3 +2 + 4;
+/// Атрибуты вершины для `Mesh::new`, 0 - терминатор
+#[cfg(feature = "graphics")]
+pub const VERTEX_ATTRS: [i32; 4] = [3, 2, 4, 0];
 
 fn cdiv(x: isize, a: isize) -> isize {
     if x < 0 { x / a - 1 } else { x / a }
@@ -80,6 +86,74 @@ fn is_blocked(x: isize, y: isize, z: isize, blocks: &BlockRegistry, group: u8, c
     }
 }
 
+/// Высота блока в клетке в долях блока; жидкость под той же жидкостью
+/// заполняет клетку целиком
+fn block_height(x: isize, y: isize, z: isize, blocks: &BlockRegistry, chunks: &[Option<Chunk>]) -> f32 {
+    let Some(current) = voxel(x, y, z, chunks) else {
+        return 1.0;
+    };
+    let Some(block) = blocks.get(current.id) else {
+        return 1.0;
+    };
+    if block.fluid.is_some() && voxel(x, y + 1, z, chunks).is_some_and(|above| above.id == current.id) {
+        1.0
+    } else {
+        block.height(current.states)
+    }
+}
+
+
+/// Видимая часть боковой грани, доли блока от его низа
+#[derive(Clone, Copy)]
+struct Span {
+    bottom: f32,
+    top: f32,
+}
+
+
+impl Span {
+    /// Координаты `y` и `v` низа и верха грани блока на высоте `y`.
+    /// Целая грань считается так же, как до жидкостей
+    fn place(self, y: isize, v1: f32, v2: f32) -> (f32, f32, f32, f32) {
+        let (yb, vb) = if self.bottom > 0.0 {
+            (y as f32 - 0.5 + self.bottom, v1 + (v2 - v1) * self.bottom)
+        } else {
+            (y as f32 - 0.5_f32, v1)
+        };
+        let (yt, vt) = if self.top < 1.0 {
+            (y as f32 - 0.5 + self.top, v1 + (v2 - v1) * self.top)
+        } else {
+            (y as f32 + 0.5_f32, v2)
+        };
+        (yb, yt, vb, vt)
+    }
+}
+
+
+/// Часть боковой грани к соседу `x, y, z`, которую надо рисовать. Рядом
+/// с таким же блоком видно только то, что выше соседа: у целых блоков ничего
+#[allow(clippy::too_many_arguments)]
+fn side_span(
+    current: &Voxel,
+    height: f32,
+    x: isize,
+    y: isize,
+    z: isize,
+    blocks: &BlockRegistry,
+    group: u8,
+    chunks: &[Option<Chunk>]
+) -> Option<Span> {
+    if voxel(x, y, z, chunks).is_some_and(|side| side.id == current.id) {
+        let side = block_height(x, y, z, blocks, chunks);
+        return (side < height).then_some(Span { bottom: side, top: height });
+    }
+    if is_blocked(x, y, z, blocks, group, chunks) {
+        None
+    } else {
+        Some(Span { bottom: 0.0, top: height })
+    }
+}
+
 
 fn vertex(
     buffer: &mut Vec<f32>,
@@ -104,13 +178,9 @@ fn vertex(
     buffer.push(s);
 }
 
-fn setup_uv(index: usize, uvsize: f32) -> (f32, f32, f32, f32) {
-        let u1 = ((index % 16) as f32) * uvsize;
-        let v1 = 1.0 - ((1 + index / 16) as f32) * uvsize;
-        let u2 = u1 + uvsize;
-        let v2 = v1 + uvsize;
-        (u1, v1, u2, v2)
-
+fn setup_uv(index: usize, atlas: &Atlas) -> (f32, f32, f32, f32) {
+    let region = atlas.uv(index);
+    (region.u1, region.v1, region.u2, region.v2)
 }
 
 pub struct VoxelRenderer {
@@ -124,12 +194,27 @@ impl VoxelRenderer {
         }
     }
 
+    /// Строит меш чанка и загружает его в видеопамять
+    #[cfg(feature = "graphics")]
     pub fn render(
         &mut self,
         chunk: &Chunk,
         chunks: &Vec<Option<Chunk>>,
-        blocks: &BlockRegistry
+        blocks: &BlockRegistry,
+        atlas: &Atlas
     ) -> Mesh {
+        let buffer = self.build(chunk, chunks, blocks, atlas);
+        Mesh::new(buffer.as_ptr(), buffer.len() / VERTEX_SIZE, VERTEX_ATTRS.as_ptr())
+    }
+
+    /// Строит вершины меша чанка на CPU, без обращения к OpenGL
+    pub fn build(
+        &mut self,
+        chunk: &Chunk,
+        chunks: &Vec<Option<Chunk>>,
+        blocks: &BlockRegistry,
+        atlas: &Atlas
+    ) -> &[f32] {
         self.buffer.clear();
 
         for y in 0_..CHUNK_H {
@@ -144,86 +229,88 @@ impl VoxelRenderer {
 
                     
                     let mut l;
-                    let uvsize = 1.0 / 16.0;
 
                     let (x, y, z) = (x as isize, y as isize, z as isize);
+                    // Жидкость и посевы ниже полного блока, их верх опускается
+                    let height = block_height(x, y, z, blocks, chunks);
+                    let top = if height < 1.0 { y as f32 - 0.5 + height } else { y as f32 + 0.5 };
                     
                     let block = blocks.blocks.get(id as usize);
                     
                     if let Some(Some(block)) = block {
                     let group = block.draw_group;
-                    if !is_blocked(x,y+1,z, &blocks, group, &chunks){
+                    if height < 1.0 || !is_blocked(x,y+1,z, blocks, group, chunks){
                         //l = 1.0_f32;
 
-                        let (u1, v1, u2, v2) = setup_uv(block.texture_faces[3] as usize, uvsize);
+                        let (u1, v1, u2, v2) = setup_uv(block.texture_faces[3] as usize, atlas);
     
-                        let lr = light(x,y+1,z, 0, &chunks) as f32 / 15.0_f32;
-                        let lg = light(x,y+1,z, 1, &chunks) as f32 / 15.0_f32;
-                        let lb = light(x,y+1,z, 2, &chunks) as f32 / 15.0_f32;
-                        let ls = light(x,y+1,z, 3, &chunks) as f32 / 15.0_f32;
+                        let lr = light(x,y+1,z, 0, chunks) as f32 / 15.0_f32;
+                        let lg = light(x,y+1,z, 1, chunks) as f32 / 15.0_f32;
+                        let lb = light(x,y+1,z, 2, chunks) as f32 / 15.0_f32;
+                        let ls = light(x,y+1,z, 3, chunks) as f32 / 15.0_f32;
     
-                        let lr0 = (light(x-1,y+1,z,0, &chunks) as f32 + lr*30_f32 + light(x-1,y+1,z-1,0, &chunks) as f32 + light(x,y+1,z-1,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lr1 = (light(x-1,y+1,z,0, &chunks) as f32 + lr*30_f32 + light(x-1,y+1,z+1,0, &chunks) as f32 + light(x,y+1,z+1,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lr2 = (light(x+1,y+1,z,0, &chunks) as f32 + lr*30_f32 + light(x+1,y+1,z+1,0, &chunks) as f32 + light(x,y+1,z+1,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lr3 = (light(x+1,y+1,z,0, &chunks) as f32 + lr*30_f32 + light(x+1,y+1,z-1,0, &chunks) as f32 + light(x,y+1,z-1,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lr0 = (light(x-1,y+1,z,0, chunks) as f32 + lr*30_f32 + light(x-1,y+1,z-1,0, chunks) as f32 + light(x,y+1,z-1,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lr1 = (light(x-1,y+1,z,0, chunks) as f32 + lr*30_f32 + light(x-1,y+1,z+1,0, chunks) as f32 + light(x,y+1,z+1,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lr2 = (light(x+1,y+1,z,0, chunks) as f32 + lr*30_f32 + light(x+1,y+1,z+1,0, chunks) as f32 + light(x,y+1,z+1,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lr3 = (light(x+1,y+1,z,0, chunks) as f32 + lr*30_f32 + light(x+1,y+1,z-1,0, chunks) as f32 + light(x,y+1,z-1,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
     
-                        let lg0 = (light(x-1,y+1,z,1, &chunks) as f32 + lg*30_f32 + light(x-1,y+1,z-1,1, &chunks) as f32 + light(x,y+1,z-1,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lg1 = (light(x-1,y+1,z,1, &chunks) as f32 + lg*30. + light(x-1,y+1,z+1,1, &chunks) as f32 + light(x,y+1,z+1,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lg2 = (light(x+1,y+1,z,1, &chunks) as f32 + lg*30. + light(x+1,y+1,z+1,1, &chunks) as f32 + light(x,y+1,z+1,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lg3 = (light(x+1,y+1,z,1, &chunks) as f32 + lg*30. + light(x+1,y+1,z-1,1, &chunks) as f32 + light(x,y+1,z-1,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lg0 = (light(x-1,y+1,z,1, chunks) as f32 + lg*30_f32 + light(x-1,y+1,z-1,1, chunks) as f32 + light(x,y+1,z-1,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lg1 = (light(x-1,y+1,z,1, chunks) as f32 + lg*30. + light(x-1,y+1,z+1,1, chunks) as f32 + light(x,y+1,z+1,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lg2 = (light(x+1,y+1,z,1, chunks) as f32 + lg*30. + light(x+1,y+1,z+1,1, chunks) as f32 + light(x,y+1,z+1,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lg3 = (light(x+1,y+1,z,1, chunks) as f32 + lg*30. + light(x+1,y+1,z-1,1, chunks) as f32 + light(x,y+1,z-1,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
     
-                        let lb0 = (light(x-1,y+1,z,2, &chunks) as f32 + lb*30. + light(x-1,y+1,z-1,2, &chunks) as f32 + light(x,y+1,z-1,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lb1 = (light(x-1,y+1,z,2, &chunks) as f32 + lb*30. + light(x-1,y+1,z+1,2, &chunks) as f32 + light(x,y+1,z+1,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lb2 = (light(x+1,y+1,z,2, &chunks) as f32 + lb*30. + light(x+1,y+1,z+1,2, &chunks) as f32 + light(x,y+1,z+1,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lb3 = (light(x+1,y+1,z,2, &chunks) as f32 + lb*30. + light(x+1,y+1,z-1,2, &chunks) as f32 + light(x,y+1,z-1,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lb0 = (light(x-1,y+1,z,2, chunks) as f32 + lb*30. + light(x-1,y+1,z-1,2, chunks) as f32 + light(x,y+1,z-1,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lb1 = (light(x-1,y+1,z,2, chunks) as f32 + lb*30. + light(x-1,y+1,z+1,2, chunks) as f32 + light(x,y+1,z+1,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lb2 = (light(x+1,y+1,z,2, chunks) as f32 + lb*30. + light(x+1,y+1,z+1,2, chunks) as f32 + light(x,y+1,z+1,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lb3 = (light(x+1,y+1,z,2, chunks) as f32 + lb*30. + light(x+1,y+1,z-1,2, chunks) as f32 + light(x,y+1,z-1,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
     
-                        let ls0 = (light(x-1,y+1,z,3, &chunks) as f32 + ls*30. + light(x-1,y+1,z-1,3, &chunks) as f32 + light(x,y+1,z-1,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let ls1 = (light(x-1,y+1,z,3, &chunks) as f32 + ls*30. + light(x-1,y+1,z+1,3, &chunks) as f32 + light(x,y+1,z+1,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let ls2 = (light(x+1,y+1,z,3, &chunks) as f32 + ls*30. + light(x+1,y+1,z+1,3, &chunks) as f32 + light(x,y+1,z+1,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let ls3 = (light(x+1,y+1,z,3, &chunks) as f32 + ls*30. + light(x+1,y+1,z-1,3, &chunks) as f32 + light(x,y+1,z-1,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let ls0 = (light(x-1,y+1,z,3, chunks) as f32 + ls*30. + light(x-1,y+1,z-1,3, chunks) as f32 + light(x,y+1,z-1,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let ls1 = (light(x-1,y+1,z,3, chunks) as f32 + ls*30. + light(x-1,y+1,z+1,3, chunks) as f32 + light(x,y+1,z+1,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let ls2 = (light(x+1,y+1,z,3, chunks) as f32 + ls*30. + light(x+1,y+1,z+1,3, chunks) as f32 + light(x,y+1,z+1,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let ls3 = (light(x+1,y+1,z,3, chunks) as f32 + ls*30. + light(x+1,y+1,z-1,3, chunks) as f32 + light(x,y+1,z-1,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
     
-                        vertex(&mut self.buffer, x as f32-0.5_f32, y as f32+0.5_f32, z as f32-0.5_f32, u2,v1, lr0, lg0, lb0, ls0);                         
-                        vertex(&mut self.buffer, x as f32-0.5_f32, y as f32+0.5_f32, z as f32+0.5_f32, u2,v2, lr1, lg1, lb1, ls1);                         
-                        vertex(&mut self.buffer, x as f32+0.5_f32, y as f32+0.5_f32, z as f32+0.5_f32, u1,v2, lr2, lg2, lb2, ls2);                              
+                        vertex(&mut self.buffer, x as f32-0.5_f32, top, z as f32-0.5_f32, u2,v1, lr0, lg0, lb0, ls0);                         
+                        vertex(&mut self.buffer, x as f32-0.5_f32, top, z as f32+0.5_f32, u2,v2, lr1, lg1, lb1, ls1);                         
+                        vertex(&mut self.buffer, x as f32+0.5_f32, top, z as f32+0.5_f32, u1,v2, lr2, lg2, lb2, ls2);                              
                         
-                        vertex(&mut self.buffer, x as f32-0.5_f32, y as f32+0.5_f32, z as f32-0.5_f32, u2,v1, lr0, lg0, lb0, ls0);                         
-                        vertex(&mut self.buffer, x as f32+0.5_f32, y as f32+0.5_f32, z as f32+0.5_f32, u1,v2, lr2, lg2, lb2, ls2);                         
-                        vertex(&mut self.buffer, x as f32+0.5_f32, y as f32+0.5_f32, z as f32-0.5_f32, u1,v1, lr3, lg3, lb3, ls3);
+                        vertex(&mut self.buffer, x as f32-0.5_f32, top, z as f32-0.5_f32, u2,v1, lr0, lg0, lb0, ls0);                         
+                        vertex(&mut self.buffer, x as f32+0.5_f32, top, z as f32+0.5_f32, u1,v2, lr2, lg2, lb2, ls2);                         
+                        vertex(&mut self.buffer, x as f32+0.5_f32, top, z as f32-0.5_f32, u1,v1, lr3, lg3, lb3, ls3);
                     }
                 }
 
                     if let Some(Some(block)) = block {
                     let group = block.draw_group;
-                    if !is_blocked(x,y-1,z, &blocks, group, &chunks){
+                    if !is_blocked(x,y-1,z, blocks, group, chunks){
                         //l = 0.75_f32;
 
                         
-                        let (u1, v1, u2, v2) = setup_uv(block.texture_faces[2] as usize, uvsize);
+                        let (u1, v1, u2, v2) = setup_uv(block.texture_faces[2] as usize, atlas);
     
-                        let lr = light(x,y-1,z, 0, &chunks) as f32 / 15.0_f32;
-                        let lg = light(x,y-1,z, 1, &chunks) as f32 / 15.0_f32;
-                        let lb = light(x,y-1,z, 2, &chunks) as f32 / 15.0_f32;
-                        let ls = light(x,y-1,z, 3, &chunks) as f32 / 15.0_f32;
+                        let lr = light(x,y-1,z, 0, chunks) as f32 / 15.0_f32;
+                        let lg = light(x,y-1,z, 1, chunks) as f32 / 15.0_f32;
+                        let lb = light(x,y-1,z, 2, chunks) as f32 / 15.0_f32;
+                        let ls = light(x,y-1,z, 3, chunks) as f32 / 15.0_f32;
     
-                        let lr0 = (light(x-1,y-1,z-1,0, &chunks) as f32 + lr*30. + light(x-1,y-1,z,0, &chunks) as f32 + light(x,y-1,z-1,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lr1 = (light(x+1,y-1,z+1,0, &chunks) as f32 + lr*30. + light(x+1,y-1,z,0, &chunks) as f32 + light(x,y-1,z+1,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lr2 = (light(x-1,y-1,z+1,0, &chunks) as f32 + lr*30. + light(x-1,y-1,z,0, &chunks) as f32 + light(x,y-1,z+1,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lr3 = (light(x+1,y-1,z-1,0, &chunks) as f32 + lr*30. + light(x+1,y-1,z,0, &chunks) as f32 + light(x,y-1,z-1,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lr0 = (light(x-1,y-1,z-1,0, chunks) as f32 + lr*30. + light(x-1,y-1,z,0, chunks) as f32 + light(x,y-1,z-1,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lr1 = (light(x+1,y-1,z+1,0, chunks) as f32 + lr*30. + light(x+1,y-1,z,0, chunks) as f32 + light(x,y-1,z+1,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lr2 = (light(x-1,y-1,z+1,0, chunks) as f32 + lr*30. + light(x-1,y-1,z,0, chunks) as f32 + light(x,y-1,z+1,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lr3 = (light(x+1,y-1,z-1,0, chunks) as f32 + lr*30. + light(x+1,y-1,z,0, chunks) as f32 + light(x,y-1,z-1,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
     
-                        let lg0 = (light(x-1,y-1,z-1,1, &chunks) as f32 + lg*30. + light(x-1,y-1,z,1, &chunks) as f32 + light(x,y-1,z-1,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lg1 = (light(x+1,y-1,z+1,1, &chunks) as f32 + lg*30. + light(x+1,y-1,z,1, &chunks) as f32 + light(x,y-1,z+1,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lg2 = (light(x-1,y-1,z+1,1, &chunks) as f32 + lg*30. + light(x-1,y-1,z,1, &chunks) as f32 + light(x,y-1,z+1,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lg3 = (light(x+1,y-1,z-1,1, &chunks) as f32 + lg*30. + light(x+1,y-1,z,1, &chunks) as f32 + light(x,y-1,z-1,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lg0 = (light(x-1,y-1,z-1,1, chunks) as f32 + lg*30. + light(x-1,y-1,z,1, chunks) as f32 + light(x,y-1,z-1,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lg1 = (light(x+1,y-1,z+1,1, chunks) as f32 + lg*30. + light(x+1,y-1,z,1, chunks) as f32 + light(x,y-1,z+1,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lg2 = (light(x-1,y-1,z+1,1, chunks) as f32 + lg*30. + light(x-1,y-1,z,1, chunks) as f32 + light(x,y-1,z+1,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lg3 = (light(x+1,y-1,z-1,1, chunks) as f32 + lg*30. + light(x+1,y-1,z,1, chunks) as f32 + light(x,y-1,z-1,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
     
-                        let lb0 = (light(x-1,y-1,z-1,2, &chunks) as f32 + lb*30. + light(x-1,y-1,z,2, &chunks) as f32 + light(x,y-1,z-1,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lb1 = (light(x+1,y-1,z+1,2, &chunks) as f32 + lb*30. + light(x+1,y-1,z,2, &chunks) as f32 + light(x,y-1,z+1,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lb2 = (light(x-1,y-1,z+1,2, &chunks) as f32 + lb*30. + light(x-1,y-1,z,2, &chunks) as f32 + light(x,y-1,z+1,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lb3 = (light(x+1,y-1,z-1,2, &chunks) as f32 + lb*30. + light(x+1,y-1,z,2, &chunks) as f32 + light(x,y-1,z-1,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lb0 = (light(x-1,y-1,z-1,2, chunks) as f32 + lb*30. + light(x-1,y-1,z,2, chunks) as f32 + light(x,y-1,z-1,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lb1 = (light(x+1,y-1,z+1,2, chunks) as f32 + lb*30. + light(x+1,y-1,z,2, chunks) as f32 + light(x,y-1,z+1,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lb2 = (light(x-1,y-1,z+1,2, chunks) as f32 + lb*30. + light(x-1,y-1,z,2, chunks) as f32 + light(x,y-1,z+1,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lb3 = (light(x+1,y-1,z-1,2, chunks) as f32 + lb*30. + light(x+1,y-1,z,2, chunks) as f32 + light(x,y-1,z-1,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
     
-                        let ls0 = (light(x-1,y-1,z-1,3, &chunks) as f32 + ls*30. + light(x-1,y-1,z,3, &chunks) as f32 + light(x,y-1,z-1,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let ls1 = (light(x+1,y-1,z+1,3, &chunks) as f32 + ls*30. + light(x+1,y-1,z,3, &chunks) as f32 + light(x,y-1,z+1,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let ls2 = (light(x-1,y-1,z+1,3, &chunks) as f32 + ls*30. + light(x-1,y-1,z,3, &chunks) as f32 + light(x,y-1,z+1,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let ls3 = (light(x+1,y-1,z-1,3, &chunks) as f32 + ls*30. + light(x+1,y-1,z,3, &chunks) as f32 + light(x,y-1,z-1,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let ls0 = (light(x-1,y-1,z-1,3, chunks) as f32 + ls*30. + light(x-1,y-1,z,3, chunks) as f32 + light(x,y-1,z-1,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let ls1 = (light(x+1,y-1,z+1,3, chunks) as f32 + ls*30. + light(x+1,y-1,z,3, chunks) as f32 + light(x,y-1,z+1,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let ls2 = (light(x-1,y-1,z+1,3, chunks) as f32 + ls*30. + light(x-1,y-1,z,3, chunks) as f32 + light(x,y-1,z+1,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let ls3 = (light(x+1,y-1,z-1,3, chunks) as f32 + ls*30. + light(x+1,y-1,z,3, chunks) as f32 + light(x,y-1,z-1,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
     
                         vertex(&mut self.buffer, x as f32-0.5_f32, y as f32-0.5_f32, z as f32-0.5_f32, u1,v1, lr0,lg0,lb0,ls0);                         
                         vertex(&mut self.buffer, x as f32+0.5_f32, y as f32-0.5_f32, z as f32+0.5_f32, u2,v2, lr1,lg1,lb1,ls1);                         
@@ -237,173 +324,177 @@ impl VoxelRenderer {
     
                     if let Some(Some(block)) = block {
                     let group = block.draw_group;
-                    if !is_blocked(x+1,y,z, &blocks, group,&chunks){
+                    if let Some(span) = side_span(voxel, height, x+1, y, z, blocks, group, chunks) {
                         //l = 0.95_f32;
 
-                        let (u1, v1, u2, v2) = setup_uv(block.texture_faces[1] as usize, uvsize);
-    
-                        let lr = light(x+1,y,z, 0, &chunks) as f32 / 15.0_f32;
-                        let lg = light(x+1,y,z, 1, &chunks) as f32 / 15.0_f32;
-                        let lb = light(x+1,y,z, 2, &chunks) as f32 / 15.0_f32;
-                        let ls = light(x+1,y,z, 3, &chunks) as f32 / 15.0_f32;
-    
-                        let lr0 = (light(x+1,y-1,z-1,0, &chunks) as f32 + lr*30. + light(x+1,y,z-1,0, &chunks) as f32 + light(x+1,y-1,z,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lr1 = (light(x+1,y+1,z-1,0, &chunks) as f32 + lr*30. + light(x+1,y,z-1,0, &chunks) as f32 + light(x+1,y+1,z,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lr2 = (light(x+1,y+1,z+1,0, &chunks) as f32 + lr*30. + light(x+1,y,z+1,0, &chunks) as f32 + light(x+1,y+1,z,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lr3 = (light(x+1,y-1,z+1,0, &chunks) as f32 + lr*30. + light(x+1,y,z+1,0, &chunks) as f32 + light(x+1,y-1,z,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-    
-                        let lg0 = (light(x+1,y-1,z-1,1, &chunks) as f32 + lg*30. + light(x+1,y,z-1,1, &chunks) as f32 + light(x+1,y-1,z,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lg1 = (light(x+1,y+1,z-1,1, &chunks) as f32 + lg*30. + light(x+1,y,z-1,1, &chunks) as f32 + light(x+1,y+1,z,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lg2 = (light(x+1,y+1,z+1,1, &chunks) as f32 + lg*30. + light(x+1,y,z+1,1, &chunks) as f32 + light(x+1,y+1,z,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lg3 = (light(x+1,y-1,z+1,1, &chunks) as f32 + lg*30. + light(x+1,y,z+1,1, &chunks) as f32 + light(x+1,y-1,z,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-    
-                        let lb0 = (light(x+1,y-1,z-1,2, &chunks) as f32 + lb*30. + light(x+1,y,z-1,2, &chunks) as f32 + light(x+1,y-1,z,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lb1 = (light(x+1,y+1,z-1,2, &chunks) as f32 + lb*30. + light(x+1,y,z-1,2, &chunks) as f32 + light(x+1,y+1,z,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lb2 = (light(x+1,y+1,z+1,2, &chunks) as f32 + lb*30. + light(x+1,y,z+1,2, &chunks) as f32 + light(x+1,y+1,z,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lb3 = (light(x+1,y-1,z+1,2, &chunks) as f32 + lb*30. + light(x+1,y,z+1,2, &chunks) as f32 + light(x+1,y-1,z,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-    
-                        let ls0 = (light(x+1,y-1,z-1,3, &chunks) as f32 + ls*30. + light(x+1,y,z-1,3, &chunks) as f32 + light(x+1,y-1,z,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let ls1 = (light(x+1,y+1,z-1,3, &chunks) as f32 + ls*30. + light(x+1,y,z-1,3, &chunks) as f32 + light(x+1,y+1,z,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let ls2 = (light(x+1,y+1,z+1,3, &chunks) as f32 + ls*30. + light(x+1,y,z+1,3, &chunks) as f32 + light(x+1,y+1,z,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let ls3 = (light(x+1,y-1,z+1,3, &chunks) as f32 + ls*30. + light(x+1,y,z+1,3, &chunks) as f32 + light(x+1,y-1,z,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-    
-                        vertex(&mut self.buffer, x as f32+0.5_f32, y as f32-0.5_f32, z as f32-0.5_f32, u2,v1, lr0,lg0,lb0,ls0);                         
-                        vertex(&mut self.buffer, x as f32+0.5_f32, y as f32+0.5_f32, z as f32-0.5_f32, u2,v2, lr1,lg1,lb1,ls1);                         
-                        vertex(&mut self.buffer, x as f32+0.5_f32, y as f32+0.5_f32, z as f32+0.5_f32, u1,v2, lr2,lg2,lb2,ls2);                              
+                        let (u1, v1, u2, v2) = setup_uv(block.texture_faces[1] as usize, atlas);
+                        let (yb, yt, vb, vt) = span.place(y, v1, v2);
+    
+                        let lr = light(x+1,y,z, 0, chunks) as f32 / 15.0_f32;
+                        let lg = light(x+1,y,z, 1, chunks) as f32 / 15.0_f32;
+                        let lb = light(x+1,y,z, 2, chunks) as f32 / 15.0_f32;
+                        let ls = light(x+1,y,z, 3, chunks) as f32 / 15.0_f32;
+    
+                        let lr0 = (light(x+1,y-1,z-1,0, chunks) as f32 + lr*30. + light(x+1,y,z-1,0, chunks) as f32 + light(x+1,y-1,z,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lr1 = (light(x+1,y+1,z-1,0, chunks) as f32 + lr*30. + light(x+1,y,z-1,0, chunks) as f32 + light(x+1,y+1,z,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lr2 = (light(x+1,y+1,z+1,0, chunks) as f32 + lr*30. + light(x+1,y,z+1,0, chunks) as f32 + light(x+1,y+1,z,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lr3 = (light(x+1,y-1,z+1,0, chunks) as f32 + lr*30. + light(x+1,y,z+1,0, chunks) as f32 + light(x+1,y-1,z,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+    
+                        let lg0 = (light(x+1,y-1,z-1,1, chunks) as f32 + lg*30. + light(x+1,y,z-1,1, chunks) as f32 + light(x+1,y-1,z,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lg1 = (light(x+1,y+1,z-1,1, chunks) as f32 + lg*30. + light(x+1,y,z-1,1, chunks) as f32 + light(x+1,y+1,z,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lg2 = (light(x+1,y+1,z+1,1, chunks) as f32 + lg*30. + light(x+1,y,z+1,1, chunks) as f32 + light(x+1,y+1,z,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lg3 = (light(x+1,y-1,z+1,1, chunks) as f32 + lg*30. + light(x+1,y,z+1,1, chunks) as f32 + light(x+1,y-1,z,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+    
+                        let lb0 = (light(x+1,y-1,z-1,2, chunks) as f32 + lb*30. + light(x+1,y,z-1,2, chunks) as f32 + light(x+1,y-1,z,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lb1 = (light(x+1,y+1,z-1,2, chunks) as f32 + lb*30. + light(x+1,y,z-1,2, chunks) as f32 + light(x+1,y+1,z,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lb2 = (light(x+1,y+1,z+1,2, chunks) as f32 + lb*30. + light(x+1,y,z+1,2, chunks) as f32 + light(x+1,y+1,z,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lb3 = (light(x+1,y-1,z+1,2, chunks) as f32 + lb*30. + light(x+1,y,z+1,2, chunks) as f32 + light(x+1,y-1,z,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+    
+                        let ls0 = (light(x+1,y-1,z-1,3, chunks) as f32 + ls*30. + light(x+1,y,z-1,3, chunks) as f32 + light(x+1,y-1,z,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let ls1 = (light(x+1,y+1,z-1,3, chunks) as f32 + ls*30. + light(x+1,y,z-1,3, chunks) as f32 + light(x+1,y+1,z,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let ls2 = (light(x+1,y+1,z+1,3, chunks) as f32 + ls*30. + light(x+1,y,z+1,3, chunks) as f32 + light(x+1,y+1,z,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let ls3 = (light(x+1,y-1,z+1,3, chunks) as f32 + ls*30. + light(x+1,y,z+1,3, chunks) as f32 + light(x+1,y-1,z,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+    
+                        vertex(&mut self.buffer, x as f32+0.5_f32, yb, z as f32-0.5_f32, u2,vb, lr0,lg0,lb0,ls0);                         
+                        vertex(&mut self.buffer, x as f32+0.5_f32, yt, z as f32-0.5_f32, u2,vt, lr1,lg1,lb1,ls1);                         
+                        vertex(&mut self.buffer, x as f32+0.5_f32, yt, z as f32+0.5_f32, u1,vt, lr2,lg2,lb2,ls2);                              
                         
-                        vertex(&mut self.buffer, x as f32+0.5_f32, y as f32-0.5_f32, z as f32-0.5_f32, u2,v1, lr0,lg0,lb0,ls0);                         
-                        vertex(&mut self.buffer, x as f32+0.5_f32, y as f32+0.5_f32, z as f32+0.5_f32, u1,v2, lr2,lg2,lb2,ls2);                         
-                        vertex(&mut self.buffer, x as f32+0.5_f32, y as f32-0.5_f32, z as f32+0.5_f32, u1,v1, lr3,lg3,lb3,ls3);
+                        vertex(&mut self.buffer, x as f32+0.5_f32, yb, z as f32-0.5_f32, u2,vb, lr0,lg0,lb0,ls0);                         
+                        vertex(&mut self.buffer, x as f32+0.5_f32, yt, z as f32+0.5_f32, u1,vt, lr2,lg2,lb2,ls2);                         
+                        vertex(&mut self.buffer, x as f32+0.5_f32, yb, z as f32+0.5_f32, u1,vb, lr3,lg3,lb3,ls3);
                     }
                 }
                     if let Some(Some(block)) = block {
                     let group = block.draw_group;
-                    if !is_blocked(x-1,y,z, &blocks, group,&chunks){
+                    if let Some(span) = side_span(voxel, height, x-1, y, z, blocks, group, chunks) {
                         //l = 0.85_f32;
 
-                        let (u1, v1, u2, v2) = setup_uv(block.texture_faces[0] as usize, uvsize);
-    
-                        let lr = light(x-1,y,z, 0, &chunks) as f32 / 15.0_f32;
-                        let lg = light(x-1,y,z, 1, &chunks) as f32 / 15.0_f32;
-                        let lb = light(x-1,y,z, 2, &chunks) as f32 / 15.0_f32;
-                        let ls = light(x-1,y,z, 3, &chunks) as f32 / 15.0_f32;
-    
-                        let lr0 = (light(x-1,y-1,z-1,0, &chunks) as f32 + lr*30. + light(x-1,y,z-1,0, &chunks) as f32 + light(x-1,y-1,z,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lr1 = (light(x-1,y+1,z+1,0, &chunks) as f32 + lr*30. + light(x-1,y,z+1,0, &chunks) as f32 + light(x-1,y+1,z,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lr2 = (light(x-1,y+1,z-1,0, &chunks) as f32 + lr*30. + light(x-1,y,z-1,0, &chunks) as f32 + light(x-1,y+1,z,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lr3 = (light(x-1,y-1,z+1,0, &chunks) as f32 + lr*30. + light(x-1,y,z+1,0, &chunks) as f32 + light(x-1,y-1,z,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-    
-                        let lg0 = (light(x-1,y-1,z-1,1, &chunks) as f32 + lg*30. + light(x-1,y,z-1,1, &chunks) as f32 + light(x-1,y-1,z,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lg1 = (light(x-1,y+1,z+1,1, &chunks) as f32 + lg*30. + light(x-1,y,z+1,1, &chunks) as f32 + light(x-1,y+1,z,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lg2 = (light(x-1,y+1,z-1,1, &chunks) as f32 + lg*30. + light(x-1,y,z-1,1, &chunks) as f32 + light(x-1,y+1,z,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lg3 = (light(x-1,y-1,z+1,1, &chunks) as f32 + lg*30. + light(x-1,y,z+1,1, &chunks) as f32 + light(x-1,y-1,z,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-    
-                        let lb0 = (light(x-1,y-1,z-1,2, &chunks) as f32 + lb*30. + light(x-1,y,z-1,2, &chunks) as f32 + light(x-1,y-1,z,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lb1 = (light(x-1,y+1,z+1,2, &chunks) as f32 + lb*30. + light(x-1,y,z+1,2, &chunks) as f32 + light(x-1,y+1,z,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lb2 = (light(x-1,y+1,z-1,2, &chunks) as f32 + lb*30. + light(x-1,y,z-1,2, &chunks) as f32 + light(x-1,y+1,z,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lb3 = (light(x-1,y-1,z+1,2, &chunks) as f32 + lb*30. + light(x-1,y,z+1,2, &chunks) as f32 + light(x-1,y-1,z,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-    
-                        let ls0 = (light(x-1,y-1,z-1,3, &chunks) as f32 + ls*30. + light(x-1,y,z-1,3, &chunks) as f32 + light(x-1,y-1,z,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let ls1 = (light(x-1,y+1,z+1,3, &chunks) as f32 + ls*30. + light(x-1,y,z+1,3, &chunks) as f32 + light(x-1,y+1,z,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let ls2 = (light(x-1,y+1,z-1,3, &chunks) as f32 + ls*30. + light(x-1,y,z-1,3, &chunks) as f32 + light(x-1,y+1,z,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let ls3 = (light(x-1,y-1,z+1,3, &chunks) as f32 + ls*30. + light(x-1,y,z+1,3, &chunks) as f32 + light(x-1,y-1,z,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-    
-                        vertex(&mut self.buffer, x as f32-0.5_f32, y as f32-0.5_f32, z as f32-0.5_f32, u1,v1, lr0,lg0,lb0,ls0);                         
-                        vertex(&mut self.buffer, x as f32-0.5_f32, y as f32+0.5_f32, z as f32+0.5_f32, u2,v2, lr1,lg1,lb1,ls1);                         
-                        vertex(&mut self.buffer, x as f32-0.5_f32, y as f32+0.5_f32, z as f32-0.5_f32, u1,v2, lr2,lg2,lb2,ls2);                
-
-                        vertex(&mut self.buffer, x as f32-0.5_f32, y as f32-0.5_f32, z as f32-0.5_f32, u1,v1, lr0,lg0,lb0,ls0);                         
-                        vertex(&mut self.buffer, x as f32-0.5_f32, y as f32-0.5_f32, z as f32+0.5_f32, u2,v1, lr3,lg3,lb3,ls3);                         
-                        vertex(&mut self.buffer, x as f32-0.5_f32, y as f32+0.5_f32, z as f32+0.5_f32, u2,v2, lr1,lg1,lb1,ls1);
+                        let (u1, v1, u2, v2) = setup_uv(block.texture_faces[0] as usize, atlas);
+                        let (yb, yt, vb, vt) = span.place(y, v1, v2);
+    
+                        let lr = light(x-1,y,z, 0, chunks) as f32 / 15.0_f32;
+                        let lg = light(x-1,y,z, 1, chunks) as f32 / 15.0_f32;
+                        let lb = light(x-1,y,z, 2, chunks) as f32 / 15.0_f32;
+                        let ls = light(x-1,y,z, 3, chunks) as f32 / 15.0_f32;
+    
+                        let lr0 = (light(x-1,y-1,z-1,0, chunks) as f32 + lr*30. + light(x-1,y,z-1,0, chunks) as f32 + light(x-1,y-1,z,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lr1 = (light(x-1,y+1,z+1,0, chunks) as f32 + lr*30. + light(x-1,y,z+1,0, chunks) as f32 + light(x-1,y+1,z,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lr2 = (light(x-1,y+1,z-1,0, chunks) as f32 + lr*30. + light(x-1,y,z-1,0, chunks) as f32 + light(x-1,y+1,z,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lr3 = (light(x-1,y-1,z+1,0, chunks) as f32 + lr*30. + light(x-1,y,z+1,0, chunks) as f32 + light(x-1,y-1,z,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+    
+                        let lg0 = (light(x-1,y-1,z-1,1, chunks) as f32 + lg*30. + light(x-1,y,z-1,1, chunks) as f32 + light(x-1,y-1,z,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lg1 = (light(x-1,y+1,z+1,1, chunks) as f32 + lg*30. + light(x-1,y,z+1,1, chunks) as f32 + light(x-1,y+1,z,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lg2 = (light(x-1,y+1,z-1,1, chunks) as f32 + lg*30. + light(x-1,y,z-1,1, chunks) as f32 + light(x-1,y+1,z,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lg3 = (light(x-1,y-1,z+1,1, chunks) as f32 + lg*30. + light(x-1,y,z+1,1, chunks) as f32 + light(x-1,y-1,z,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+    
+                        let lb0 = (light(x-1,y-1,z-1,2, chunks) as f32 + lb*30. + light(x-1,y,z-1,2, chunks) as f32 + light(x-1,y-1,z,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lb1 = (light(x-1,y+1,z+1,2, chunks) as f32 + lb*30. + light(x-1,y,z+1,2, chunks) as f32 + light(x-1,y+1,z,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lb2 = (light(x-1,y+1,z-1,2, chunks) as f32 + lb*30. + light(x-1,y,z-1,2, chunks) as f32 + light(x-1,y+1,z,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lb3 = (light(x-1,y-1,z+1,2, chunks) as f32 + lb*30. + light(x-1,y,z+1,2, chunks) as f32 + light(x-1,y-1,z,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+    
+                        let ls0 = (light(x-1,y-1,z-1,3, chunks) as f32 + ls*30. + light(x-1,y,z-1,3, chunks) as f32 + light(x-1,y-1,z,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let ls1 = (light(x-1,y+1,z+1,3, chunks) as f32 + ls*30. + light(x-1,y,z+1,3, chunks) as f32 + light(x-1,y+1,z,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let ls2 = (light(x-1,y+1,z-1,3, chunks) as f32 + ls*30. + light(x-1,y,z-1,3, chunks) as f32 + light(x-1,y+1,z,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let ls3 = (light(x-1,y-1,z+1,3, chunks) as f32 + ls*30. + light(x-1,y,z+1,3, chunks) as f32 + light(x-1,y-1,z,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+    
+                        vertex(&mut self.buffer, x as f32-0.5_f32, yb, z as f32-0.5_f32, u1,vb, lr0,lg0,lb0,ls0);                         
+                        vertex(&mut self.buffer, x as f32-0.5_f32, yt, z as f32+0.5_f32, u2,vt, lr1,lg1,lb1,ls1);                         
+                        vertex(&mut self.buffer, x as f32-0.5_f32, yt, z as f32-0.5_f32, u1,vt, lr2,lg2,lb2,ls2);                
+
+                        vertex(&mut self.buffer, x as f32-0.5_f32, yb, z as f32-0.5_f32, u1,vb, lr0,lg0,lb0,ls0);                         
+                        vertex(&mut self.buffer, x as f32-0.5_f32, yb, z as f32+0.5_f32, u2,vb, lr3,lg3,lb3,ls3);                         
+                        vertex(&mut self.buffer, x as f32-0.5_f32, yt, z as f32+0.5_f32, u2,vt, lr1,lg1,lb1,ls1);
                     }
                 }
     
                     if let Some(Some(block)) = block {
                     let group = block.draw_group;
-                    if !is_blocked(x,y,z+1, &blocks, group,&chunks){
+                    if let Some(span) = side_span(voxel, height, x, y, z+1, blocks, group, chunks) {
                         l = 0.9_f32;
 
-                        let (u1, v1, u2, v2) = setup_uv(block.texture_faces[5] as usize, uvsize);
-    
-                        let lr = light(x,y,z+1, 0, &chunks) as f32 / 15.0_f32;
-                        let lg = light(x,y,z+1, 1, &chunks) as f32 / 15.0_f32;
-                        let lb = light(x,y,z+1, 2, &chunks) as f32 / 15.0_f32;
-                        let ls = light(x,y,z+1, 3, &chunks) as f32 / 15.0_f32;
-    
-                        let lr0 = l*(light(x-1,y-1,z+1,0, &chunks) as f32 + lr*30. + light(x,y-1,z+1,0, &chunks) as f32 + light(x-1,y,z+1,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lr1 = l*(light(x+1,y+1,z+1,0, &chunks) as f32 + lr*30. + light(x,y+1,z+1,0, &chunks) as f32 + light(x+1,y,z+1,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lr2 = l*(light(x-1,y+1,z+1,0, &chunks) as f32 + lr*30. + light(x,y+1,z+1,0, &chunks) as f32 + light(x-1,y,z+1,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lr3 = l*(light(x+1,y-1,z+1,0, &chunks) as f32 + lr*30. + light(x,y-1,z+1,0, &chunks) as f32 + light(x+1,y,z+1,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-    
-                        let lg0 = l*(light(x-1,y-1,z+1,1, &chunks) as f32 + lg*30. + light(x,y-1,z+1,1, &chunks) as f32 + light(x-1,y,z+1,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lg1 = l*(light(x+1,y+1,z+1,1, &chunks) as f32 + lg*30. + light(x,y+1,z+1,1, &chunks) as f32 + light(x+1,y,z+1,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lg2 = l*(light(x-1,y+1,z+1,1, &chunks) as f32 + lg*30. + light(x,y+1,z+1,1, &chunks) as f32 + light(x-1,y,z+1,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lg3 = l*(light(x+1,y-1,z+1,1, &chunks) as f32 + lg*30. + light(x,y-1,z+1,1, &chunks) as f32 + light(x+1,y,z+1,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-    
-                        let lb0 = l*(light(x-1,y-1,z+1,2, &chunks) as f32 + lb*30. + light(x,y-1,z+1,2, &chunks) as f32 + light(x-1,y,z+1,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lb1 = l*(light(x+1,y+1,z+1,2, &chunks) as f32 + lb*30. + light(x,y+1,z+1,2, &chunks) as f32 + light(x+1,y,z+1,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lb2 = l*(light(x-1,y+1,z+1,2, &chunks) as f32 + lb*30. + light(x,y+1,z+1,2, &chunks) as f32 + light(x-1,y,z+1,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lb3 = l*(light(x+1,y-1,z+1,2, &chunks) as f32 + lb*30. + light(x,y-1,z+1,2, &chunks) as f32 + light(x+1,y,z+1,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-    
-                        let ls0 = l*(light(x-1,y-1,z+1,3, &chunks) as f32 + ls*30. + light(x,y-1,z+1,3, &chunks) as f32 + light(x-1,y,z+1,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let ls1 = l*(light(x+1,y+1,z+1,3, &chunks) as f32 + ls*30. + light(x,y+1,z+1,3, &chunks) as f32 + light(x+1,y,z+1,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let ls2 = l*(light(x-1,y+1,z+1,3, &chunks) as f32 + ls*30. + light(x,y+1,z+1,3, &chunks) as f32 + light(x-1,y,z+1,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let ls3 = l*(light(x+1,y-1,z+1,3, &chunks) as f32 + ls*30. + light(x,y-1,z+1,3, &chunks) as f32 + light(x+1,y,z+1,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-    
-                        vertex(&mut self.buffer, x as f32-0.5_f32, y as f32-0.5_f32, z as f32+0.5_f32, u1,v1, lr0,lg0,lb0,ls0);                         
-                        vertex(&mut self.buffer, x as f32+0.5_f32, y as f32+0.5_f32, z as f32+0.5_f32, u2,v2, lr1,lg1,lb1,ls1);                         
-                        vertex(&mut self.buffer, x as f32-0.5_f32, y as f32+0.5_f32, z as f32+0.5_f32, u1,v2, lr2,lg2,lb2,ls2);   
-
-                        vertex(&mut self.buffer, x as f32-0.5_f32, y as f32-0.5_f32, z as f32+0.5_f32, u1,v1, lr0,lg0,lb0,ls0);                         
-                        vertex(&mut self.buffer, x as f32+0.5_f32, y as f32-0.5_f32, z as f32+0.5_f32, u2,v1, lr3,lg3,lb3,ls3);                         
-                        vertex(&mut self.buffer, x as f32+0.5_f32, y as f32+0.5_f32, z as f32+0.5_f32, u2,v2, lr1,lg1,lb1,ls1);
+                        let (u1, v1, u2, v2) = setup_uv(block.texture_faces[5] as usize, atlas);
+                        let (yb, yt, vb, vt) = span.place(y, v1, v2);
+    
+                        let lr = light(x,y,z+1, 0, chunks) as f32 / 15.0_f32;
+                        let lg = light(x,y,z+1, 1, chunks) as f32 / 15.0_f32;
+                        let lb = light(x,y,z+1, 2, chunks) as f32 / 15.0_f32;
+                        let ls = light(x,y,z+1, 3, chunks) as f32 / 15.0_f32;
+    
+                        let lr0 = l*(light(x-1,y-1,z+1,0, chunks) as f32 + lr*30. + light(x,y-1,z+1,0, chunks) as f32 + light(x-1,y,z+1,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lr1 = l*(light(x+1,y+1,z+1,0, chunks) as f32 + lr*30. + light(x,y+1,z+1,0, chunks) as f32 + light(x+1,y,z+1,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lr2 = l*(light(x-1,y+1,z+1,0, chunks) as f32 + lr*30. + light(x,y+1,z+1,0, chunks) as f32 + light(x-1,y,z+1,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lr3 = l*(light(x+1,y-1,z+1,0, chunks) as f32 + lr*30. + light(x,y-1,z+1,0, chunks) as f32 + light(x+1,y,z+1,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+    
+                        let lg0 = l*(light(x-1,y-1,z+1,1, chunks) as f32 + lg*30. + light(x,y-1,z+1,1, chunks) as f32 + light(x-1,y,z+1,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lg1 = l*(light(x+1,y+1,z+1,1, chunks) as f32 + lg*30. + light(x,y+1,z+1,1, chunks) as f32 + light(x+1,y,z+1,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lg2 = l*(light(x-1,y+1,z+1,1, chunks) as f32 + lg*30. + light(x,y+1,z+1,1, chunks) as f32 + light(x-1,y,z+1,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lg3 = l*(light(x+1,y-1,z+1,1, chunks) as f32 + lg*30. + light(x,y-1,z+1,1, chunks) as f32 + light(x+1,y,z+1,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+    
+                        let lb0 = l*(light(x-1,y-1,z+1,2, chunks) as f32 + lb*30. + light(x,y-1,z+1,2, chunks) as f32 + light(x-1,y,z+1,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lb1 = l*(light(x+1,y+1,z+1,2, chunks) as f32 + lb*30. + light(x,y+1,z+1,2, chunks) as f32 + light(x+1,y,z+1,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lb2 = l*(light(x-1,y+1,z+1,2, chunks) as f32 + lb*30. + light(x,y+1,z+1,2, chunks) as f32 + light(x-1,y,z+1,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lb3 = l*(light(x+1,y-1,z+1,2, chunks) as f32 + lb*30. + light(x,y-1,z+1,2, chunks) as f32 + light(x+1,y,z+1,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+    
+                        let ls0 = l*(light(x-1,y-1,z+1,3, chunks) as f32 + ls*30. + light(x,y-1,z+1,3, chunks) as f32 + light(x-1,y,z+1,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let ls1 = l*(light(x+1,y+1,z+1,3, chunks) as f32 + ls*30. + light(x,y+1,z+1,3, chunks) as f32 + light(x+1,y,z+1,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let ls2 = l*(light(x-1,y+1,z+1,3, chunks) as f32 + ls*30. + light(x,y+1,z+1,3, chunks) as f32 + light(x-1,y,z+1,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let ls3 = l*(light(x+1,y-1,z+1,3, chunks) as f32 + ls*30. + light(x,y-1,z+1,3, chunks) as f32 + light(x+1,y,z+1,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+    
+                        vertex(&mut self.buffer, x as f32-0.5_f32, yb, z as f32+0.5_f32, u1,vb, lr0,lg0,lb0,ls0);                         
+                        vertex(&mut self.buffer, x as f32+0.5_f32, yt, z as f32+0.5_f32, u2,vt, lr1,lg1,lb1,ls1);                         
+                        vertex(&mut self.buffer, x as f32-0.5_f32, yt, z as f32+0.5_f32, u1,vt, lr2,lg2,lb2,ls2);   
+
+                        vertex(&mut self.buffer, x as f32-0.5_f32, yb, z as f32+0.5_f32, u1,vb, lr0,lg0,lb0,ls0);                         
+                        vertex(&mut self.buffer, x as f32+0.5_f32, yb, z as f32+0.5_f32, u2,vb, lr3,lg3,lb3,ls3);                         
+                        vertex(&mut self.buffer, x as f32+0.5_f32, yt, z as f32+0.5_f32, u2,vt, lr1,lg1,lb1,ls1);
                     }
                 }
                     if let Some(Some(block)) = block {
                     let group = block.draw_group;
-                    if !is_blocked(x,y,z-1, &blocks, group,&chunks){
+                    if let Some(span) = side_span(voxel, height, x, y, z-1, blocks, group, chunks) {
                         l = 0.8_f32;
 
-                        let (u1, v1, u2, v2) = setup_uv(block.texture_faces[4] as usize, uvsize);
-    
-                        let lr = light(x,y,z-1, 0, &chunks) as f32 / 15.0_f32;
-                        let lg = light(x,y,z-1, 1, &chunks) as f32 / 15.0_f32;
-                        let lb = light(x,y,z-1, 2, &chunks) as f32 / 15.0_f32;
-                        let ls = light(x,y,z-1, 3, &chunks) as f32 / 15.0_f32;
-    
-                        let lr0 = l*(light(x-1,y-1,z-1,0, &chunks) as f32 + lr*30_f32 + light(x,y-1,z-1,0, &chunks) as f32 + light(x-1,y,z-1,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lr1 = l*(light(x-1,y+1,z-1,0, &chunks) as f32 + lr*30_f32 + light(x,y+1,z-1,0, &chunks) as f32 + light(x-1,y,z-1,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lr2 = l*(light(x+1,y+1,z-1,0, &chunks) as f32 + lr*30_f32 + light(x,y+1,z-1,0, &chunks) as f32 + light(x+1,y,z-1,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lr3 = l*(light(x+1,y-1,z-1,0, &chunks) as f32 + lr*30_f32 + light(x,y-1,z-1,0, &chunks) as f32 + light(x+1,y,z-1,0, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-    
-                        let lg0 = l*(light(x-1,y-1,z-1,1, &chunks) as f32 + lg*30_f32 + light(x,y-1,z-1,1, &chunks) as f32 + light(x-1,y,z-1,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lg1 = l*(light(x-1,y+1,z-1,1, &chunks) as f32 + lg*30_f32 + light(x,y+1,z-1,1, &chunks) as f32 + light(x-1,y,z-1,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lg2 = l*(light(x+1,y+1,z-1,1, &chunks) as f32 + lg*30_f32 + light(x,y+1,z-1,1, &chunks) as f32 + light(x+1,y,z-1,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lg3 = l*(light(x+1,y-1,z-1,1, &chunks) as f32 + lg*30_f32 + light(x,y-1,z-1,1, &chunks) as f32 + light(x+1,y,z-1,1, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-    
-                        let lb0 = l*(light(x-1,y-1,z-1,2, &chunks) as f32 + lb*30_f32 + light(x,y-1,z-1,2, &chunks) as f32 + light(x-1,y,z-1,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lb1 = l*(light(x-1,y+1,z-1,2, &chunks) as f32 + lb*30_f32 + light(x,y+1,z-1,2, &chunks) as f32 + light(x-1,y,z-1,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lb2 = l*(light(x+1,y+1,z-1,2, &chunks) as f32 + lb*30_f32 + light(x,y+1,z-1,2, &chunks) as f32 + light(x+1,y,z-1,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let lb3 = l*(light(x+1,y-1,z-1,2, &chunks) as f32 + lb*30_f32 + light(x,y-1,z-1,2, &chunks) as f32 + light(x+1,y,z-1,2, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-    
-                        let ls0 = l*(light(x-1,y-1,z-1,3, &chunks) as f32 + ls*30_f32 + light(x,y-1,z-1,3, &chunks) as f32 + light(x-1,y,z-1,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let ls1 = l*(light(x-1,y+1,z-1,3, &chunks) as f32 + ls*30_f32 + light(x,y+1,z-1,3, &chunks) as f32 + light(x-1,y,z-1,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let ls2 = l*(light(x+1,y+1,z-1,3, &chunks) as f32 + ls*30_f32 + light(x,y+1,z-1,3, &chunks) as f32 + light(x+1,y,z-1,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-                        let ls3 = l*(light(x+1,y-1,z-1,3, &chunks) as f32 + ls*30_f32  + light(x,y-1,z-1,3, &chunks) as f32 + light(x+1,y,z-1,3, &chunks) as f32) / 5.0_f32 / 15.0_f32;
-    
-                        vertex(&mut self.buffer, x as f32 -0.5_f32, y as f32-0.5_f32, z as f32-0.5_f32, u2,v1, lr0,lg0,lb0,ls0);                         
-                        vertex(&mut self.buffer, x as f32 -0.5_f32, y as f32+0.5_f32, z as f32-0.5_f32, u2,v2, lr1,lg1,lb1,ls1);                         
-                        vertex(&mut self.buffer, x as f32 +0.5_f32, y as f32+0.5_f32, z as f32-0.5_f32, u1,v2, lr2,lg2,lb2,ls2);                              
+                        let (u1, v1, u2, v2) = setup_uv(block.texture_faces[4] as usize, atlas);
+                        let (yb, yt, vb, vt) = span.place(y, v1, v2);
+    
+                        let lr = light(x,y,z-1, 0, chunks) as f32 / 15.0_f32;
+                        let lg = light(x,y,z-1, 1, chunks) as f32 / 15.0_f32;
+                        let lb = light(x,y,z-1, 2, chunks) as f32 / 15.0_f32;
+                        let ls = light(x,y,z-1, 3, chunks) as f32 / 15.0_f32;
+    
+                        let lr0 = l*(light(x-1,y-1,z-1,0, chunks) as f32 + lr*30_f32 + light(x,y-1,z-1,0, chunks) as f32 + light(x-1,y,z-1,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lr1 = l*(light(x-1,y+1,z-1,0, chunks) as f32 + lr*30_f32 + light(x,y+1,z-1,0, chunks) as f32 + light(x-1,y,z-1,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lr2 = l*(light(x+1,y+1,z-1,0, chunks) as f32 + lr*30_f32 + light(x,y+1,z-1,0, chunks) as f32 + light(x+1,y,z-1,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lr3 = l*(light(x+1,y-1,z-1,0, chunks) as f32 + lr*30_f32 + light(x,y-1,z-1,0, chunks) as f32 + light(x+1,y,z-1,0, chunks) as f32) / 5.0_f32 / 15.0_f32;
+    
+                        let lg0 = l*(light(x-1,y-1,z-1,1, chunks) as f32 + lg*30_f32 + light(x,y-1,z-1,1, chunks) as f32 + light(x-1,y,z-1,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lg1 = l*(light(x-1,y+1,z-1,1, chunks) as f32 + lg*30_f32 + light(x,y+1,z-1,1, chunks) as f32 + light(x-1,y,z-1,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lg2 = l*(light(x+1,y+1,z-1,1, chunks) as f32 + lg*30_f32 + light(x,y+1,z-1,1, chunks) as f32 + light(x+1,y,z-1,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lg3 = l*(light(x+1,y-1,z-1,1, chunks) as f32 + lg*30_f32 + light(x,y-1,z-1,1, chunks) as f32 + light(x+1,y,z-1,1, chunks) as f32) / 5.0_f32 / 15.0_f32;
+    
+                        let lb0 = l*(light(x-1,y-1,z-1,2, chunks) as f32 + lb*30_f32 + light(x,y-1,z-1,2, chunks) as f32 + light(x-1,y,z-1,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lb1 = l*(light(x-1,y+1,z-1,2, chunks) as f32 + lb*30_f32 + light(x,y+1,z-1,2, chunks) as f32 + light(x-1,y,z-1,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lb2 = l*(light(x+1,y+1,z-1,2, chunks) as f32 + lb*30_f32 + light(x,y+1,z-1,2, chunks) as f32 + light(x+1,y,z-1,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let lb3 = l*(light(x+1,y-1,z-1,2, chunks) as f32 + lb*30_f32 + light(x,y-1,z-1,2, chunks) as f32 + light(x+1,y,z-1,2, chunks) as f32) / 5.0_f32 / 15.0_f32;
+    
+                        let ls0 = l*(light(x-1,y-1,z-1,3, chunks) as f32 + ls*30_f32 + light(x,y-1,z-1,3, chunks) as f32 + light(x-1,y,z-1,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let ls1 = l*(light(x-1,y+1,z-1,3, chunks) as f32 + ls*30_f32 + light(x,y+1,z-1,3, chunks) as f32 + light(x-1,y,z-1,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let ls2 = l*(light(x+1,y+1,z-1,3, chunks) as f32 + ls*30_f32 + light(x,y+1,z-1,3, chunks) as f32 + light(x+1,y,z-1,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+                        let ls3 = l*(light(x+1,y-1,z-1,3, chunks) as f32 + ls*30_f32  + light(x,y-1,z-1,3, chunks) as f32 + light(x+1,y,z-1,3, chunks) as f32) / 5.0_f32 / 15.0_f32;
+    
+                        vertex(&mut self.buffer, x as f32 -0.5_f32, yb, z as f32-0.5_f32, u2,vb, lr0,lg0,lb0,ls0);                         
+                        vertex(&mut self.buffer, x as f32 -0.5_f32, yt, z as f32-0.5_f32, u2,vt, lr1,lg1,lb1,ls1);                         
+                        vertex(&mut self.buffer, x as f32 +0.5_f32, yt, z as f32-0.5_f32, u1,vt, lr2,lg2,lb2,ls2);                              
                         
-                        vertex(&mut self.buffer, x as f32 -0.5_f32, y as f32-0.5_f32, z as f32-0.5_f32, u2,v1, lr0,lg0,lb0,ls0);                         
-                        vertex(&mut self.buffer, x as f32 +0.5_f32, y as f32 +0.5_f32, z as f32-0.5_f32, u1,v2, lr2,lg2,lb2,ls2);                         
-                        vertex(&mut self.buffer, x as f32 +0.5_f32, y as f32 -0.5_f32, z as f32-0.5_f32, u1,v1, lr3,lg3,lb3,ls3);
+                        vertex(&mut self.buffer, x as f32 -0.5_f32, yb, z as f32-0.5_f32, u2,vb, lr0,lg0,lb0,ls0);                         
+                        vertex(&mut self.buffer, x as f32 +0.5_f32, yt, z as f32-0.5_f32, u1,vt, lr2,lg2,lb2,ls2);                         
+                        vertex(&mut self.buffer, x as f32 +0.5_f32, yb, z as f32-0.5_f32, u1,vb, lr3,lg3,lb3,ls3);
                     }
                 }
                 }
             }
         }
 
-        Mesh::new(self.buffer.as_ptr(), self.buffer.len() / VERTEX_SIZE, [3, 2, 4, 0].as_ptr())
+        &self.buffer
     }
 }
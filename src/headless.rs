@@ -0,0 +1,213 @@
+use std::time::Instant;
+
+use crate::assets::Atlas;
+use crate::config::Config;
+use crate::console::{CommandRegistry, Context};
+use crate::edit::Editor;
+use crate::controls::Controls;
+use crate::graphics::VoxelRenderer;
+use crate::graphics::voxel_renderer::VERTEX_SIZE;
+use crate::input::recording::{Recording, Replay};
+use crate::player::Hotbar;
+use crate::stats::DebugStats;
+use crate::voxels::{BlockRegistry, Chunk};
+use crate::window::{Camera, Events};
+use crate::world::World;
+use crate::{load_content, open_world};
+
+use glam::Vec3;
+
+
+/// Запуск без окна и OpenGL: загрузка мира, освещение,
+/// построение мешей на CPU и сохранение.
+/// С записью ввода вместо сохранения воспроизводит её кадр за кадром,
+/// а скрипт команд консоли выполняется перед сохранением
+pub fn run(config: &Config, replay: Option<Recording>, script: Option<&str>) -> Result<(), String> {
+    println!("start headless");
+    let (mut atlas, blocks_controller) = load_content();
+    let blocks = &blocks_controller.blocks;
+
+    let mut camera = start_camera(config);
+    let mut world = open_world(config, camera.position, blocks)?;
+    let mut renderer = VoxelRenderer::new(config.graphics.renderer_capacity);
+
+    println!("start meshing");
+    let start = Instant::now();
+    let mut mesh_sizes = vec![0; world.chunks.volume];
+    mesh_modified(&mut world, &mut renderer, blocks, &atlas, &mut mesh_sizes);
+    println!("meshing: ok, {} vertices in {:?}", mesh_sizes.iter().sum::<usize>(), start.elapsed());
+
+    let mut stats = DebugStats::default();
+
+    if let Some(recording) = replay {
+        println!("start replay");
+        let start = Instant::now();
+        let after_frame = |delta, world: &mut World, atlas: &Atlas| {
+            stats.frame(delta);
+            // Меши перестраиваются как в игре, чтобы падения мешера тоже воспроизводились
+            stats.chunks_rebuilt = mesh_modified(world, &mut renderer, blocks, atlas, &mut mesh_sizes);
+        };
+        let mut replay = Replay::new(recording);
+        let mut live = Events::new();
+        let (controls, frames) = replay_frames(&mut replay, &mut live, config, &mut camera, &mut world, blocks, &mut atlas, after_frame);
+        println!("replay: ok, {} frames, {} ticks in {:?}", frames, world.tick, start.elapsed());
+        for line in &controls.console.output {
+            println!("console: {}", line);
+        }
+        print_stats(&mut stats, &mut world, &camera, &mesh_sizes, blocks);
+        println!("player eye: {}", world.player.eye_position());
+        println!("world checksum: {:016x}", world.checksum());
+        println!("finish headless");
+        return Ok(());
+    }
+
+    if let Some(script) = script {
+        println!("start script");
+        let registry = CommandRegistry::default();
+        let mut editor = Editor::default();
+        let mut context = Context {
+            world: &mut world,
+            blocks,
+            atlas: &atlas,
+            world_path: &config.world.path,
+            editor: &mut editor,
+            look: camera.front,
+        };
+        for line in registry.run_script(script, &mut context).map_err(|e| format!("script: {}", e))? {
+            println!("script: {}", line);
+        }
+        stats.chunks_rebuilt = mesh_modified(&mut world, &mut renderer, blocks, &atlas, &mut mesh_sizes);
+        println!("script: ok");
+    }
+
+    print_stats(&mut stats, &mut world, &camera, &mesh_sizes, blocks);
+
+    println!("saving world");
+    match world.save(&config.world.path) {
+        Ok(()) => println!("world saved on {}", config.world.path),
+        Err(e) => eprintln!("world not saved: {}", e),
+    }
+    println!("finish headless");
+    Ok(())
+}
+
+
+fn start_camera(config: &Config) -> Camera {
+    Camera::new(Vec3::new(5.0, 5.0, 20.0), config.graphics.fov.to_radians())
+}
+
+
+/// Воспроизводит запись кадр за кадром до конца или до выхода из игры;
+/// `live` - ввод окна, который при воспроизведении не должен учитываться.
+/// `after_frame` получает длительность кадра и вызывается после каждого.
+/// Возвращает управление с его консолью и число сыгранных кадров
+#[allow(clippy::too_many_arguments)]
+fn replay_frames(
+    replay: &mut Replay,
+    live: &mut Events,
+    config: &Config,
+    camera: &mut Camera,
+    world: &mut World,
+    blocks: &BlockRegistry,
+    atlas: &mut Atlas,
+    mut after_frame: impl FnMut(f32, &mut World, &Atlas),
+) -> (Controls, usize) {
+    let hotbar = Hotbar::from_names(&config.gameplay.hotbar, blocks);
+    let mut controls = Controls::new(config.graphics.width, config.graphics.height, hotbar);
+    let mut frames = 0;
+    while let (Some(delta), events) = replay.input(live) {
+        frames += 1;
+        let result = controls.frame(delta, events, config, camera, world, blocks, atlas);
+        // Кадры анимаций переключаются как в игре, хоть загружать их и некуда
+        atlas.animate(world.time.elapsed());
+        after_frame(delta, world, atlas);
+        if result.exit {
+            break;
+        }
+    }
+    (controls, frames)
+}
+
+
+fn print_stats(stats: &mut DebugStats, world: &mut World, camera: &Camera, mesh_sizes: &[usize], blocks: &BlockRegistry) {
+    stats.observe(world, camera.position, camera.front, blocks);
+    stats.meshes(mesh_sizes.iter().copied());
+    for line in stats.lines() {
+        println!("stats: {}", line);
+    }
+}
+
+
+/// Строит на CPU меши изменённых чанков, записывая их размеры в вершинах
+/// в `sizes`; возвращает число перестроенных чанков
+fn mesh_modified(
+    world: &mut World,
+    renderer: &mut VoxelRenderer,
+    blocks: &BlockRegistry,
+    atlas: &Atlas,
+    sizes: &mut [usize],
+) -> usize {
+    let mut closes: Vec<Option<Chunk>> = vec![None; 27];
+    let mut rebuilt = 0;
+    for (i, size) in sizes.iter_mut().enumerate() {
+        if !world.chunks.chunks[i].modified {
+            continue;
+        }
+        world.chunks.neighbours(i, &mut closes);
+        world.chunks.chunks[i].modified = false;
+        let chunk = &world.chunks.chunks[i];
+        *size = renderer.build(chunk, &closes, blocks, atlas).len() / VERTEX_SIZE;
+        rebuilt += 1;
+    }
+    rebuilt
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::gamepad::{GamepadState, AXIS_RIGHT_TRIGGER, AXIS_RIGHT_X};
+    use crate::input::keys::{letter, MOUSE_RIGHT};
+    use crate::input::recording::world_snapshot_path;
+
+    /// Короткая запись: захват курсора, взгляд вниз, шаги, ломание,
+    /// прыжок и установка блока. Снимок мира 2x1x2 лежит рядом с ней
+    const RECORDING: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/replay/walk.rec");
+
+    const CHECKSUM: u64 = 0xf46f8af3a18bab4e;
+
+    /// Контрольная сумма мира после записи при живом вводе `live`
+    fn replay_checksum(live: &mut Events) -> u64 {
+        let recording = Recording::load(RECORDING).unwrap();
+        let frame_count = recording.frames.len();
+        let mut config = Config::parse(&recording.config).unwrap();
+        config.world.path = world_snapshot_path(RECORDING);
+
+        let (mut atlas, blocks_controller) = load_content();
+        let blocks = &blocks_controller.blocks;
+        let mut camera = start_camera(&config);
+        let mut world = open_world(&config, camera.position, blocks).unwrap();
+        let mut replay = Replay::new(recording);
+        let (_, frames) = replay_frames(&mut replay, live, &config, &mut camera, &mut world, blocks, &mut atlas, |_, _, _| {});
+        assert_eq!(frames, frame_count);
+        world.checksum()
+    }
+
+    #[test]
+    fn replay_reproduces_world() {
+        assert_eq!(replay_checksum(&mut Events::new()), CHECKSUM);
+    }
+
+    #[test]
+    fn live_input_does_not_leak_into_replay() {
+        let mut live = Events::new();
+        live.cursor_locked = true;
+        live.key_event(letter('S') as usize, true);
+        live.key_event(1024 + MOUSE_RIGHT as usize, true);
+        let mut pad = GamepadState { connected: true, ..Default::default() };
+        pad.axes[AXIS_RIGHT_X] = 1.0;
+        pad.axes[AXIS_RIGHT_TRIGGER] = 1.0;
+        live.gamepad_event(pad);
+        assert_eq!(replay_checksum(&mut live), CHECKSUM);
+    }
+}
@@ -0,0 +1,177 @@
+use glam::Vec2;
+
+
+// Раскладка кнопок и осей как у стандартного геймпада GLFW
+
+pub const PAD_A: usize = 0;
+pub const PAD_B: usize = 1;
+pub const PAD_Y: usize = 3;
+pub const PAD_LEFT_BUMPER: usize = 4;
+pub const PAD_RIGHT_BUMPER: usize = 5;
+pub const PAD_DPAD_RIGHT: usize = 12;
+pub const PAD_DPAD_LEFT: usize = 14;
+pub const BUTTON_COUNT: usize = 15;
+
+pub const AXIS_LEFT_X: usize = 0;
+pub const AXIS_LEFT_Y: usize = 1;
+pub const AXIS_RIGHT_X: usize = 2;
+pub const AXIS_RIGHT_Y: usize = 3;
+pub const AXIS_LEFT_TRIGGER: usize = 4;
+pub const AXIS_RIGHT_TRIGGER: usize = 5;
+pub const AXIS_COUNT: usize = 6;
+
+/// Курки считаются ещё двумя кнопками после обычных
+pub const PAD_LEFT_TRIGGER: usize = BUTTON_COUNT;
+pub const PAD_RIGHT_TRIGGER: usize = BUTTON_COUNT + 1;
+pub const PAD_INPUT_COUNT: usize = BUTTON_COUNT + 2;
+
+/// Насколько нужно нажать курок, чтобы он считался нажатым
+pub const TRIGGER_THRESHOLD: f32 = 0.5;
+
+const NAMES: [&str; PAD_INPUT_COUNT] = [
+    "PadA", "PadB", "PadX", "PadY", "PadLB", "PadRB", "PadBack", "PadStart", "PadGuide",
+    "PadLeftThumb", "PadRightThumb", "PadUp", "PadRight", "PadDown", "PadLeft", "PadLT", "PadRT",
+];
+
+
+/// Состояние геймпада за кадр. Стики в [-1, 1], ось Y направлена вниз;
+/// курки в [0, 1], 0 - отпущен
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GamepadState {
+    pub connected: bool,
+    pub buttons: [bool; BUTTON_COUNT],
+    pub axes: [f32; AXIS_COUNT],
+}
+
+
+impl GamepadState {
+    /// Кнопка или курок по индексу `PAD_*`
+    pub fn pressed(&self, input: usize) -> bool {
+        match input {
+            PAD_LEFT_TRIGGER => self.axes[AXIS_LEFT_TRIGGER] > TRIGGER_THRESHOLD,
+            PAD_RIGHT_TRIGGER => self.axes[AXIS_RIGHT_TRIGGER] > TRIGGER_THRESHOLD,
+            input => self.buttons.get(input).copied().unwrap_or(false),
+        }
+    }
+
+
+    /// Отклонение стика с круглой мёртвой зоной: внутри неё ноль,
+    /// снаружи длина плавно растёт от 0 до 1
+    pub fn stick(&self, x_axis: usize, y_axis: usize, dead_zone: f32) -> Vec2 {
+        let value = Vec2::new(self.axes[x_axis], self.axes[y_axis]);
+        let length = value.length();
+        if !self.connected || length <= dead_zone {
+            return Vec2::ZERO;
+        }
+        let scaled = ((length - dead_zone) / (1.0 - dead_zone)).min(1.0);
+        value / length * scaled
+    }
+}
+
+
+/// Откуда берётся состояние геймпада
+#[cfg(feature = "graphics")]
+pub trait GamepadSource {
+    fn poll(&mut self) -> GamepadState;
+}
+
+
+/// Первый подключённый джойстик с раскладкой геймпада
+#[cfg(feature = "graphics")]
+pub struct GlfwGamepad {
+    joystick: glfw::Joystick,
+}
+
+
+#[cfg(feature = "graphics")]
+impl GlfwGamepad {
+    pub fn new(glfw: &glfw::Glfw) -> Self {
+        Self { joystick: glfw.get_joystick(glfw::JoystickId::Joystick1) }
+    }
+}
+
+
+#[cfg(feature = "graphics")]
+impl GamepadSource for GlfwGamepad {
+    fn poll(&mut self) -> GamepadState {
+        let Some(state) = self.joystick.get_gamepad_state() else {
+            return GamepadState::default();
+        };
+
+        let mut result = GamepadState { connected: true, ..Default::default() };
+        for (index, button) in result.buttons.iter_mut().enumerate() {
+            let id = glfw::GamepadButton::from_i32(index as i32).unwrap();
+            *button = state.get_button_state(id) == glfw::Action::Press;
+        }
+        for (index, axis) in result.axes.iter_mut().enumerate() {
+            *axis = state.get_axis(glfw::GamepadAxis::from_i32(index as i32).unwrap());
+        }
+        // GLFW отдаёт курки в [-1, 1]
+        for trigger in [AXIS_LEFT_TRIGGER, AXIS_RIGHT_TRIGGER] {
+            result.axes[trigger] = (result.axes[trigger] + 1.0) * 0.5;
+        }
+        result
+    }
+}
+
+
+/// Индекс кнопки по имени: `PadA`, `PadRB`, `PadLT`...
+pub fn pad_input(name: &str) -> Option<usize> {
+    NAMES.iter().position(|pad| pad.eq_ignore_ascii_case(name))
+}
+
+
+pub fn pad_name(input: usize) -> String {
+    NAMES.get(input).map_or_else(|| format!("Pad{}", input), |name| name.to_string())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pad() -> GamepadState {
+        GamepadState { connected: true, ..Default::default() }
+    }
+
+    #[test]
+    fn stick_dead_zone() {
+        let mut state = pad();
+        state.axes[AXIS_LEFT_X] = 0.1;
+        state.axes[AXIS_LEFT_Y] = -0.1;
+        assert_eq!(state.stick(AXIS_LEFT_X, AXIS_LEFT_Y, 0.2), Vec2::ZERO);
+
+        state.axes[AXIS_LEFT_X] = 0.6;
+        state.axes[AXIS_LEFT_Y] = 0.0;
+        let stick = state.stick(AXIS_LEFT_X, AXIS_LEFT_Y, 0.2);
+        assert!((stick.x - 0.5).abs() < 1e-6 && stick.y == 0.0);
+
+        // Углы квадрата не дают длину больше 1
+        state.axes[AXIS_LEFT_X] = 1.0;
+        state.axes[AXIS_LEFT_Y] = 1.0;
+        assert!((state.stick(AXIS_LEFT_X, AXIS_LEFT_Y, 0.2).length() - 1.0).abs() < 1e-6);
+
+        state.connected = false;
+        assert_eq!(state.stick(AXIS_LEFT_X, AXIS_LEFT_Y, 0.2), Vec2::ZERO);
+    }
+
+    #[test]
+    fn triggers_are_buttons() {
+        let mut state = pad();
+        state.axes[AXIS_RIGHT_TRIGGER] = TRIGGER_THRESHOLD;
+        assert!(!state.pressed(PAD_RIGHT_TRIGGER));
+        state.axes[AXIS_RIGHT_TRIGGER] = 0.9;
+        assert!(state.pressed(PAD_RIGHT_TRIGGER));
+        assert!(!state.pressed(PAD_LEFT_TRIGGER));
+        assert!(!state.pressed(PAD_INPUT_COUNT));
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for input in 0..PAD_INPUT_COUNT {
+            assert_eq!(pad_input(&pad_name(input)), Some(input));
+        }
+        assert_eq!(pad_input("padrt"), Some(PAD_RIGHT_TRIGGER));
+        assert_eq!(pad_input("PadZ"), None);
+    }
+}
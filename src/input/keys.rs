@@ -0,0 +1,114 @@
+// Коды клавиш и кнопок совпадают с GLFW, но не зависят от него,
+// чтобы ввод можно было подавать и без окна
+
+/// Левая кнопка мыши
+pub const MOUSE_LEFT: i32 = 0;
+/// Правая кнопка мыши
+pub const MOUSE_RIGHT: i32 = 1;
+/// Средняя кнопка мыши
+pub const MOUSE_MIDDLE: i32 = 2;
+
+pub const SPACE: i32 = 32;
+pub const MINUS: i32 = 45;
+pub const K_0: i32 = 48;
+pub const A: i32 = 65;
+pub const GRAVE: i32 = 96;
+pub const ESCAPE: i32 = 256;
+pub const ENTER: i32 = 257;
+pub const TAB: i32 = 258;
+pub const BACKSPACE: i32 = 259;
+pub const RIGHT: i32 = 262;
+pub const LEFT: i32 = 263;
+pub const DOWN: i32 = 264;
+pub const UP: i32 = 265;
+pub const F1: i32 = 290;
+pub const LEFT_SHIFT: i32 = 340;
+pub const LEFT_CONTROL: i32 = 341;
+pub const LEFT_ALT: i32 = 342;
+pub const RIGHT_SHIFT: i32 = 344;
+pub const RIGHT_CONTROL: i32 = 345;
+pub const RIGHT_ALT: i32 = 346;
+
+
+/// Буква латиницы в код клавиши
+pub const fn letter(c: char) -> i32 {
+    A + (c as i32 - 'A' as i32)
+}
+
+
+const NAMED: [(&str, i32); 17] = [
+    ("Space", SPACE),
+    ("Escape", ESCAPE),
+    ("Enter", ENTER),
+    ("Tab", TAB),
+    ("Backspace", BACKSPACE),
+    ("Right", RIGHT),
+    ("Left", LEFT),
+    ("Down", DOWN),
+    ("Up", UP),
+    ("Grave", GRAVE),
+    ("LeftShift", LEFT_SHIFT),
+    ("LeftControl", LEFT_CONTROL),
+    ("LeftAlt", LEFT_ALT),
+    ("RightShift", RIGHT_SHIFT),
+    ("RightControl", RIGHT_CONTROL),
+    ("RightAlt", RIGHT_ALT),
+    ("Minus", MINUS),
+];
+
+const MOUSE: [(&str, i32); 3] = [
+    ("MouseLeft", MOUSE_LEFT),
+    ("MouseRight", MOUSE_RIGHT),
+    ("MouseMiddle", MOUSE_MIDDLE),
+];
+
+
+/// Код клавиши по имени: `W`, `7`, `F3`, `Space`, `LeftShift`...
+pub fn key_code(name: &str) -> Option<i32> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return match c {
+            'A'..='Z' => Some(letter(c)),
+            'a'..='z' => Some(letter(c.to_ascii_uppercase())),
+            '0'..='9' => Some(K_0 + (c as i32 - '0' as i32)),
+            _ => None,
+        };
+    }
+    if let Some(n) = name.strip_prefix(['F', 'f']).and_then(|n| n.parse::<i32>().ok()) {
+        return (1..=12).contains(&n).then_some(F1 + n - 1);
+    }
+    if let Some(code) = name.strip_prefix("Key").and_then(|code| code.parse::<i32>().ok()) {
+        return (0..1024).contains(&code).then_some(code);
+    }
+    NAMED.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, code)| *code)
+}
+
+
+pub fn key_name(code: i32) -> String {
+    match code {
+        c if (A..A + 26).contains(&c) => ((b'A' + (c - A) as u8) as char).to_string(),
+        c if (K_0..K_0 + 10).contains(&c) => (c - K_0).to_string(),
+        c if (F1..F1 + 12).contains(&c) => format!("F{}", c - F1 + 1),
+        c => NAMED
+            .iter()
+            .find(|(_, code)| *code == c)
+            .map_or_else(|| format!("Key{}", c), |(name, _)| name.to_string()),
+    }
+}
+
+
+/// Кнопка мыши по имени: `MouseLeft`, `MouseRight`, `MouseMiddle`
+pub fn mouse_button(name: &str) -> Option<i32> {
+    if let Some(button) = name.strip_prefix("Mouse").and_then(|button| button.parse::<i32>().ok()) {
+        return (0..8).contains(&button).then_some(button);
+    }
+    MOUSE.iter().find(|(button, _)| button.eq_ignore_ascii_case(name)).map(|(_, code)| *code)
+}
+
+
+pub fn mouse_name(button: i32) -> String {
+    MOUSE
+        .iter()
+        .find(|(_, code)| *code == button)
+        .map_or_else(|| format!("Mouse{}", button), |(name, _)| name.to_string())
+}
@@ -0,0 +1,480 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::player::HOTBAR_SIZE;
+use crate::window::Events;
+
+use self::gamepad::*;
+use self::keys::*;
+
+pub mod gamepad;
+pub mod keys;
+pub mod recording;
+
+
+/// Число слотов выбора блока, `SelectSlot(0..SLOT_COUNT)`
+pub const SLOT_COUNT: u8 = HOTBAR_SIZE as u8;
+
+/// Сколько слотов было до панели блоков; привязки лишних слотов
+/// из старых файлов настроек пропускаются
+const LEGACY_SLOT_COUNT: u8 = 10;
+
+
+/// Игровое действие, к которому привязываются клавиши
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Descend,
+    Break,
+    Place,
+    ToggleCursor,
+    ToggleFly,
+    TimeBackward,
+    TimeForward,
+    Exit,
+    NextSlot,
+    PreviousSlot,
+    /// Открыть или закрыть инвентарь
+    Inventory,
+    DebugOverlay,
+    Console,
+    /// Углы выделения области по блоку под прицелом
+    SelectFirst,
+    SelectSecond,
+    Undo,
+    Redo,
+    /// Снимок экрана и снимок в `screenshot_scale` раз больше окна
+    Screenshot,
+    HighResScreenshot,
+    SelectSlot(u8),
+}
+
+
+const NAMES: [(Action, &str); 24] = [
+    (Action::MoveForward, "move_forward"),
+    (Action::MoveBack, "move_back"),
+    (Action::MoveLeft, "move_left"),
+    (Action::MoveRight, "move_right"),
+    (Action::Jump, "jump"),
+    (Action::Descend, "descend"),
+    (Action::Break, "break"),
+    (Action::Place, "place"),
+    (Action::ToggleCursor, "toggle_cursor"),
+    (Action::ToggleFly, "toggle_fly"),
+    (Action::TimeBackward, "time_backward"),
+    (Action::TimeForward, "time_forward"),
+    (Action::Exit, "exit"),
+    (Action::NextSlot, "next_slot"),
+    (Action::PreviousSlot, "previous_slot"),
+    (Action::Inventory, "inventory"),
+    (Action::DebugOverlay, "debug_overlay"),
+    (Action::Console, "console"),
+    (Action::SelectFirst, "select_first"),
+    (Action::SelectSecond, "select_second"),
+    (Action::Undo, "undo"),
+    (Action::Redo, "redo"),
+    (Action::Screenshot, "screenshot"),
+    (Action::HighResScreenshot, "high_res_screenshot"),
+];
+
+
+impl Action {
+    /// Все действия в порядке записи в файл настроек
+    pub fn all() -> Vec<Action> {
+        let mut actions: Vec<Action> = NAMES.iter().map(|(action, _)| *action).collect();
+        actions.extend((0..SLOT_COUNT).map(Action::SelectSlot));
+        actions
+    }
+
+
+    /// Имя в файле настроек: `move_forward`, `select_slot_3`...
+    /// Слоты в именах нумеруются с нуля, как в старых файлах настроек
+    pub fn name(&self) -> String {
+        match self {
+            Action::SelectSlot(slot) => format!("select_slot_{}", slot),
+            action => NAMES.iter().find(|(a, _)| a == action).unwrap().1.to_string(),
+        }
+    }
+
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        if let Some(slot) = name.strip_prefix("select_slot_").and_then(|slot| slot.parse::<u8>().ok()) {
+            return (slot < SLOT_COUNT).then_some(Action::SelectSlot(slot));
+        }
+        NAMES.iter().find(|(_, n)| *n == name).map(|(action, _)| *action)
+    }
+}
+
+
+/// Слот, которого больше нет: `select_slot_9` из десятислотовых настроек
+fn is_legacy_slot(name: &str) -> bool {
+    let slot = name.strip_prefix("select_slot_").and_then(|slot| slot.parse::<u8>().ok());
+    slot.is_some_and(|slot| (SLOT_COUNT..LEGACY_SLOT_COUNT).contains(&slot))
+}
+
+
+/// Модификаторы; левый и правый варианты клавиши равноправны
+pub const SHIFT: u8 = 1;
+pub const CONTROL: u8 = 2;
+pub const ALT: u8 = 4;
+
+const MODIFIERS: [(u8, &str, [i32; 2]); 3] = [
+    (CONTROL, "Ctrl", [LEFT_CONTROL, RIGHT_CONTROL]),
+    (SHIFT, "Shift", [LEFT_SHIFT, RIGHT_SHIFT]),
+    (ALT, "Alt", [LEFT_ALT, RIGHT_ALT]),
+];
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Input {
+    Key(i32),
+    Mouse(i32),
+    /// Кнопка или курок геймпада, `PAD_*`
+    Pad(usize),
+}
+
+
+/// Клавиша или кнопка мыши вместе с модификаторами, которые должны быть зажаты.
+/// Лишние зажатые модификаторы не мешают, чтобы, например, можно было бежать
+/// вперёд с зажатым Shift, если на Shift+W ничего не назначено
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Binding {
+    pub input: Input,
+    pub modifiers: u8,
+}
+
+
+impl Binding {
+    pub fn key(code: i32) -> Self {
+        Self { input: Input::Key(code), modifiers: 0 }
+    }
+
+
+    pub fn mouse(button: i32) -> Self {
+        Self { input: Input::Mouse(button), modifiers: 0 }
+    }
+
+
+    pub fn pad(input: usize) -> Self {
+        Self { input: Input::Pad(input), modifiers: 0 }
+    }
+
+
+    /// Разбор `W`, `MouseLeft`, `PadA`, `Ctrl+S`, `Ctrl+Shift+Z`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let last = parts.pop().filter(|last| !last.is_empty()).ok_or_else(|| format!("empty binding '{}'", text))?;
+
+        let mut modifiers = 0;
+        for part in parts {
+            let (flag, _, _) = MODIFIERS
+                .iter()
+                .find(|(_, name, _)| name.eq_ignore_ascii_case(part))
+                .ok_or_else(|| format!("unknown modifier '{}' in '{}'", part, text))?;
+            modifiers |= flag;
+        }
+
+        let input = if let Some(code) = key_code(last) {
+            Input::Key(code)
+        } else if let Some(button) = mouse_button(last) {
+            Input::Mouse(button)
+        } else if let Some(pad) = pad_input(last) {
+            Input::Pad(pad)
+        } else {
+            return Err(format!("unknown key '{}'", last));
+        };
+        Ok(Self { input, modifiers })
+    }
+
+
+    fn modifiers_held(&self, events: &Events) -> bool {
+        MODIFIERS.iter().all(|(flag, _, keys)| {
+            self.modifiers & flag == 0 || keys.iter().any(|key| events.pressed(*key))
+        })
+    }
+
+
+    pub fn pressed(&self, events: &Events) -> bool {
+        let down = match self.input {
+            Input::Key(code) => events.pressed(code),
+            Input::Mouse(button) => events.clicked(button),
+            Input::Pad(pad) => events.pad_pressed(pad),
+        };
+        down && self.modifiers_held(events)
+    }
+
+
+    /// Нажато именно в этом кадре
+    pub fn jpressed(&self, events: &Events) -> bool {
+        let down = match self.input {
+            Input::Key(code) => events.jpressed(code),
+            Input::Mouse(button) => events.jclicked(button),
+            Input::Pad(pad) => events.pad_jpressed(pad),
+        };
+        down && self.modifiers_held(events)
+    }
+}
+
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (flag, name, _) in MODIFIERS {
+            if self.modifiers & flag != 0 {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match self.input {
+            Input::Key(code) => write!(f, "{}", key_name(code)),
+            Input::Mouse(button) => write!(f, "{}", mouse_name(button)),
+            Input::Pad(pad) => write!(f, "{}", pad_name(pad)),
+        }
+    }
+}
+
+
+/// Привязки действий; у действия может быть несколько привязок или ни одной
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bindings {
+    map: HashMap<Action, Vec<Binding>>,
+}
+
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let mut bindings = Self { map: HashMap::new() };
+        bindings.bind(Action::MoveForward, Binding::key(letter('W')));
+        bindings.bind(Action::MoveBack, Binding::key(letter('S')));
+        bindings.bind(Action::MoveLeft, Binding::key(letter('A')));
+        bindings.bind(Action::MoveRight, Binding::key(letter('D')));
+        bindings.bind(Action::Jump, Binding::key(SPACE));
+        bindings.bind(Action::Descend, Binding::key(LEFT_SHIFT));
+        bindings.bind(Action::Break, Binding::mouse(MOUSE_LEFT));
+        bindings.bind(Action::Place, Binding::mouse(MOUSE_RIGHT));
+        bindings.bind(Action::ToggleCursor, Binding::key(TAB));
+        bindings.bind(Action::ToggleFly, Binding::key(letter('F')));
+        bindings.bind(Action::TimeBackward, Binding::key(letter('Q')));
+        bindings.bind(Action::TimeForward, Binding::key(letter('E')));
+        bindings.bind(Action::Exit, Binding::key(ESCAPE));
+        bindings.bind(Action::Inventory, Binding::key(letter('I')));
+        bindings.bind(Action::DebugOverlay, Binding::key(F1 + 2));
+        bindings.bind(Action::Console, Binding::key(GRAVE));
+        bindings.bind(Action::SelectFirst, Binding::key(letter('Z')));
+        bindings.bind(Action::SelectSecond, Binding::key(letter('X')));
+        bindings.bind(Action::Undo, Binding::key(letter('U')));
+        bindings.bind(Action::Redo, Binding::key(letter('R')));
+        bindings.bind(Action::Screenshot, Binding::key(F1 + 1));
+        bindings.bind(Action::HighResScreenshot, Binding { input: Input::Key(F1 + 1), modifiers: SHIFT });
+        for slot in 0..SLOT_COUNT {
+            bindings.bind(Action::SelectSlot(slot), Binding::key(K_0 + 1 + slot as i32));
+        }
+
+        bindings.bind(Action::Jump, Binding::pad(PAD_A));
+        bindings.bind(Action::Descend, Binding::pad(PAD_B));
+        bindings.bind(Action::Break, Binding::pad(PAD_RIGHT_TRIGGER));
+        bindings.bind(Action::Place, Binding::pad(PAD_LEFT_TRIGGER));
+        bindings.bind(Action::ToggleFly, Binding::pad(PAD_Y));
+        bindings.bind(Action::TimeBackward, Binding::pad(PAD_DPAD_LEFT));
+        bindings.bind(Action::TimeForward, Binding::pad(PAD_DPAD_RIGHT));
+        bindings.bind(Action::NextSlot, Binding::pad(PAD_RIGHT_BUMPER));
+        bindings.bind(Action::PreviousSlot, Binding::pad(PAD_LEFT_BUMPER));
+        bindings
+    }
+}
+
+
+impl Bindings {
+    /// Добавляет ещё одну привязку к действию
+    pub fn bind(&mut self, action: Action, binding: Binding) {
+        let list = self.map.entry(action).or_default();
+        if !list.contains(&binding) {
+            list.push(binding);
+        }
+    }
+
+
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.map.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+
+    /// Заменяет привязки действия списком через запятую: `W, Up`.
+    /// Пустая строка снимает все привязки
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        if is_legacy_slot(name) {
+            return Ok(());
+        }
+        let action = Action::from_name(name).ok_or_else(|| format!("unknown action '{}'", name))?;
+        let mut list = Vec::new();
+        for text in value.split(',').map(str::trim).filter(|text| !text.is_empty()) {
+            let binding = Binding::parse(text)?;
+            if !list.contains(&binding) {
+                list.push(binding);
+            }
+        }
+        self.map.insert(action, list);
+        Ok(())
+    }
+
+
+    /// Строки `action = binding, binding` для файла настроек
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for action in Action::all() {
+            let list: Vec<String> = self.get(action).iter().map(Binding::to_string).collect();
+            text += &format!("{} = {}\n", action.name(), list.join(", "));
+        }
+        text
+    }
+
+
+    pub fn pressed(&self, events: &Events, action: Action) -> bool {
+        self.get(action).iter().any(|binding| binding.pressed(events) && !self.shadowed(binding, events))
+    }
+
+
+    pub fn jpressed(&self, events: &Events, action: Action) -> bool {
+        self.get(action).iter().any(|binding| binding.jpressed(events) && !self.shadowed(binding, events))
+    }
+
+
+    /// Нажато в этом кадре кнопкой геймпада; остальные привязки не смотрятся
+    pub fn pad_jpressed(&self, events: &Events, action: Action) -> bool {
+        self.get(action).iter().any(|binding| {
+            matches!(binding.input, Input::Pad(_)) && binding.jpressed(events) && !self.shadowed(binding, events)
+        })
+    }
+
+
+    /// Перекрыта ли привязка более точной на той же клавише:
+    /// при зажатом Ctrl сочетание Ctrl+Q срабатывает вместо Q
+    fn shadowed(&self, binding: &Binding, events: &Events) -> bool {
+        self.map.values().flatten().any(|other| {
+            other.input == binding.input
+                && other.modifiers != binding.modifiers
+                && other.modifiers & binding.modifiers == binding.modifiers
+                && other.modifiers_held(events)
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binding_text_round_trip() {
+        for text in ["W", "7", "F3", "Space", "Minus", "Grave", "MouseLeft", "Ctrl+S", "Ctrl+Shift+Z", "Key500"] {
+            let binding = Binding::parse(text).unwrap();
+            assert_eq!(binding.to_string(), text);
+            assert_eq!(Binding::parse(&binding.to_string()).unwrap(), binding);
+        }
+        assert_eq!(Binding::parse("ctrl + s").unwrap(), Binding::parse("Ctrl+S").unwrap());
+    }
+
+    #[test]
+    fn bindings_text_round_trip() {
+        let bindings = Bindings::default();
+        let mut parsed = Bindings { map: HashMap::new() };
+        for line in bindings.to_text().lines() {
+            let (name, value) = line.split_once('=').unwrap();
+            parsed.set(name.trim(), value.trim()).unwrap();
+        }
+        assert_eq!(parsed, bindings);
+    }
+
+    #[test]
+    fn modifier_shadows_plain_binding() {
+        let bindings = Bindings::default();
+        let mut events = Events::new();
+        events.begin_frame();
+        events.key_event((F1 + 1) as usize, true);
+        assert!(bindings.jpressed(&events, Action::Screenshot));
+        assert!(!bindings.jpressed(&events, Action::HighResScreenshot));
+
+        events.begin_frame();
+        events.key_event((F1 + 1) as usize, false);
+        events.key_event(LEFT_SHIFT as usize, true);
+        events.key_event((F1 + 1) as usize, true);
+        assert!(bindings.jpressed(&events, Action::HighResScreenshot));
+        assert!(!bindings.jpressed(&events, Action::Screenshot));
+    }
+
+    #[test]
+    fn extra_modifiers_do_not_block() {
+        let bindings = Bindings::default();
+        let mut events = Events::new();
+        events.begin_frame();
+        events.key_event(LEFT_SHIFT as usize, true);
+        events.key_event(letter('W') as usize, true);
+        assert!(bindings.pressed(&events, Action::MoveForward));
+    }
+
+    #[test]
+    fn set_rejects_bad_values() {
+        let mut bindings = Bindings::default();
+        assert!(bindings.set("fly_to_moon", "W").is_err());
+        assert!(bindings.set("jump", "Hyper+W").is_err());
+        assert!(bindings.set("jump", "NoSuchKey").is_err());
+        assert!(bindings.set("jump", "Ctrl+").is_err());
+        assert_eq!(bindings.get(Action::Jump), Bindings::default().get(Action::Jump));
+
+        bindings.set("jump", "").unwrap();
+        assert!(bindings.get(Action::Jump).is_empty());
+    }
+
+    #[test]
+    fn pad_jpressed_edges() {
+        let bindings = Bindings::default();
+        let mut events = Events::new();
+        let mut state = GamepadState { connected: true, ..Default::default() };
+        state.buttons[PAD_A] = true;
+
+        events.begin_frame();
+        events.gamepad_event(state);
+        assert!(events.pad_jpressed(PAD_A));
+        assert!(bindings.pad_jpressed(&events, Action::Jump));
+
+        // Тот же снимок в следующем кадре - удержание, а не новое нажатие
+        events.begin_frame();
+        events.gamepad_event(state);
+        assert!(events.pad_pressed(PAD_A) && !events.pad_jpressed(PAD_A));
+
+        events.begin_frame();
+        state.buttons[PAD_A] = false;
+        state.axes[AXIS_RIGHT_TRIGGER] = 1.0;
+        events.gamepad_event(state);
+        assert!(!events.pad_pressed(PAD_A));
+        assert!(events.pad_jpressed(PAD_RIGHT_TRIGGER));
+        assert!(bindings.pad_jpressed(&events, Action::Break));
+    }
+
+    #[test]
+    fn pad_jpressed_ignores_mouse_and_keys() {
+        let bindings = Bindings::default();
+        let mut events = Events::new();
+        events.begin_frame();
+        events.key_event(1024 + MOUSE_LEFT as usize, true);
+        assert!(bindings.jpressed(&events, Action::Break));
+        assert!(!bindings.pad_jpressed(&events, Action::Break));
+    }
+
+    #[test]
+    fn slot_names_keep_old_numbering() {
+        assert_eq!(Action::SelectSlot(0).name(), "select_slot_0");
+        assert_eq!(Action::from_name("select_slot_0"), Some(Action::SelectSlot(0)));
+        assert_eq!(Action::from_name("select_slot_8"), Some(Action::SelectSlot(8)));
+        assert_eq!(Action::from_name("select_slot_9"), None);
+
+        // Старые настройки с десятым слотом читаются, его привязка пропускается
+        let mut bindings = Bindings::default();
+        bindings.set("select_slot_0", "0").unwrap();
+        assert!(bindings.set("select_slot_9", "9").is_ok());
+        assert!(bindings.set("select_slot_10", "9").is_err());
+        assert_eq!(bindings.get(Action::SelectSlot(0)), &[Binding::key(K_0)]);
+    }
+}
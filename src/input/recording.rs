@@ -0,0 +1,219 @@
+use std::fs;
+#[cfg(feature = "graphics")]
+use std::io;
+use std::str::FromStr;
+
+#[cfg(feature = "graphics")]
+use crate::files::write_binary_file;
+use super::gamepad::{GamepadState, AXIS_COUNT, BUTTON_COUNT};
+use crate::window::Events;
+
+
+/// Текущая версия формата записи
+pub const VERSION: u32 = 1;
+
+/// Снимок мира лежит рядом с записью: `<запись>.world`
+pub fn world_snapshot_path(path: &str) -> String {
+    format!("{}.world", path)
+}
+
+
+/// Откуда берётся ввод игры
+pub enum Session {
+    Live,
+    /// Живой ввод, который записывается в файл
+    Record(String),
+    Replay(Recording),
+}
+
+
+/// Событие ввода в том виде, в каком оно пришло в `Events`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputEvent {
+    Key { index: usize, pressed: bool },
+    Cursor { x: f64, y: f64 },
+    Gamepad(GamepadState),
+    Resize { width: i32, height: i32 },
+    Scroll { y: f32 },
+    Char(char),
+}
+
+
+/// Ввод одного кадра и его длительность
+#[derive(Clone, Debug, Default)]
+pub struct InputFrame {
+    pub delta: f32,
+    pub events: Vec<InputEvent>,
+}
+
+
+/// Запись сессии: настройки, с которыми она шла, и ввод по кадрам.
+///
+/// Текстовый формат, по строке на запись:
+/// `recording <версия>`, `config <строка файла настроек>`,
+/// затем `frame <dt>` и события кадра: `key <индекс> <0|1>`, `cursor <x> <y>`,
+/// `resize <w> <h>`, `scroll <dy>`, `char <код символа>`, `pad <0|1 подключён> <кнопки 0/1 подряд> <оси...>`.
+/// Числа пишутся так, что читаются обратно без потерь
+#[derive(Clone, Debug, Default)]
+pub struct Recording {
+    pub config: String,
+    pub frames: Vec<InputFrame>,
+}
+
+
+impl Recording {
+    #[cfg(feature = "graphics")]
+    pub fn new(config: String) -> Self {
+        Self { config, frames: Vec::new() }
+    }
+
+
+    #[cfg(feature = "graphics")]
+    pub fn push(&mut self, delta: f32, events: Vec<InputEvent>) {
+        self.frames.push(InputFrame { delta, events });
+    }
+
+
+    #[cfg(feature = "graphics")]
+    pub fn to_text(&self) -> String {
+        let mut text = format!("recording {}\n", VERSION);
+        for line in self.config.lines() {
+            text += &format!("config {}\n", line);
+        }
+        for frame in &self.frames {
+            text += &format!("frame {}\n", frame.delta);
+            for event in &frame.events {
+                match event {
+                    InputEvent::Key { index, pressed } => text += &format!("key {} {}\n", index, *pressed as u8),
+                    InputEvent::Cursor { x, y } => text += &format!("cursor {} {}\n", x, y),
+                    InputEvent::Resize { width, height } => text += &format!("resize {} {}\n", width, height),
+                    InputEvent::Scroll { y } => text += &format!("scroll {}\n", y),
+                    InputEvent::Char(c) => text += &format!("char {}\n", *c as u32),
+                    InputEvent::Gamepad(state) => {
+                        let buttons: String = state.buttons.iter().map(|b| if *b { '1' } else { '0' }).collect();
+                        let axes: Vec<String> = state.axes.iter().map(f32::to_string).collect();
+                        text += &format!("pad {} {} {}\n", state.connected as u8, buttons, axes.join(" "));
+                    }
+                }
+            }
+        }
+        text
+    }
+
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines().enumerate();
+        match lines.next() {
+            Some((_, line)) if line.trim() == format!("recording {}", VERSION) => {}
+            _ => return Err(format!("not a version {} recording", VERSION)),
+        }
+
+        let mut recording = Self::default();
+        for (line_number, line) in lines {
+            let error = |message: String| format!("line {}: {}", line_number + 1, message);
+            let (tag, rest) = line.split_once(' ').unwrap_or((line, ""));
+            if tag == "config" {
+                recording.config += rest;
+                recording.config.push('\n');
+                continue;
+            }
+
+            let values: Vec<&str> = rest.split_whitespace().collect();
+            let at = |i: usize| values.get(i).copied().unwrap_or("");
+            if tag == "frame" {
+                recording.frames.push(InputFrame { delta: number(at(0)).map_err(error)?, events: Vec::new() });
+                continue;
+            }
+
+            let event = match tag {
+                "key" => InputEvent::Key {
+                    index: number(at(0)).map_err(error)?,
+                    pressed: number::<u8>(at(1)).map_err(error)? != 0,
+                },
+                "cursor" => InputEvent::Cursor { x: number(at(0)).map_err(error)?, y: number(at(1)).map_err(error)? },
+                "resize" => InputEvent::Resize {
+                    width: number(at(0)).map_err(error)?,
+                    height: number(at(1)).map_err(error)?,
+                },
+                "scroll" => InputEvent::Scroll { y: number(at(0)).map_err(error)? },
+                "char" => {
+                    let code = number::<u32>(at(0)).map_err(error)?;
+                    InputEvent::Char(char::from_u32(code).ok_or_else(|| error(format!("invalid character {}", code)))?)
+                }
+                "pad" => {
+                    let connected = number::<u8>(at(0)).map_err(error)? != 0;
+                    let mut state = GamepadState { connected, ..Default::default() };
+                    let buttons = values.get(1).map_or(&[][..], |b| b.as_bytes());
+                    if buttons.len() != BUTTON_COUNT || values.len() != 2 + AXIS_COUNT {
+                        return Err(error("invalid gamepad state".to_string()));
+                    }
+                    for (button, c) in state.buttons.iter_mut().zip(buttons) {
+                        *button = *c == b'1';
+                    }
+                    for (index, axis) in state.axes.iter_mut().enumerate() {
+                        *axis = number(at(2 + index)).map_err(error)?;
+                    }
+                    InputEvent::Gamepad(state)
+                }
+                "" => continue,
+                other => return Err(error(format!("unknown entry '{}'", other))),
+            };
+            recording.frames.last_mut().ok_or_else(|| error("event before first frame".to_string()))?.events.push(event);
+        }
+        Ok(recording)
+    }
+
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("can't read recording '{}': {}", path, e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+
+    #[cfg(feature = "graphics")]
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        write_binary_file(path, self.to_text().as_bytes())
+    }
+}
+
+
+/// Воспроизведение записи. Ввод записи живёт в своих `Events`, поэтому
+/// клавиши, кнопки и геймпад, зажатые в окне, к нему не примешиваются
+pub struct Replay {
+    frames: std::vec::IntoIter<InputFrame>,
+    events: Events,
+}
+
+
+impl Replay {
+    pub fn new(recording: Recording) -> Self {
+        Self { frames: recording.frames.into_iter(), events: Events::new() }
+    }
+
+
+    pub fn is_finished(&self) -> bool {
+        self.frames.len() == 0
+    }
+
+
+    /// Длительность и ввод следующего кадра записи. Когда запись кончилась -
+    /// `None` и живой ввод `live`, которому передаётся захват курсора
+    pub fn input<'a>(&'a mut self, live: &'a mut Events) -> (Option<f32>, &'a mut Events) {
+        let Some(frame) = self.frames.next() else {
+            return (None, live);
+        };
+        self.events.begin_frame();
+        for event in &frame.events {
+            self.events.replay_event(event);
+        }
+        if self.is_finished() {
+            live.cursor_locked = self.events.cursor_locked;
+        }
+        (Some(frame.delta), &mut self.events)
+    }
+}
+
+
+fn number<T: FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid number '{}'", value))
+}
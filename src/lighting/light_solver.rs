@@ -2,6 +2,13 @@ use std::collections::VecDeque;
 
 use crate::voxels::{ chunks::Chunks, BlockRegistry, CHUNK_D, CHUNK_H, CHUNK_W };
 
+/// Канал небесного света
+pub const SKY_CHANNEL: i32 = 3;
+/// Максимальный уровень света
+pub const MAX_LIGHT: u8 = 15;
+/// Индекс направления "вниз" в таблице соседей `solve`
+const DOWN: usize = 3;
+
 #[derive(Clone, Copy)]
 struct LightEntry {
     x: i32,
@@ -10,10 +17,20 @@ struct LightEntry {
     light: u8,
 }
 
+/// Работа решателя с последнего `take_stats`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SolverStats {
+    /// Обработано записей очередей
+    pub processed: usize,
+    /// Наибольшая суммарная длина очередей
+    pub peak: usize,
+}
+
 pub struct LightSolver {
     add_queue: VecDeque<LightEntry>,
     rem_queue: VecDeque<LightEntry>,
     channel: i32,
+    stats: SolverStats,
 }
 
 impl LightSolver {
@@ -22,9 +39,19 @@ impl LightSolver {
             add_queue: VecDeque::new(),
             rem_queue: VecDeque::new(),
             channel,
+            stats: SolverStats::default(),
         }
     }
 
+    pub fn take_stats(&mut self) -> SolverStats {
+        std::mem::take(&mut self.stats)
+    }
+
+    fn count(&mut self) {
+        self.stats.processed += 1;
+        self.stats.peak = self.stats.peak.max(self.add_queue.len() + self.rem_queue.len() + 1);
+    }
+
     pub fn add(&mut self, x: i32, y: i32, z: i32, emission: Option<i32>, chunks: &mut Chunks) {
         if let Some(emission) = emission {
             if emission <= 1 {
@@ -44,7 +71,6 @@ impl LightSolver {
                 entry.z as isize
             );
             if let Some(chunk) = chunk {
-                chunk.modified = true;
                 chunk.lightmap.set(
                     (entry.x as usize) - (chunk.x as usize) * CHUNK_W,
                     (entry.y as usize) - (chunk.y as usize) * CHUNK_H,
@@ -52,6 +78,7 @@ impl LightSolver {
                     self.channel as usize,
                     entry.light
                 );
+                chunks.set_modified(entry.x as isize, entry.y as isize, entry.z as isize);
             }
         } else {
             self.add(x as i32,y as i32,z as i32, Some(chunks.get_light(x as isize,y as isize,z as isize, self.channel as usize) as i32), chunks);
@@ -94,13 +121,20 @@ impl LightSolver {
                 self.channel as usize,
                 0
             );
+            chunks.set_modified(x, y, z);
         }
     }
 
+    /// Небесный свет полной яркости идёт вниз без затухания
+    fn is_sky_column(&self, direction: usize, light: u8) -> bool {
+        self.channel == SKY_CHANNEL && direction == DOWN && light == MAX_LIGHT
+    }
+
     pub fn solve(&mut self, blocks: &BlockRegistry, chunks: &mut Chunks) {
         let coords = [0, 0, 1, 0, 0, -1, 0, 1, 0, 0, -1, 0, 1, 0, 0, -1, 0, 0];
 
         while let Some(entry) = self.rem_queue.pop_front() {
+            self.count();
             for i in 0..6 {
                 let x = entry.x + coords[i * 3];
                 let y = entry.y + coords[i * 3 + 1];
@@ -118,7 +152,11 @@ impl LightSolver {
                         z as isize
                     )
                 {
-                    if light != 0 && light == entry.light - 1 {
+                    // Из-за поглощения в блоках сосед мог получить свет от entry
+                    // с любым уровнем ниже; более яркий сосед - самостоятельный источник
+                    let dependent = light < entry.light ||
+                        (light == MAX_LIGHT && self.is_sky_column(i, entry.light));
+                    if light != 0 && dependent {
                         let nentry = LightEntry { x, y, z, light };
                         self.rem_queue.push_back(nentry);
                         chunk.lightmap.set(
@@ -128,7 +166,7 @@ impl LightSolver {
                             self.channel as usize,
                             0
                         );
-                        chunk.modified = true;
+                        chunks.set_modified(x as isize, y as isize, z as isize);
                     } else if light >= entry.light {
                         let nentry = LightEntry { x, y, z, light };
                         self.add_queue.push_back(nentry);
@@ -138,6 +176,7 @@ impl LightSolver {
         }
 
         while let Some(entry) = self.add_queue.pop_front() {
+            self.count();
             if entry.light <= 1 {
                 continue;
             }
@@ -161,20 +200,25 @@ impl LightSolver {
                 {
                     if let Some(v) = v {
                         if let Some(block) = blocks.get(v.id){
-                            if block.light_passing && light + 2 <= entry.light {
+                            let nlight = if self.is_sky_column(i, entry.light) {
+                                MAX_LIGHT
+                            } else {
+                                entry.light - 1
+                            }.saturating_sub(block.light_absorption[self.channel as usize]);
+                            if block.light_passing && light < nlight {
                                 chunk.lightmap.set(
                                     (x as usize) - (chunk.x as usize) * CHUNK_W,
                                     (y as usize) - (chunk.y as usize) * CHUNK_H,
                                     (z as usize) - (chunk.z as usize) * CHUNK_D,
                                     self.channel as usize,
-                                    entry.light - 1
+                                    nlight
                                 );
-                                chunk.modified = true;
+                                chunks.set_modified(x as isize, y as isize, z as isize);
                                 let nentry = LightEntry {
                                     x,
                                     y,
                                     z,
-                                    light: entry.light - 1,
+                                    light: nlight,
                                 };
                                 self.add_queue.push_back(nentry);
                             }
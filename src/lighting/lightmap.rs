@@ -62,6 +62,7 @@ impl Lightmap {
         self.map[index] = (self.map[index] & 0xF0FF) | ((value as u16) << 8);
     }
 
+    #[allow(unused)]
     pub fn set_s(&mut self, x: usize, y: usize, z: usize, value: u8) {
         let index = y * CHUNK_D * CHUNK_W + z * CHUNK_W + x;
         self.map[index] = (self.map[index] & 0x0FFF) | ((value as u16) << 12);
@@ -1,6 +1,6 @@
 use crate::voxels::{ chunks::Chunks, BlockRegistry, CHUNK_D, CHUNK_H, CHUNK_VOL, CHUNK_W };
 
-use self::light_solver::LightSolver;
+use self::light_solver::{LightSolver, SolverStats, MAX_LIGHT};
 
 pub mod light_solver;
 pub mod lightmap;
@@ -25,6 +25,16 @@ impl Lighting {
         }
     }
 
+    /// Работа решателей R, G, B и неба с прошлого вызова
+    pub fn take_stats(&mut self) -> [SolverStats; 4] {
+        [
+            self.solver_r.take_stats(),
+            self.solver_g.take_stats(),
+            self.solver_b.take_stats(),
+            self.solver_s.take_stats(),
+        ]
+    }
+
     pub fn clear(&mut self, chunks: &mut Chunks) {
         for y in 0..chunks.h {
             for z in 0..chunks.d {
@@ -48,67 +58,25 @@ impl Lighting {
             for z in 0..d * CHUNK_D {
                 for x in 0..w * CHUNK_W {
                     let vox = chunks.get_voxel(x as isize, y as isize, z as isize);
-                    if let Some(vox) = vox {
-                        if vox.id == 3 {
-                            self.solver_r.add(x as i32, y as i32, z as i32, Some(15), chunks);
-                            self.solver_g.add(x as i32, y as i32, z as i32, Some(15), chunks);
-                            self.solver_b.add(x as i32, y as i32, z as i32, Some(15), chunks);
-                        }
-                    }
-                }
-            }
-        }
-
-        for z in 0..d * CHUNK_D {
-            for x in 0..w * CHUNK_W {
-                for y in 0..h * CHUNK_H {
-                    let vox = chunks.get_voxel(x as isize, y as isize, z as isize);
-                    if let Some(vox) = vox {
-                        if vox.id != 0 {
-                            break;
-                        }
-                        let voxel = chunks.get_mut_chunk_by_voxel(
-                            x as isize,
-                            y as isize,
-                            z as isize
-                        );
-                        if let Some(voxel) = voxel {
-                            voxel.lightmap.set_s(x % CHUNK_W, y % CHUNK_H, z % CHUNK_D, 0xf);
+                    let emission = vox.and_then(|vox| blocks.get(vox.id)).map(|block| block.emission);
+                    if let Some(emission) = emission {
+                        if emission != [0; 3] {
+                            self.solver_r.add(x as i32, y as i32, z as i32, Some(emission[0] as i32), chunks);
+                            self.solver_g.add(x as i32, y as i32, z as i32, Some(emission[1] as i32), chunks);
+                            self.solver_b.add(x as i32, y as i32, z as i32, Some(emission[2] as i32), chunks);
                         }
                     }
                 }
             }
         }
 
+        // Небо над загруженным миром считается открытым: засеиваем верхний
+        // слой, дальше решатель сам опустит свет по столбцам без затухания
+        let top = chunks.height_in_voxels() - 1;
         for z in 0..d * CHUNK_D {
             for x in 0..w * CHUNK_W {
-                for y in (0..=h * CHUNK_H - 1).rev() {
-                    let vox = chunks.get_voxel(x as isize, y as isize, z as isize);
-                    if let Some(vox) = vox {
-                        if vox.id != 0 {
-                            break;
-                        }
-
-                        if
-                            chunks.get_light((x as isize) - 1, y as isize, z as isize, 3) == 0 ||
-                            chunks.get_light((x as isize) + 1, y as isize, z as isize, 3) == 0 ||
-                            chunks.get_light(x as isize, (y as isize) - 1, z as isize, 3) == 0 ||
-                            chunks.get_light(x as isize, (y as isize) + 1, z as isize, 3) == 0 ||
-                            chunks.get_light(x as isize, y as isize, (z as isize) - 1, 3) == 0 ||
-                            chunks.get_light(x as isize, y as isize, (z as isize) + 1, 3) == 0
-                        {
-                            self.solver_s.add(x as i32, y as i32, z as i32, None, chunks);
-                        }
-                        if
-                            let Some(voxel) = chunks.get_mut_chunk_by_voxel(
-                                x as isize,
-                                y as isize,
-                                z as isize
-                            )
-                        {
-                            voxel.lightmap.set_s(x % CHUNK_W, y % CHUNK_H, z % CHUNK_D, 0xf);
-                        }
-                    }
+                if Self::is_light_passing(x as isize, top, z as isize, blocks, chunks) {
+                    self.solver_s.add(x as i32, top as i32, z as i32, Some(MAX_LIGHT as i32), chunks);
                 }
             }
         }
@@ -119,6 +87,61 @@ impl Lighting {
         self.solver_s.solve(&blocks, chunks);
     }
 
+    fn is_light_passing(x: isize, y: isize, z: isize, blocks: &BlockRegistry, chunks: &Chunks) -> bool {
+        match chunks.get_voxel(x, y, z) {
+            Some(voxel) => blocks.get(voxel.id).is_some_and(|block| block.light_passing),
+            None => false,
+        }
+    }
+
+    /// Пересчёт света после замены сразу многих блоков: источники и гашение
+    /// по всем изменённым клеткам ставятся в очереди вместе, и каждый решатель
+    /// проходит их один раз, а не по разу на блок
+    pub fn on_blocks_set(&mut self, positions: &[[isize; 3]], blocks: &BlockRegistry, chunks: &mut Chunks) {
+        for &[x, y, z] in positions {
+            self.solver_r.remove(x, y, z, chunks);
+            self.solver_g.remove(x, y, z, chunks);
+            self.solver_b.remove(x, y, z, chunks);
+            self.solver_s.remove(x, y, z, chunks);
+        }
+        self.solver_r.solve(blocks, chunks);
+        self.solver_g.solve(blocks, chunks);
+        self.solver_b.solve(blocks, chunks);
+        self.solver_s.solve(blocks, chunks);
+
+        // Источники ставятся первыми, чтобы свет соседей их не затёр
+        for &[x, y, z] in positions {
+            let emission = chunks.get_voxel(x, y, z).and_then(|voxel| blocks.get(voxel.id)).map(|block| block.emission);
+            if let Some(emission) = emission.filter(|emission| *emission != [0; 3]) {
+                let (x, y, z) = (x as i32, y as i32, z as i32);
+                self.solver_r.add(x, y, z, Some(emission[0] as i32), chunks);
+                self.solver_g.add(x, y, z, Some(emission[1] as i32), chunks);
+                self.solver_b.add(x, y, z, Some(emission[2] as i32), chunks);
+            }
+        }
+        let top = chunks.height_in_voxels() - 1;
+        for &[x, y, z] in positions {
+            if !Self::is_light_passing(x, y, z, blocks, chunks) {
+                continue;
+            }
+            if y == top {
+                self.solver_s.add(x as i32, y as i32, z as i32, Some(MAX_LIGHT as i32), chunks);
+            }
+            // В прозрачную клетку свет заходит от соседей; тёмные соседи пропускаются решателем
+            let (x, y, z) = (x as i32, y as i32, z as i32);
+            for (dx, dy, dz) in [(0, 1, 0), (0, -1, 0), (1, 0, 0), (-1, 0, 0), (0, 0, 1), (0, 0, -1)] {
+                self.solver_r.add(x + dx, y + dy, z + dz, None, chunks);
+                self.solver_g.add(x + dx, y + dy, z + dz, None, chunks);
+                self.solver_b.add(x + dx, y + dy, z + dz, None, chunks);
+                self.solver_s.add(x + dx, y + dy, z + dz, None, chunks);
+            }
+        }
+        self.solver_r.solve(blocks, chunks);
+        self.solver_g.solve(blocks, chunks);
+        self.solver_b.solve(blocks, chunks);
+        self.solver_s.solve(blocks, chunks);
+    }
+
     pub fn on_block_set(
         &mut self,
         x: isize,
@@ -137,17 +160,11 @@ impl Lighting {
             self.solver_g.solve(&blocks, chunks);
             self.solver_b.solve(&blocks, chunks);
 
-            if chunks.get_light(x, y + 1, z, 3) == 0xf {
-                for i in (0..=y).rev() {
-                    let voxel = chunks.get_voxel(x, i, z);
-                    if let Some(voxel) = voxel {
-                        if voxel.id != 0 {
-                            break;
-                        }
-                        self.solver_s.add(x as i32, i as i32, z as i32, Some(0xf), chunks);
-                    }
-                }
+            // Блок на самом верху мира открывает столбец небу напрямую
+            if y + 1 >= chunks.height_in_voxels() {
+                self.solver_s.add(x as i32, y as i32, z as i32, Some(MAX_LIGHT as i32), chunks);
             }
+
             let (x, y, z) = (x as i32, y as i32, z as i32);
             self.solver_r.add(x, y + 1, z, None, chunks);
             self.solver_g.add(x, y + 1, z, None, chunks);
@@ -179,19 +196,17 @@ impl Lighting {
             self.solver_b.solve(&blocks, chunks);
             self.solver_s.solve(&blocks, chunks);
         } else {
+            // Удаление небесного света в точке само гасит весь столбец под ней,
+            // а соседи с более ярким светом заново заливают освободившееся место
             self.solver_r.remove(x, y, z, chunks);
             self.solver_g.remove(x, y, z, chunks);
             self.solver_b.remove(x, y, z, chunks);
             self.solver_s.remove(x, y, z, chunks);
 
-            for i in (0..=y - 1).rev() {
-                self.solver_s.remove(x, i, z, chunks);
-                if let Some(voxel) = chunks.get_voxel(x, i - 1, z) {
-                    if i == 0 || voxel.id != 0 {
-                        break;
-                    }
-                }
+            if y + 1 >= chunks.height_in_voxels() && Self::is_light_passing(x, y, z, blocks, chunks) {
+                self.solver_s.add(x as i32, y as i32, z as i32, Some(MAX_LIGHT as i32), chunks);
             }
+
             self.solver_r.solve(&blocks, chunks);
             self.solver_g.solve(&blocks, chunks);
             self.solver_b.solve(&blocks, chunks);
@@ -199,7 +214,7 @@ impl Lighting {
 
             let block = blocks.get(id);
             if let Some(block) = block {
-                if block.emission[0] != 0 || block.emission[0] != 0 || block.emission[0] != 0 {
+                if block.emission[0] != 0 || block.emission[1] != 0 || block.emission[2] != 0 {
                     let (x, y, z) = (x as i32, y as i32, z as i32);
                     self.solver_r.add(x, y, z, Some(block.emission[0] as i32), chunks);
                     self.solver_g.add(x, y, z, Some(block.emission[1] as i32), chunks);
@@ -213,3 +228,139 @@ impl Lighting {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::light_solver::SKY_CHANNEL;
+    use crate::voxels::Block;
+
+    const AIR: u8 = 0;
+    const STONE: u8 = 1;
+    const LAMP: u8 = 2;
+    const RED_GLASS: u8 = 3;
+
+    fn blocks() -> BlockRegistry {
+        let mut blocks = BlockRegistry::new();
+        let mut air = Block::new(AIR as u32, "air", 0);
+        air.light_passing = true;
+        blocks.blocks[AIR as usize] = Some(air);
+        blocks.blocks[STONE as usize] = Some(Block::new(STONE as u32, "stone", 0));
+        let mut lamp = Block::new(LAMP as u32, "lamp", 0);
+        lamp.emission = [15; 3];
+        blocks.blocks[LAMP as usize] = Some(lamp);
+        let mut glass = Block::new(RED_GLASS as u32, "red_glass", 0);
+        glass.light_passing = true;
+        glass.light_absorption = [0, 15, 15, 0];
+        blocks.blocks[RED_GLASS as usize] = Some(glass);
+        blocks
+    }
+
+    /// Каменный чанк с тоннелем вдоль x на высоте 8 и лампой в его начале
+    fn tunnel() -> Chunks {
+        let mut chunks = Chunks::filled(1, 1, 1, STONE);
+        for x in 1..15 {
+            chunks.set(x, 8, 8, AIR as i32);
+        }
+        chunks.set(1, 8, 8, LAMP as i32);
+        chunks
+    }
+
+    fn rgb(chunks: &Chunks, x: isize) -> [u8; 3] {
+        [0, 1, 2].map(|channel| chunks.get_light(x, 8, 8, channel))
+    }
+
+    #[test]
+    fn red_glass_tints_light_behind_it() {
+        let blocks = blocks();
+        let mut chunks = tunnel();
+        chunks.set(4, 8, 8, RED_GLASS as i32);
+        let mut lighting = Lighting::new();
+        lighting.on_world_loaded(&blocks, &mut chunks);
+
+        assert_eq!(rgb(&chunks, 3), [13, 13, 13]);
+        assert_eq!(rgb(&chunks, 4), [12, 0, 0]);
+        assert_eq!(rgb(&chunks, 6), [10, 0, 0]);
+    }
+
+    #[test]
+    fn placing_and_breaking_glass_relights_tunnel() {
+        let blocks = blocks();
+        let mut chunks = tunnel();
+        let mut lighting = Lighting::new();
+        lighting.on_world_loaded(&blocks, &mut chunks);
+        assert_eq!(rgb(&chunks, 6), [10, 10, 10]);
+
+        chunks.set(4, 8, 8, RED_GLASS as i32);
+        lighting.on_block_set(4, 8, 8, RED_GLASS, &blocks, &mut chunks);
+        assert_eq!(rgb(&chunks, 6), [10, 0, 0]);
+
+        chunks.set(4, 8, 8, AIR as i32);
+        lighting.on_block_set(4, 8, 8, AIR, &blocks, &mut chunks);
+        assert_eq!(rgb(&chunks, 6), [10, 10, 10]);
+    }
+
+    #[test]
+    fn sky_light_is_not_tinted() {
+        let blocks = blocks();
+        let mut chunks = Chunks::filled(1, 1, 1, AIR);
+        chunks.set(8, 10, 8, RED_GLASS as i32);
+        let mut lighting = Lighting::new();
+        lighting.on_world_loaded(&blocks, &mut chunks);
+
+        assert_eq!(chunks.get_light(8, 9, 8, SKY_CHANNEL as usize), MAX_LIGHT);
+    }
+
+    fn sky(chunks: &Chunks, y: isize) -> u8 {
+        chunks.get_light(8, y, 8, SKY_CHANNEL as usize)
+    }
+
+    /// Два чанка воздуха друг над другом и каменная крыша 5x5 на высоте 20
+    fn roofed() -> Chunks {
+        let mut chunks = Chunks::filled(1, 2, 1, AIR);
+        for z in 6..=10 {
+            for x in 6..=10 {
+                chunks.set(x, 20, z, STONE as i32);
+            }
+        }
+        chunks
+    }
+
+    #[test]
+    fn roof_darkens_column_below() {
+        let blocks = blocks();
+        let mut chunks = roofed();
+        let mut lighting = Lighting::new();
+        lighting.on_world_loaded(&blocks, &mut chunks);
+
+        assert_eq!(sky(&chunks, 21), MAX_LIGHT);
+        // Под центром крыши свет только обходит её сбоку
+        assert_eq!(sky(&chunks, 19), MAX_LIGHT - 3);
+        assert_eq!(sky(&chunks, 5), MAX_LIGHT - 3);
+    }
+
+    #[test]
+    fn breaking_roof_brings_sky_back() {
+        let blocks = blocks();
+        let mut chunks = roofed();
+        let mut lighting = Lighting::new();
+        lighting.on_world_loaded(&blocks, &mut chunks);
+
+        chunks.set(8, 20, 8, AIR as i32);
+        lighting.on_block_set(8, 20, 8, AIR, &blocks, &mut chunks);
+        assert_eq!(sky(&chunks, 19), MAX_LIGHT);
+        assert_eq!(sky(&chunks, 5), MAX_LIGHT);
+    }
+
+    #[test]
+    fn sky_column_crosses_chunk_border() {
+        let blocks = blocks();
+        let mut chunks = Chunks::filled(1, 2, 1, AIR);
+        let mut lighting = Lighting::new();
+        lighting.on_world_loaded(&blocks, &mut chunks);
+
+        for y in 0..2 * CHUNK_H as isize {
+            assert_eq!(sky(&chunks, y), MAX_LIGHT, "y = {}", y);
+        }
+    }
+}
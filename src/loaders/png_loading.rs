@@ -1,13 +1,16 @@
+#[cfg(feature = "graphics")]
 use gl::types::*;
 use image::codecs::png;
-use image::ImageDecoder;
+use image::{ExtendedColorType, ImageDecoder, ImageEncoder};
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, BufWriter};
+#[cfg(feature = "graphics")]
 use std::os::raw::c_void;
 
+#[cfg(feature = "graphics")]
 use crate::graphics::Texture;
 
-fn load_png(file: &str) -> Result<(Vec<u8>, i32, i32), image::error::ImageError> {
+pub fn load_png(file: &str) -> Result<(Vec<u8>, i32, i32), image::error::ImageError> {
     let file = File::open(file)?;
     let reader = BufReader::new(file);
     let decoder = png::PngDecoder::new(reader)?;
@@ -19,20 +22,34 @@ fn load_png(file: &str) -> Result<(Vec<u8>, i32, i32), image::error::ImageError>
 
     decoder.read_image(&mut buf)?;
 
-    let mut flipped_buf = vec![0; buf.len()];
-    let row_size = (width as usize) * 4;
+    Ok((flip_rows(&buf, width as usize), width, height))
+}
+
+
+/// Переставляет строки RGBA картинки в обратном порядке: в PNG они идут
+/// сверху вниз, а в OpenGL - снизу вверх
+pub fn flip_rows(data: &[u8], width: usize) -> Vec<u8> {
+    let mut flipped_buf = vec![0; data.len()];
+    let row_size = width * 4;
     flipped_buf
         .chunks_exact_mut(row_size)
         .rev()
         .enumerate()
         .for_each(|(i, dest_row)| {
             let src_offset = i * row_size;
-            dest_row.copy_from_slice(&buf[src_offset..src_offset + row_size]);
+            dest_row.copy_from_slice(&data[src_offset..src_offset + row_size]);
         });
+    flipped_buf
+}
 
-    Ok((flipped_buf, width, height))
+/// Сохраняет RGBA пиксели, строки которых идут снизу вверх, как их отдаёт `load_png`
+pub fn save_png(file: &str, data: &[u8], width: u32, height: u32) -> Result<(), image::error::ImageError> {
+    let flipped_buf = flip_rows(data, width as usize);
+    let writer = BufWriter::new(File::create(file)?);
+    png::PngEncoder::new(writer).write_image(&flipped_buf, width, height, ExtendedColorType::Rgba8)
 }
 
+#[cfg(feature = "graphics")]
 fn create_texture_from_png(data: Vec<u8>, width: i32, height: i32) -> GLuint {
     let mut texture: GLuint = 0;
     unsafe {
@@ -67,14 +84,8 @@ fn create_texture_from_png(data: Vec<u8>, width: i32, height: i32) -> GLuint {
     texture
 }
 
-pub fn load_texture(filename: &str) -> Result<Texture, String> {
-    let (data, width, height) = match load_png(filename) {
-        Ok((data, width, height)) => (data, width, height),
-        Err(err) => {
-            return Err(format!("Error loading PNG: {}", err));
-        }
-    };
-
+#[cfg(feature = "graphics")]
+pub fn texture_from_data(data: Vec<u8>, width: i32, height: i32) -> Texture {
     let texture_id = create_texture_from_png(data, width, height);
-    Ok(Texture::new(texture_id, width, height))
+    Texture::new(texture_id, width, height)
 }
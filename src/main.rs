@@ -1,13 +1,16 @@
+
+use std::io;
+
 use settings::*;
-use math::*;
 
-use window::{Window, Events, Camera};
-use assets::{Assets, BlocksController};
-use voxels::{Chunks, Chunk, CHUNK_VOL};
-use graphics::{VoxelRenderer, LineBatch, Mesh};
-use lighting::Lighting;
-use files::{read_binary_file, write_binary_file};
-use world::draw_world;
+use assets::{Atlas, AtlasBuilder, BlocksController};
+use cli::{commands, Command, PlayOptions};
+use config::{Config, CONFIG_PATH};
+use input::recording::{world_snapshot_path, Recording, Session};
+use math::Vec3;
+use player::Player;
+use voxels::{BlockRegistry, Chunks};
+use world::{world_file, World, WorldTime};
 
 
 mod settings;
@@ -20,233 +23,173 @@ mod lighting;
 mod files;
 mod world;
 mod loaders;
-
-
-const WIDTH: u32 = 1280;
-const HEIGHT: u32 = 720;
-
-const VERTICES: [f32; 8] = [
-    // x   | y
-    -0.01, -0.01, 0.01, 0.01,
-
-    -0.01, 0.01, 0.01, -0.01,
-];
-
-#[allow(non_upper_case_globals)]
-const attrs: [i32; 2] = [2, 0]; // null terminator
-
-fn main() {
-    let mut window = Window::new(WIDTH, HEIGHT, "Window 2.0").unwrap();
-    let mut events = Events::new();
-
-    events.initialize(&mut window);
-
-    let mut assets = Assets::init().expect("fail load assets");
-    println!("load assets: ok");
+mod physics;
+mod player;
+mod cli;
+mod config;
+mod input;
+#[cfg(feature = "graphics")]
+mod game;
+mod headless;
+mod controls;
+mod ui;
+mod stats;
+mod console;
+mod edit;
+mod export;
+mod fluids;
+mod ticks;
+
+
+/// Атлас текстур и реестр блоков: нужны и с окном, и без него
+fn load_content() -> (Atlas, BlocksController) {
+    println!("start atlas build");
+    let mut atlas_builder = AtlasBuilder::new(ATLAS_PADDING);
+    atlas_builder.add_dir(TEXTURES_PATH).expect("fail load textures");
+    let atlas = atlas_builder.build();
+    println!("atlas build: ok ({}x{})", atlas.width, atlas.height);
 
     println!("start block init");
     let mut blocks_controller = BlocksController::init().unwrap();
 
-    blocks_controller.setup_blocks();
+    blocks_controller.setup_blocks(&atlas).expect("fail setup blocks");
     println!("blocks init: ok");
 
-    println!("start chunks init");
-    let mut chunks = Chunks::new(4, 4, 4);
-    let mut meshes = Vec::with_capacity(chunks.volume);
-    for _ in 0..chunks.volume {
-        meshes.push(None);
-    }
-    println!("chunks init: ok");
-
-    println!("start init renderer");
-    let mut renderer = VoxelRenderer::new(1024 * 1024 * 8);
-    let mut line_batch = LineBatch::new(4096);
-    println!("renderer init: ok");
-
-    window.gl_setting();
-
-    let crosshair = Mesh::new(VERTICES.as_ptr(), 4, attrs.as_ptr());
-    println!("crosshair init: ok");
-
-    let mut camera = Camera::new(Vec3::new(5.0, 5.0, 20.0), (70.0_f32).to_radians());
-    println!("camera init: ok");
-
-    println!("start settings init");
-    let mut last_time = window.glfw.get_time();
-    let mut _delta: f32 = 0.0;
-
-    let mut cam_x = 0.0;
-    let mut cam_y = 0.0;
-
-    let speed = 15.0;
-
-    let mut choosen_block: i32 = 1;
-    println!("settings init: ok");
-
-    println!("start lighting init");
-    let mut lighting = Lighting::new();
-
-    lighting.on_world_loaded(&blocks_controller.blocks, &mut chunks);
-    println!("lighting init: ok");
+    (atlas, blocks_controller)
+}
 
+/// Открывает мир из файла или генерирует новый, если файла нет.
+/// Файл, который не удалось прочитать, не трогается: игра с ним не
+/// запускается, чтобы не сохранить поверх него новый мир.
+/// Освещение считается, только если его нет в файле
+fn open_world(config: &Config, eye: Vec3, blocks: &BlockRegistry) -> Result<World, String> {
     println!("start world loaded");
-    let mut buffer = vec![0u8; chunks.volume * CHUNK_VOL];
-    let _result = read_binary_file("res/worlds/world.bin", &mut buffer);
-    chunks.read(&buffer);
-
-    lighting.clear(&mut chunks);
-    lighting.on_world_loaded(&blocks_controller.blocks, &mut chunks);
+    let (chunks, has_light) = match world_file::load(&config.world.path) {
+        Ok((header, chunks)) => (chunks, header.has_light()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            println!("world not found, generating new");
+            let size = config.world.size;
+            (Chunks::new(size.w, size.h, size.d, config.world.seed), false)
+        }
+        Err(e) => return Err(format!("can't load world '{}': {}", config.world.path, e)),
+    };
+    let mut player = Player::new(eye);
+    player.walk_speed = config.gameplay.walk_speed;
+    player.fly_speed = config.gameplay.fly_speed;
+    let mut world = World::new(chunks, WorldTime::new(config.gameplay.day_length), player);
     println!("finish world loaded");
 
+    if !has_light {
+        println!("start lighting init");
+        world.relight(blocks);
+        println!("lighting init: ok");
+    }
+    world.fluids.schedule_unsettled(world.tick, &world.chunks, blocks);
+    world.ticks.schedule_unsettled(world.tick, &world.chunks, blocks);
+    Ok(world)
+}
+
+/// Настройки и источник ввода для запуска игры. При воспроизведении
+/// настройки берутся из записи, а мир - из снимка рядом с ней
+fn play_session(loaded: &Config, options: &PlayOptions) -> Result<(Config, Session), String> {
+    if let Some(path) = &options.replay {
+        let recording = Recording::load(path)?;
+        let mut config = Config::parse(&recording.config).map_err(|e| format!("{}: {}", path, e))?;
+        config.apply(options)?;
+        config.world.path = world_snapshot_path(path);
+        return Ok((config, Session::Replay(recording)));
+    }
 
-    println!("start main loop");
-    while !window.should_close() {
-        let current_time = window.glfw.get_time();
-        _delta = (current_time - last_time) as f32;
-        last_time = current_time;
-
-        if events.jpressed(ESCAPE) {
-            window.close();
-        }
-
-        if events.jpressed(TAB) {
-            window.window.set_cursor_mode(events.toggle_cursor());
+    let mut config = loaded.clone();
+    config.apply(options)?;
+    let session = match &options.record {
+        Some(path) => Session::Record(path.clone()),
+        None => Session::Live,
+    };
+    Ok((config, session))
+}
+
+/// Всё, что нужно для `play`, или выход с кодом 2 при ошибке в аргументах
+fn prepare_play(loaded: &Config, options: &PlayOptions) -> (Config, Session, Option<String>) {
+    let (config, session) = match play_session(loaded, options) {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(2);
         }
-
-        for i in 0..5 {
-            if events.jpressed(K_0 + i) {
-                choosen_block = i;
+    };
+    let script = match &options.script {
+        Some(path) => match std::fs::read_to_string(path) {
+            Ok(script) => Some(script),
+            Err(e) => {
+                eprintln!("error: can't read script '{}': {}", path, e);
+                std::process::exit(2);
             }
-        }
-
-        if events.pressed(W) {
-            camera.position += camera.front * _delta * speed;
-        }
+        },
+        None => None,
+    };
+    (config, session, script)
+}
 
-        if events.pressed(S) {
-            camera.position -= camera.front * _delta * speed;
-        }
-
-        if events.pressed(D) {
-            camera.position -= camera.right * _delta * speed;
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let cli = match cli::parse(&args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
         }
-
-        if events.pressed(A) {
-            camera.position += camera.right * _delta * speed;
+    };
+    let config_path = cli.config.as_deref();
+    let loaded = match Config::load(config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(2);
         }
-
-        if events.cursor_locked {
-            cam_y -= (-events.delta_y / (window.height() as f32)) * 2.0;
-            cam_x += (-events.delta_x / (window.height() as f32)) * 2.0;
-
-            if cam_y < -(89.0_f32).to_radians() {
-                cam_y = -(89.0_f32).to_radians();
-            }
-            if cam_y > (89.0_f32).to_radians() {
-                cam_y = (89.0_f32).to_radians();
+    };
+
+    let result = match cli.command {
+        Command::Play(options) => {
+            let (config, session, script) = prepare_play(&loaded, &options);
+            // Сюда попадают только изменения, сделанные в игре, без флагов
+            let saved = loaded.clone();
+            // Менять настройки может только игра с окном
+            #[cfg(feature = "graphics")]
+            let (mut config, mut saved) = (config, saved);
+            let result = match session {
+                #[cfg(feature = "graphics")]
+                session if !options.headless => game::play(&mut config, &mut saved, session, script.as_deref()),
+                Session::Live => headless::run(&config, None, script.as_deref()),
+                Session::Replay(recording) => headless::run(&config, Some(recording), script.as_deref()),
+                Session::Record(path) => Err(format!("can't record '{}' without a window", path)),
+            };
+            if saved != loaded {
+                println!("saving settings");
+                if let Err(e) = saved.save(config_path) {
+                    eprintln!("error: can't save config '{}': {}", config_path.unwrap_or(CONFIG_PATH), e);
+                }
             }
-
-            camera.rotation = Quat::IDENTITY;
-            camera.rotate(cam_y, cam_x, 0.0);
+            result.map_err(io::Error::other)
         }
-
-        let mut end = glam::Vec3::default();
-        let mut norm = glam::Vec3::default();
-        let mut iend = glam::Vec3::default();
-
-        if let Some(_vox) = chunks.ray_cast(
-            camera.position,
-            camera.front,
-            10.0,
-            &mut end,
-            &mut norm,
-            &mut iend)
-        {
-            line_batch.boxx(
-                iend.x + 0.5,
-                iend.y + 0.5,
-                iend.z + 0.5,
-                1.01,
-                1.01,
-                1.01,
-                0.0,
-                0.0,
-                0.0,
-                1.0
-            );
-
-            if events.jclicked(LCM) && events.cursor_locked {
-                let x = iend.x as isize;
-                let y = iend.y as isize;
-                let z = iend.z as isize;
-
-                chunks.set(x, y, z, 0);
-
-                lighting.on_block_set(x, y, z, 0, &blocks_controller.blocks, &mut chunks);
-            }
-
-            if events.jclicked(PCM) && events.cursor_locked {
-                let x = (iend.x + norm.x) as isize;
-                let y = (iend.y + norm.y) as isize;
-                let z = (iend.z + norm.z) as isize;
-                chunks.set(x, y, z, choosen_block);
-
-                lighting.on_block_set(x, y, z, choosen_block as u8, &blocks_controller.blocks, &mut chunks);
-            }
+        Command::Generate { world, seed, size } => {
+            let seed = seed.unwrap_or(loaded.world.seed);
+            let size = size.unwrap_or(loaded.world.size);
+            commands::generate(&world, seed, size)
         }
-
-        let mut closes: Vec<Option<Chunk>> = vec![None; 27];
-
-        for i in 0..chunks.volume {
-            if let Some(chunk) = chunks.chunks.get_mut(i) {
-                if !chunk.modified {
-                    continue;
-                }
-                chunk.modified = false;
-            }
-            let chunk = &chunks.chunks[i];
-
-            if let Some(mesh) = meshes[i].take() {
-                // Освобождаем ресурсы меша
-                drop(mesh);
-            }
-
-            // Инициализируем массив closes снова
-            for elem in &mut closes {
-                *elem = None;
-            }
-
-            for j in 0..chunks.volume {
-                let other = &chunks.chunks[j];
-                let ox = other.x - chunk.x;
-                let oy = other.y - chunk.y;
-                let oz = other.z - chunk.z;
-
-                if ox.abs() > 1 || oy.abs() > 1 || oz.abs() > 1 {
-                    continue;
-                }
-
-                let index = ((oy + 1) * 3 + (oz + 1)) * 3 + (ox + 1);
-                closes[index as usize] = Some(other.clone());
-            }
-
-            let mesh = renderer.render(chunk, &closes, &blocks_controller.blocks);
-            meshes[i] = Some(mesh);
+        Command::Info { world } => commands::info(&world),
+        Command::Convert { from, to, size } => commands::convert(&from, &to, size),
+        Command::Relight { world } => commands::relight(&world),
+        Command::Export { world, output, time } => commands::export(&world, &output, time),
+        Command::Render(options) => commands::render(&options, loaded.graphics.fov),
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            Ok(())
         }
+    };
 
-
-        draw_world(&assets, &camera, &window, &chunks, &meshes, &crosshair, &mut line_batch);
-
-        window.swap_buffers();
-        events.pull_events(&mut window);
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
     }
-    println!("finish main loop");
-
-    println!("saving world");
-    let mut buffer = vec![0u8; chunks.volume * CHUNK_VOL];
-    chunks.write(&mut buffer);
-    let _result = write_binary_file("res/worlds/world.bin", &buffer);
-    println!("world saved in {} bytes on res/worlds/world.bin", chunks.volume * CHUNK_VOL, );
-
-    window.terminate();
-}
\ No newline at end of file
+}
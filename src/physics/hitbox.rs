@@ -0,0 +1,28 @@
+use glam::Vec3;
+
+
+/// Осевой ограничивающий параллелепипед с центром в `position`
+#[derive(Clone, Copy, Debug)]
+pub struct Hitbox {
+    pub position: Vec3,
+    pub half_size: Vec3,
+    pub velocity: Vec3,
+    pub grounded: bool,
+}
+
+
+impl Hitbox {
+    pub fn new(position: Vec3, half_size: Vec3) -> Self {
+        Self { position, half_size, velocity: Vec3::ZERO, grounded: false }
+    }
+
+
+    pub fn min(&self) -> Vec3 {
+        self.position - self.half_size
+    }
+
+
+    pub fn max(&self) -> Vec3 {
+        self.position + self.half_size
+    }
+}
@@ -0,0 +1,219 @@
+use glam::Vec3;
+
+use crate::voxels::{BlockRegistry, Chunks};
+
+pub use hitbox::Hitbox;
+
+pub mod hitbox;
+
+
+/// Зазор между хитбоксом и гранью блока после столкновения
+const EPSILON: f32 = 0.001;
+/// Максимальное смещение за один подшаг, чтобы не проскакивать блоки
+const MAX_SUBSTEP: f32 = 0.4;
+
+
+/// Является ли воксель препятствием. Всё ниже мира твёрдое, чтобы игрок
+/// не падал бесконечно; остальные воксели вне мира свободны
+pub fn is_obstacle(x: isize, y: isize, z: isize, chunks: &Chunks, blocks: &BlockRegistry) -> bool {
+    if y < 0 {
+        return true;
+    }
+    chunks.get_voxel(x, y, z)
+        .and_then(|voxel| blocks.get(voxel.id))
+        .is_some_and(|block| block.obstacle)
+}
+
+
+/// Пересекает ли параллелепипед [min, max] хотя бы один твёрдый блок
+pub fn collides(min: Vec3, max: Vec3, chunks: &Chunks, blocks: &BlockRegistry) -> bool {
+    let (x1, y1, z1) = (min.x.floor() as isize, min.y.floor() as isize, min.z.floor() as isize);
+    let (x2, y2, z2) = (max.x.floor() as isize, max.y.floor() as isize, max.z.floor() as isize);
+    for y in y1..=y2 {
+        for z in z1..=z2 {
+            for x in x1..=x2 {
+                if is_obstacle(x, y, z, chunks, blocks) {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+
+/// Двигает хитбокс по его скорости за `delta` секунд, разрешая столкновения
+/// по каждой оси отдельно. `step_height` - высота, на которую хитбокс,
+/// стоящий на земле, может шагнуть при упоре в стену.
+pub fn move_hitbox(hitbox: &mut Hitbox, delta: f32, step_height: f32, chunks: &Chunks, blocks: &BlockRegistry) {
+    let offset = hitbox.velocity * delta;
+    let distance = offset.abs().max_element();
+    let steps = ((distance / MAX_SUBSTEP).ceil() as usize).max(1);
+    let offset = offset / steps as f32;
+
+    let was_grounded = hitbox.grounded;
+    hitbox.grounded = false;
+
+    for _ in 0..steps {
+        move_axis(hitbox, 1, offset.y, chunks, blocks);
+
+        let grounded = was_grounded || hitbox.grounded;
+        for axis in [0, 2] {
+            if !move_axis(hitbox, axis, offset[axis], chunks, blocks) && grounded && step_height > 0.0 {
+                step_up(hitbox, axis, offset[axis], step_height, chunks, blocks);
+            }
+        }
+    }
+}
+
+
+/// Сдвиг вдоль одной оси; возвращает false, если упёрлись в блок
+fn move_axis(hitbox: &mut Hitbox, axis: usize, amount: f32, chunks: &Chunks, blocks: &BlockRegistry) -> bool {
+    if amount == 0.0 {
+        return true;
+    }
+
+    let mut min = hitbox.min();
+    let mut max = hitbox.max();
+    min[axis] += amount;
+    max[axis] += amount;
+
+    if !collides(min, max, chunks, blocks) {
+        hitbox.position[axis] += amount;
+        return true;
+    }
+
+    // Прижимаемся вплотную к грани блока, в который упёрлись
+    if amount > 0.0 {
+        let face = max[axis].floor();
+        hitbox.position[axis] = face - hitbox.half_size[axis] - EPSILON;
+    } else {
+        let face = min[axis].floor() + 1.0;
+        hitbox.position[axis] = face + hitbox.half_size[axis] + EPSILON;
+        if axis == 1 {
+            hitbox.grounded = true;
+        }
+    }
+    hitbox.velocity[axis] = 0.0;
+    false
+}
+
+
+fn step_up(hitbox: &mut Hitbox, axis: usize, amount: f32, step_height: f32, chunks: &Chunks, blocks: &BlockRegistry) {
+    let bottom = hitbox.min().y;
+    let lift = (bottom + step_height).floor() - bottom + EPSILON;
+    if lift <= 0.0 || lift > step_height + EPSILON {
+        return;
+    }
+
+    let mut min = hitbox.min();
+    let mut max = hitbox.max();
+    min.y += lift;
+    max.y += lift;
+    min[axis] += amount;
+    max[axis] += amount;
+
+    if !collides(min, max, chunks, blocks) {
+        hitbox.position.y += lift;
+        hitbox.position[axis] += amount;
+        hitbox.grounded = true;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxels::Block;
+
+    const AIR: u8 = 0;
+    const STONE: u8 = 1;
+    const DELTA: f32 = 0.05;
+    const GRAVITY: f32 = 20.0;
+    const STEP_HEIGHT: f32 = 0.5;
+
+    fn blocks() -> BlockRegistry {
+        let mut blocks = BlockRegistry::new();
+        let mut air = Block::new(AIR as u32, "air", 0);
+        air.obstacle = false;
+        blocks.blocks[AIR as usize] = Some(air);
+        blocks.blocks[STONE as usize] = Some(Block::new(STONE as u32, "stone", 0));
+        blocks
+    }
+
+    /// Пустой чанк с каменным полом на высоте 2 для `x < floor_end`
+    fn floor(floor_end: isize) -> Chunks {
+        let mut chunks = Chunks::filled(1, 1, 1, AIR);
+        for z in 0..16 {
+            for x in 0..floor_end {
+                chunks.set(x, 2, z, STONE as i32);
+            }
+        }
+        chunks
+    }
+
+    fn player(x: f32, y: f32, z: f32) -> Hitbox {
+        Hitbox::new(Vec3::new(x, y, z), Vec3::new(0.3, 0.9, 0.3))
+    }
+
+    fn simulate(hitbox: &mut Hitbox, seconds: f32, chunks: &Chunks, blocks: &BlockRegistry) {
+        for _ in 0..(seconds / DELTA) as usize {
+            hitbox.velocity.y -= GRAVITY * DELTA;
+            move_hitbox(hitbox, DELTA, STEP_HEIGHT, chunks, blocks);
+        }
+    }
+
+    #[test]
+    fn lands_on_ground() {
+        let blocks = blocks();
+        let chunks = floor(16);
+        let mut hitbox = player(4.5, 10.0, 4.5);
+        simulate(&mut hitbox, 2.0, &chunks, &blocks);
+
+        assert!(hitbox.grounded);
+        assert!((hitbox.min().y - 3.0).abs() < 0.01, "feet at {}", hitbox.min().y);
+        assert_eq!(hitbox.velocity.y, 0.0);
+    }
+
+    #[test]
+    fn slides_along_wall() {
+        let blocks = blocks();
+        let mut chunks = floor(16);
+        for y in 3..6 {
+            for z in 0..16 {
+                chunks.set(8, y, z, STONE as i32);
+            }
+        }
+        let mut hitbox = player(6.5, 3.91, 2.5);
+        hitbox.velocity = Vec3::new(5.0, 0.0, 5.0);
+        simulate(&mut hitbox, 1.0, &chunks, &blocks);
+
+        assert!(hitbox.max().x < 8.0);
+        assert!(hitbox.max().x > 7.99);
+        assert!((hitbox.position.z - 7.5).abs() < 0.01, "z = {}", hitbox.position.z);
+    }
+
+    #[test]
+    fn steps_off_ledge_and_falls() {
+        let blocks = blocks();
+        let chunks = floor(8);
+        let mut hitbox = player(6.5, 3.91, 4.5);
+        simulate(&mut hitbox, 0.2, &chunks, &blocks);
+        assert!(hitbox.grounded);
+
+        hitbox.velocity.x = 5.0;
+        simulate(&mut hitbox, 0.5, &chunks, &blocks);
+        assert!(hitbox.min().y < 3.0);
+    }
+
+    #[test]
+    fn world_bottom_is_solid() {
+        let blocks = blocks();
+        let chunks = floor(16);
+        let mut hitbox = player(-5.5, 10.0, 4.5);
+        simulate(&mut hitbox, 3.0, &chunks, &blocks);
+
+        assert!(hitbox.grounded);
+        assert!(hitbox.min().y.abs() < 0.01, "feet at {}", hitbox.min().y);
+    }
+}
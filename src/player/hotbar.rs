@@ -0,0 +1,133 @@
+use crate::voxels::BlockRegistry;
+
+
+/// Число слотов панели быстрого доступа
+pub const HOTBAR_SIZE: usize = 9;
+
+
+/// Панель быстрого доступа: блоки, которые игрок ставит, по слотам.
+/// Слоты задаются именами блоков, так что не зависят от порядка id
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hotbar {
+    pub slots: [Option<u8>; HOTBAR_SIZE],
+    pub selected: usize,
+}
+
+
+impl Hotbar {
+    /// Слоты по именам блоков; пустое имя - пустой слот.
+    /// Неизвестные имена и воздух тоже дают пустой слот с предупреждением
+    pub fn from_names(names: &[String], blocks: &BlockRegistry) -> Self {
+        let mut slots = [None; HOTBAR_SIZE];
+        for (slot, name) in slots.iter_mut().zip(names) {
+            if name.is_empty() {
+                continue;
+            }
+            match blocks.find(name) {
+                Some(id) if id != 0 => *slot = Some(id),
+                _ => eprintln!("Warning: hotbar block '{}' is not registered", name),
+            }
+        }
+        Self { slots, selected: 0 }
+    }
+
+
+    /// Имена блоков по слотам для файла настроек
+    #[cfg(feature = "graphics")]
+    pub fn names(&self, blocks: &BlockRegistry) -> Vec<String> {
+        self.slots
+            .iter()
+            .map(|slot| slot.and_then(|id| blocks.get(id)).map_or_else(String::new, |block| block.name.clone()))
+            .collect()
+    }
+
+
+    pub fn selected_block(&self) -> Option<u8> {
+        self.slots[self.selected]
+    }
+
+
+    pub fn select(&mut self, slot: usize) {
+        if slot < HOTBAR_SIZE {
+            self.selected = slot;
+        }
+    }
+
+
+    /// Сдвигает выбор на `steps` слотов по кругу, отрицательные - влево
+    pub fn scroll(&mut self, steps: i32) {
+        self.selected = (self.selected as i32 + steps).rem_euclid(HOTBAR_SIZE as i32) as usize;
+    }
+
+
+    /// Кладёт блок в выбранный слот
+    pub fn set_selected(&mut self, id: u8) {
+        self.slots[self.selected] = (id != 0).then_some(id);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxels::Block;
+
+    fn blocks() -> BlockRegistry {
+        let mut blocks = BlockRegistry::new();
+        for (id, name) in [(0, "air"), (1, "stone"), (4, "planks")] {
+            blocks.blocks[id] = Some(Block::new(id as u32, name, 0));
+        }
+        blocks
+    }
+
+    fn hotbar(names: &[&str]) -> Hotbar {
+        let names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+        Hotbar::from_names(&names, &blocks())
+    }
+
+    #[test]
+    fn slots_come_from_names() {
+        let named = hotbar(&["planks", "", "marble", "air", "stone"]);
+        assert_eq!(named.slots[..5], [Some(4), None, None, None, Some(1)]);
+        assert_eq!(named.slots[5..], [None; HOTBAR_SIZE - 5]);
+        assert_eq!(named.selected_block(), Some(4));
+
+        // Лишние имена отбрасываются
+        let long = vec!["stone"; HOTBAR_SIZE + 3];
+        assert_eq!(hotbar(&long).slots, [Some(1); HOTBAR_SIZE]);
+    }
+
+    #[test]
+    fn scroll_wraps_around() {
+        let mut hotbar = hotbar(&[]);
+        hotbar.scroll(-1);
+        assert_eq!(hotbar.selected, HOTBAR_SIZE - 1);
+        hotbar.scroll(2);
+        assert_eq!(hotbar.selected, 1);
+        hotbar.scroll(HOTBAR_SIZE as i32 * 3 + 2);
+        assert_eq!(hotbar.selected, 3);
+    }
+
+    #[test]
+    fn select_ignores_missing_slots() {
+        let mut hotbar = hotbar(&[]);
+        hotbar.select(4);
+        assert_eq!(hotbar.selected, 4);
+        hotbar.select(HOTBAR_SIZE);
+        assert_eq!(hotbar.selected, 4);
+    }
+
+    #[test]
+    fn set_selected_fills_and_clears_slot() {
+        let mut hotbar = hotbar(&["stone"]);
+        hotbar.select(2);
+        hotbar.set_selected(4);
+        assert_eq!(hotbar.slots[2], Some(4));
+        assert_eq!(hotbar.selected_block(), Some(4));
+
+        // Воздух очищает слот
+        hotbar.set_selected(0);
+        assert_eq!(hotbar.slots[2], None);
+        assert_eq!(hotbar.slots[0], Some(1));
+    }
+}
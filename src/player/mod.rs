@@ -0,0 +1,229 @@
+use glam::Vec3;
+
+use crate::physics::{self, Hitbox};
+use crate::voxels::{BlockRegistry, Chunks};
+
+pub use hotbar::{Hotbar, HOTBAR_SIZE};
+
+pub mod hotbar;
+
+
+/// Половина размеров хитбокса игрока
+const PLAYER_HALF_SIZE: Vec3 = Vec3::new(0.3, 0.9, 0.3);
+/// Высота глаз относительно центра хитбокса
+const EYE_OFFSET: f32 = 0.7;
+
+const GRAVITY: f32 = 28.0;
+const JUMP_VELOCITY: f32 = 9.0;
+const MAX_FALL_SPEED: f32 = 60.0;
+const STEP_HEIGHT: f32 = 1.0;
+
+const WALK_SPEED: f32 = 5.0;
+const FLY_SPEED: f32 = 15.0;
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveMode {
+    /// Гравитация и столкновения
+    Walk,
+    /// Свободный полёт сквозь блоки
+    Fly,
+}
+
+
+/// Ввод игрока за один шаг симуляции
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlayerInput {
+    /// Желаемое направление движения в мировых координатах
+    pub direction: Vec3,
+    pub jump: bool,
+    pub descend: bool,
+}
+
+
+pub struct Player {
+    pub hitbox: Hitbox,
+    pub mode: MoveMode,
+    pub walk_speed: f32,
+    pub fly_speed: f32,
+}
+
+
+impl Player {
+    /// `eye` - позиция глаз (камеры) игрока
+    pub fn new(eye: Vec3) -> Self {
+        let position = eye - Vec3::new(0.0, EYE_OFFSET, 0.0);
+        Self {
+            hitbox: Hitbox::new(position, PLAYER_HALF_SIZE),
+            mode: MoveMode::Fly,
+            walk_speed: WALK_SPEED,
+            fly_speed: FLY_SPEED,
+        }
+    }
+
+
+    pub fn eye_position(&self) -> Vec3 {
+        self.hitbox.position + Vec3::new(0.0, EYE_OFFSET, 0.0)
+    }
+
+
+    /// Переносит игрока так, чтобы глаза оказались в `eye`
+    pub fn teleport(&mut self, eye: Vec3) {
+        self.hitbox.position = eye - Vec3::new(0.0, EYE_OFFSET, 0.0);
+        self.hitbox.velocity = Vec3::ZERO;
+        self.hitbox.grounded = false;
+    }
+
+
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            MoveMode::Walk => MoveMode::Fly,
+            MoveMode::Fly => MoveMode::Walk,
+        };
+        self.hitbox.velocity = Vec3::ZERO;
+        self.hitbox.grounded = false;
+    }
+
+
+    /// Шаг физики игрока. Зависит только от мира, ввода и `delta`,
+    /// поэтому может выполняться без окна
+    pub fn update(&mut self, input: &PlayerInput, chunks: &Chunks, blocks: &BlockRegistry, delta: f32) {
+        match self.mode {
+            MoveMode::Fly => {
+                let mut direction = input.direction.normalize_or_zero();
+                if input.jump {
+                    direction.y += 1.0;
+                }
+                if input.descend {
+                    direction.y -= 1.0;
+                }
+                self.hitbox.velocity = Vec3::ZERO;
+                self.hitbox.position += direction * self.fly_speed * delta;
+            }
+            MoveMode::Walk => {
+                let horizontal = Vec3::new(input.direction.x, 0.0, input.direction.z).normalize_or_zero();
+                let velocity = &mut self.hitbox.velocity;
+                velocity.x = horizontal.x * self.walk_speed;
+                velocity.z = horizontal.z * self.walk_speed;
+
+                if input.jump && self.hitbox.grounded {
+                    velocity.y = JUMP_VELOCITY;
+                }
+                velocity.y = (velocity.y - GRAVITY * delta).max(-MAX_FALL_SPEED);
+
+                physics::move_hitbox(&mut self.hitbox, delta, STEP_HEIGHT, chunks, blocks);
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxels::Block;
+    use crate::world::TICK_DELTA;
+
+    const AIR: u8 = 0;
+    const STONE: u8 = 1;
+    /// Высота ног игрока, стоящего на полу
+    const FLOOR_TOP: f32 = 3.0;
+
+    fn blocks() -> BlockRegistry {
+        let mut blocks = BlockRegistry::new();
+        let mut air = Block::new(AIR as u32, "air", 0);
+        air.obstacle = false;
+        blocks.blocks[AIR as usize] = Some(air);
+        blocks.blocks[STONE as usize] = Some(Block::new(STONE as u32, "stone", 0));
+        blocks
+    }
+
+    /// Пустой чанк с каменным полом на высоте 2
+    fn floor() -> Chunks {
+        let mut chunks = Chunks::filled(1, 1, 1, AIR);
+        for z in 0..16 {
+            for x in 0..16 {
+                chunks.set(x, 2, z, STONE as i32);
+            }
+        }
+        chunks
+    }
+
+    /// Идущий игрок, уже стоящий на полу в точке `x, z`
+    fn standing(x: f32, z: f32, chunks: &Chunks, blocks: &BlockRegistry) -> Player {
+        let mut player = Player::new(Vec3::new(x, FLOOR_TOP + 2.0, z));
+        player.toggle_mode();
+        run(&mut player, &PlayerInput::default(), 1.0, chunks, blocks);
+        assert!(player.hitbox.grounded);
+        player
+    }
+
+    fn run(player: &mut Player, input: &PlayerInput, seconds: f32, chunks: &Chunks, blocks: &BlockRegistry) {
+        for _ in 0..(seconds / TICK_DELTA).round() as usize {
+            player.update(input, chunks, blocks, TICK_DELTA);
+        }
+    }
+
+    fn feet(player: &Player) -> f32 {
+        player.hitbox.min().y
+    }
+
+    #[test]
+    fn steps_up_one_block() {
+        let blocks = blocks();
+        let mut chunks = floor();
+        for z in 0..16 {
+            for x in 8..16 {
+                chunks.set(x, 3, z, STONE as i32);
+            }
+        }
+        let mut player = standing(5.5, 4.5, &chunks, &blocks);
+        assert!((feet(&player) - FLOOR_TOP).abs() < 0.01);
+
+        let forward = PlayerInput { direction: Vec3::X, ..Default::default() };
+        run(&mut player, &forward, 1.0, &chunks, &blocks);
+        assert!((feet(&player) - (FLOOR_TOP + 1.0)).abs() < 0.01, "feet at {}", feet(&player));
+        assert!(player.hitbox.position.x > 9.0);
+    }
+
+    #[test]
+    fn jumps_from_ground() {
+        let blocks = blocks();
+        let chunks = floor();
+        let mut player = standing(5.5, 4.5, &chunks, &blocks);
+
+        let jump = PlayerInput { jump: true, ..Default::default() };
+        player.update(&jump, &chunks, &blocks, TICK_DELTA);
+        assert!(!player.hitbox.grounded);
+        let mut highest = feet(&player);
+        for _ in 0..(1.0 / TICK_DELTA) as usize {
+            // Прыжок в воздухе ничего не добавляет
+            player.update(&jump, &chunks, &blocks, TICK_DELTA);
+            highest = highest.max(feet(&player));
+            if player.hitbox.grounded {
+                break;
+            }
+        }
+
+        // v² / 2g = 81 / 56
+        let apex = JUMP_VELOCITY * JUMP_VELOCITY / (2.0 * GRAVITY);
+        assert!((highest - FLOOR_TOP - apex).abs() < 0.15, "jumped {}", highest - FLOOR_TOP);
+        assert!(player.hitbox.grounded);
+        assert!((feet(&player) - FLOOR_TOP).abs() < 0.01);
+    }
+
+    #[test]
+    fn fly_mode_ignores_gravity() {
+        let blocks = blocks();
+        let chunks = floor();
+        let mut player = Player::new(Vec3::new(5.5, 10.0, 4.5));
+        assert_eq!(player.mode, MoveMode::Fly);
+
+        run(&mut player, &PlayerInput::default(), 1.0, &chunks, &blocks);
+        assert_eq!(player.eye_position(), Vec3::new(5.5, 10.0, 4.5));
+
+        let up = PlayerInput { jump: true, ..Default::default() };
+        run(&mut player, &up, 0.5, &chunks, &blocks);
+        assert!((player.eye_position().y - (10.0 + FLY_SPEED * 0.5)).abs() < 0.01);
+    }
+}
@@ -1,24 +1,25 @@
-use gl::types::GLenum;
-use glfw::ffi::{KEY_0, KEY_A, KEY_D, KEY_E, KEY_ESCAPE, KEY_Q, KEY_S, KEY_TAB, KEY_W, MOUSE_BUTTON_LEFT, MOUSE_BUTTON_MIDDLE, MOUSE_BUTTON_RIGHT};
-use std::ffi::c_int;
+/// Файл мира
+pub const WORLD_PATH: &str = "res/worlds/world.bin";
+/// Папка с текстурами блоков для атласа
+pub const TEXTURES_PATH: &str = "res/textures";
+/// Соответствие палитры моделей MagicaVoxel блокам
+pub const VOX_PALETTE_PATH: &str = "res/vox_palette.txt";
+/// Папка для снимков экрана
+#[cfg(feature = "graphics")]
+pub const SCREENSHOTS_PATH: &str = "screenshots";
+/// Растровый шрифт интерфейса: сетка 16x16 символов ASCII
+#[cfg(feature = "graphics")]
+pub const FONT_PATH: &str = "res/fonts/font.png";
+/// Поля вокруг текстур в атласе, в пикселях
+pub const ATLAS_PADDING: u32 = 4;
+/// Длина игровых суток в секундах
+pub const DAY_LENGTH: f32 = 600.0;
+/// Размер мира в чанках
+pub const WORLD_W: usize = 4;
+pub const WORLD_H: usize = 4;
+pub const WORLD_D: usize = 4;
 
 
 /// тип рендера треугольники
-pub const TRIANGLES: GLenum = gl::TRIANGLES;
-
-
-/// Левая кнопка мыши
-pub const LCM: c_int = MOUSE_BUTTON_LEFT;
-/// Правая кнопка мыши
-pub const PCM: c_int = MOUSE_BUTTON_RIGHT;
-/// Средняя кнопка мыши
-pub const SCM: c_int = MOUSE_BUTTON_MIDDLE;
-pub const ESCAPE: c_int = KEY_ESCAPE;
-pub const TAB: c_int = KEY_TAB;
-pub const W: c_int = KEY_W;
-pub const A: c_int = KEY_A;
-pub const S: c_int = KEY_S;
-pub const D: c_int = KEY_D;
-pub const Q: c_int = KEY_Q;
-pub const E: c_int = KEY_E;
-pub const K_0: c_int = KEY_0;
\ No newline at end of file
+#[cfg(feature = "graphics")]
+pub const TRIANGLES: gl::types::GLenum = gl::TRIANGLES;
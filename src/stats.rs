@@ -0,0 +1,138 @@
+use glam::Vec3;
+
+use crate::lighting::light_solver::SolverStats;
+use crate::voxels::{BlockRegistry, CHUNK_D, CHUNK_H, CHUNK_W};
+use crate::world::{World, REACH_DISTANCE};
+
+
+/// За сколько секунд усредняется время кадра
+const FRAME_WINDOW: f32 = 0.5;
+/// Названия каналов света в том порядке, в каком они хранятся
+const CHANNELS: [&str; 4] = ["r", "g", "b", "s"];
+
+
+/// Блок под прицелом
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TargetInfo {
+    pub position: [isize; 3],
+    pub id: u8,
+    pub name: String,
+    /// Свет R, G, B и неба перед гранью, в которую смотрит игрок:
+    /// внутри непрозрачного блока света нет, а на грани видно именно этот
+    pub light: [u8; 4],
+}
+
+
+/// Статистика движка за последний кадр. Собирается и для отладочного
+/// экрана, и без окна, чтобы замеры можно было сравнивать между запусками
+#[derive(Clone, Debug, Default)]
+pub struct DebugStats {
+    pub fps: f32,
+    /// Среднее время кадра в миллисекундах
+    pub frame_time: f32,
+    frames: u32,
+    window: f32,
+
+    pub tick: u64,
+    pub camera: Vec3,
+    pub chunk: [isize; 3],
+    pub target: Option<TargetInfo>,
+
+    pub chunks_loaded: usize,
+    /// Чанков с непустым мешем
+    pub chunks_meshed: usize,
+    /// Чанков, нарисованных в этом кадре
+    pub chunks_drawn: usize,
+    /// Чанков, меш которых перестроен в этом кадре
+    pub chunks_rebuilt: usize,
+    pub vertices: usize,
+
+    /// Очереди решателей света по каналам за кадр
+    pub light: [SolverStats; 4],
+}
+
+
+impl DebugStats {
+    /// Учитывает кадр длительностью `delta` секунд
+    pub fn frame(&mut self, delta: f32) {
+        self.frames += 1;
+        self.window += delta;
+        if self.window >= FRAME_WINDOW {
+            self.frame_time = self.window / self.frames as f32 * 1000.0;
+            self.fps = self.frames as f32 / self.window;
+            self.frames = 0;
+            self.window = 0.0;
+        }
+    }
+
+
+    /// Положение камеры, блок под прицелом и очереди освещения.
+    /// Забирает счётчики решателей, так что вызывается раз в кадр
+    pub fn observe(&mut self, world: &mut World, eye: Vec3, look: Vec3, blocks: &BlockRegistry) {
+        self.tick = world.tick;
+        self.camera = eye;
+        self.chunk = [
+            (eye.x / CHUNK_W as f32).floor() as isize,
+            (eye.y / CHUNK_H as f32).floor() as isize,
+            (eye.z / CHUNK_D as f32).floor() as isize,
+        ];
+        self.chunks_loaded = world.chunks.volume;
+        self.light = world.lighting.take_stats();
+
+        let mut end = Vec3::default();
+        let mut norm = Vec3::default();
+        let mut iend = Vec3::default();
+        let chunks = &world.chunks;
+        self.target = chunks.ray_cast(eye, look, REACH_DISTANCE, &mut end, &mut norm, &mut iend).map(|voxel| {
+            let face = iend + norm;
+            let light = [0, 1, 2, 3].map(|channel| chunks.get_light(face.x as isize, face.y as isize, face.z as isize, channel));
+            TargetInfo {
+                position: [iend.x as isize, iend.y as isize, iend.z as isize],
+                id: voxel.id,
+                name: blocks.get(voxel.id).map_or_else(|| "?".to_string(), |block| block.name.clone()),
+                light,
+            }
+        });
+    }
+
+
+    /// Размеры мешей всех чанков в вершинах; 0 - меша нет или он пуст
+    pub fn meshes(&mut self, sizes: impl Iterator<Item = usize>) {
+        self.chunks_meshed = 0;
+        self.vertices = 0;
+        for size in sizes.filter(|size| *size > 0) {
+            self.chunks_meshed += 1;
+            self.vertices += size;
+        }
+    }
+
+
+    /// Строки отладочного экрана
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("{:.0} fps ({:.2} ms)  tick {}", self.fps, self.frame_time, self.tick),
+            format!("xyz: {:.2} / {:.2} / {:.2}", self.camera.x, self.camera.y, self.camera.z),
+            format!("chunk: {} {} {}", self.chunk[0], self.chunk[1], self.chunk[2]),
+        ];
+        lines.push(match &self.target {
+            Some(target) => {
+                let [x, y, z] = target.position;
+                let light: Vec<String> = CHANNELS.iter().zip(target.light).map(|(c, l)| format!("{}{}", c, l)).collect();
+                format!("target: {} {} {}  {} ({})  light {}", x, y, z, target.name, target.id, light.join(" "))
+            }
+            None => "target: -".to_string(),
+        });
+        lines.push(format!(
+            "chunks: {} loaded, {} meshed, {} drawn, {} rebuilt",
+            self.chunks_loaded, self.chunks_meshed, self.chunks_drawn, self.chunks_rebuilt
+        ));
+        lines.push(format!("vertices: {}", self.vertices));
+        let queues: Vec<String> = CHANNELS
+            .iter()
+            .zip(&self.light)
+            .map(|(c, s)| format!("{} {}/{}", c, s.processed, s.peak))
+            .collect();
+        lines.push(format!("light queues (done/peak): {}", queues.join("  ")));
+        lines
+    }
+}
@@ -0,0 +1,389 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::voxels::{BlockRegistry, Chunks, CHUNK_D, CHUNK_H, CHUNK_VOL, CHUNK_W};
+
+
+/// Случайных шагов на секцию (чанк) за шаг симуляции
+pub const RANDOM_TICKS: u32 = 3;
+/// Больше запланированных обновлений за шаг не делаем, остальные ждут следующего шага
+const MAX_UPDATES: usize = 4096;
+/// Шагов между изменением под падающим блоком и его падением
+const FALL_DELAY: u64 = 2;
+
+const NEIGHBOURS: [[isize; 3]; 7] = [[0, 0, 0], [1, 0, 0], [-1, 0, 0], [0, 1, 0], [0, -1, 0], [0, 0, 1], [0, 0, -1]];
+
+
+/// Очередь обновлений клеток по шагам. Клетка стоит в очереди один раз:
+/// повторная постановка лишь переносит её на более ранний шаг. Порядок
+/// по шагу и координатам, поэтому один и тот же мир обновляется одинаково
+#[derive(Default)]
+pub struct TickQueue {
+    queue: BTreeSet<(u64, [isize; 3])>,
+    pending: BTreeMap<[isize; 3], u64>,
+}
+
+
+impl TickQueue {
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+
+    pub fn schedule(&mut self, position: [isize; 3], at: u64) {
+        if let Some(&scheduled) = self.pending.get(&position) {
+            if scheduled <= at {
+                return;
+            }
+            self.queue.remove(&(scheduled, position));
+        }
+        self.pending.insert(position, at);
+        self.queue.insert((at, position));
+    }
+
+
+    /// Следующая клетка, чей шаг не позже `tick`
+    pub fn pop_due(&mut self, tick: u64) -> Option<[isize; 3]> {
+        let &(at, position) = self.queue.first()?;
+        if at > tick {
+            return None;
+        }
+        self.queue.pop_first();
+        self.pending.remove(&position);
+        Some(position)
+    }
+}
+
+
+/// Поведение блока на шагах симуляции
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Behaviour {
+    /// Трава: на случайном шаге зарастает блок `onto` рядом, если над ним
+    /// светло, и сама становится `onto` под непрозрачным блоком
+    Spreads { onto: u8 },
+    /// Песок, гравий: падает, пока снизу воздух или жидкость
+    Falls,
+    /// Посев растёт на случайных шагах, стадия хранится в `Voxel::states`
+    Grows { stages: u8 },
+    /// Через `period` шагов после установки становится блоком `into`
+    Toggles { into: u8, period: u64 },
+}
+
+
+impl Behaviour {
+    /// Через сколько шагов после изменения рядом блок обновляется;
+    /// `None` у блоков, которые живут только случайными шагами
+    pub fn delay(&self) -> Option<u64> {
+        match self {
+            Behaviour::Falls => Some(FALL_DELAY),
+            Behaviour::Toggles { period, .. } => Some(*period),
+            Behaviour::Spreads { .. } | Behaviour::Grows { .. } => None,
+        }
+    }
+}
+
+
+/// Запланированные и случайные обновления блоков с поведением.
+/// Случайные клетки выбираются хешем зерна мира, шага и секции, так что
+/// при одном зерне мир меняется одинаково
+#[derive(Default)]
+pub struct BlockTicks {
+    scheduled: TickQueue,
+}
+
+
+impl BlockTicks {
+    /// Сколько обновлений ждёт очереди
+    pub fn pending(&self) -> usize {
+        self.scheduled.len()
+    }
+
+
+    /// Ставит в очередь клетку и её соседей, если их блок обновляется по расписанию
+    pub fn schedule_around(&mut self, position: [isize; 3], tick: u64, chunks: &Chunks, blocks: &BlockRegistry) {
+        for direction in NEIGHBOURS {
+            let position = offset(position, direction);
+            if let Some(delay) = behaviour_at(position, chunks, blocks).and_then(|behaviour| behaviour.delay()) {
+                self.scheduled.schedule(position, tick + delay);
+            }
+        }
+    }
+
+
+    /// Ставит в очередь блоки, которые ждали своего шага до сохранения мира:
+    /// переключатели и падающие блоки над пустотой
+    pub fn schedule_unsettled(&mut self, tick: u64, chunks: &Chunks, blocks: &BlockRegistry) {
+        let size = [chunks.w * CHUNK_W, chunks.h * CHUNK_H, chunks.d * CHUNK_D];
+        for y in 0..size[1] as isize {
+            for z in 0..size[2] as isize {
+                for x in 0..size[0] as isize {
+                    let position = [x, y, z];
+                    let waiting = match behaviour_at(position, chunks, blocks) {
+                        Some(Behaviour::Toggles { .. }) => true,
+                        Some(Behaviour::Falls) => can_fall_into(offset(position, [0, -1, 0]), chunks, blocks),
+                        _ => false,
+                    };
+                    if waiting {
+                        self.schedule_around(position, tick, chunks, blocks);
+                    }
+                }
+            }
+        }
+    }
+
+
+    /// Запланированные обновления, чей шаг наступил, затем случайные.
+    /// Возвращает клетки, где сменился блок
+    pub fn update(&mut self, tick: u64, chunks: &mut Chunks, blocks: &BlockRegistry) -> Vec<[isize; 3]> {
+        let mut changed = Vec::new();
+        for _ in 0..MAX_UPDATES {
+            match self.scheduled.pop_due(tick) {
+                Some(position) => scheduled_tick(position, chunks, blocks, &mut changed),
+                None => break,
+            }
+        }
+
+        for index in 0..chunks.chunks.len() {
+            let chunk = &chunks.chunks[index];
+            let origin = [chunk.x * CHUNK_W as isize, chunk.y * CHUNK_H as isize, chunk.z * CHUNK_D as isize];
+            for n in 0..RANDOM_TICKS {
+                let random = mix(&[chunks.seed as u64, tick, index as u64, n as u64]);
+                let voxel = (random % CHUNK_VOL as u64) as usize;
+                let local = [voxel % CHUNK_W, voxel / (CHUNK_W * CHUNK_D), voxel / CHUNK_W % CHUNK_D];
+                let position = offset(origin, local.map(|axis| axis as isize));
+                random_tick(position, random / CHUNK_VOL as u64, chunks, blocks, &mut changed);
+            }
+        }
+        changed
+    }
+}
+
+
+fn scheduled_tick(position: [isize; 3], chunks: &mut Chunks, blocks: &BlockRegistry, changed: &mut Vec<[isize; 3]>) {
+    let [x, y, z] = position;
+    let Some(&voxel) = chunks.get_voxel(x, y, z) else {
+        return;
+    };
+    match blocks.get(voxel.id).and_then(|block| block.behaviour) {
+        Some(Behaviour::Falls) => {
+            let below = offset(position, [0, -1, 0]);
+            if can_fall_into(below, chunks, blocks) {
+                set(position, 0, chunks, changed);
+                set(below, voxel.id, chunks, changed);
+            }
+        }
+        Some(Behaviour::Toggles { into, .. }) => set(position, into, chunks, changed),
+        _ => {}
+    }
+}
+
+
+/// `random` - оставшиеся случайные биты, из них выбирается сосед
+fn random_tick(position: [isize; 3], random: u64, chunks: &mut Chunks, blocks: &BlockRegistry, changed: &mut Vec<[isize; 3]>) {
+    let [x, y, z] = position;
+    let Some(&voxel) = chunks.get_voxel(x, y, z) else {
+        return;
+    };
+    match blocks.get(voxel.id).and_then(|block| block.behaviour) {
+        Some(Behaviour::Spreads { onto }) => {
+            if !is_open_above(position, chunks, blocks) {
+                set(position, onto, chunks, changed);
+                return;
+            }
+            // Сосед в кубе 3x5x3: на блок выше и на три ниже
+            let target = offset(position, [(random % 3) as isize - 1, (random / 3 % 5) as isize - 3, (random / 15 % 3) as isize - 1]);
+            let [tx, ty, tz] = target;
+            let lit = (0..4).any(|channel| chunks.get_light(tx, ty + 1, tz, channel) >= 9);
+            if id_at(target, chunks) == Some(onto) && is_open_above(target, chunks, blocks) && lit {
+                set(target, voxel.id, chunks, changed);
+            }
+        }
+        Some(Behaviour::Grows { stages }) if voxel.states + 1 < stages => chunks.set_states(x, y, z, voxel.states + 1),
+        _ => {}
+    }
+}
+
+
+fn set(position: [isize; 3], id: u8, chunks: &mut Chunks, changed: &mut Vec<[isize; 3]>) {
+    let [x, y, z] = position;
+    chunks.set(x, y, z, id as i32);
+    changed.push(position);
+}
+
+
+fn offset([x, y, z]: [isize; 3], [dx, dy, dz]: [isize; 3]) -> [isize; 3] {
+    [x + dx, y + dy, z + dz]
+}
+
+
+fn id_at([x, y, z]: [isize; 3], chunks: &Chunks) -> Option<u8> {
+    chunks.get_voxel(x, y, z).map(|voxel| voxel.id)
+}
+
+
+fn behaviour_at(position: [isize; 3], chunks: &Chunks, blocks: &BlockRegistry) -> Option<Behaviour> {
+    blocks.get(id_at(position, chunks)?)?.behaviour
+}
+
+
+/// Падающий блок проходит сквозь воздух и жидкости, но не сквозь край мира
+fn can_fall_into(position: [isize; 3], chunks: &Chunks, blocks: &BlockRegistry) -> bool {
+    match id_at(position, chunks) {
+        Some(0) => true,
+        Some(id) => blocks.get(id).is_some_and(|block| block.fluid.is_some()),
+        None => false,
+    }
+}
+
+
+/// Над блоком пропускающий свет блок или небо
+fn is_open_above(position: [isize; 3], chunks: &Chunks, blocks: &BlockRegistry) -> bool {
+    match id_at(offset(position, [0, 1, 0]), chunks) {
+        Some(id) => blocks.get(id).is_none_or(|block| block.light_passing),
+        None => true,
+    }
+}
+
+
+/// Хеш чисел, SplitMix64 по цепочке
+fn mix(values: &[u64]) -> u64 {
+    let mut hash: u64 = 0;
+    for value in values {
+        hash = hash.wrapping_add(*value).wrapping_add(0x9e3779b97f4a7c15);
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+        hash ^= hash >> 31;
+    }
+    hash
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::Player;
+    use crate::voxels::Block;
+    use crate::world::{TickInput, World, WorldTime};
+    use glam::Vec3;
+
+    const AIR: u8 = 0;
+    const STONE: u8 = 1;
+    const GRASS: u8 = 2;
+    const DIRT: u8 = 10;
+    const SAND: u8 = 11;
+    const LAMP_ON: u8 = 14;
+    const LAMP_OFF: u8 = 15;
+    const PERIOD: u64 = 60;
+
+    fn blocks() -> BlockRegistry {
+        let mut blocks = BlockRegistry::new();
+        let mut air = Block::new(AIR as u32, "air", 0);
+        air.light_passing = true;
+        air.obstacle = false;
+        let mut grass = Block::new(GRASS as u32, "grass", 0);
+        grass.behaviour = Some(Behaviour::Spreads { onto: DIRT });
+        let mut sand = Block::new(SAND as u32, "sand", 0);
+        sand.behaviour = Some(Behaviour::Falls);
+        let mut on = Block::new(LAMP_ON as u32, "lamp_on", 0);
+        on.behaviour = Some(Behaviour::Toggles { into: LAMP_OFF, period: PERIOD });
+        let mut off = Block::new(LAMP_OFF as u32, "lamp_off", 0);
+        off.behaviour = Some(Behaviour::Toggles { into: LAMP_ON, period: PERIOD });
+        for block in [air, Block::new(STONE as u32, "stone", 0), grass, Block::new(DIRT as u32, "dirt", 0), sand, on, off] {
+            let id = block.id as usize;
+            blocks.blocks[id] = Some(block);
+        }
+        blocks
+    }
+
+    /// Чанк с полом из земли на `y = 0` и полосой травы вдоль `x` при `z = 0`
+    fn world(seed: u32, blocks: &BlockRegistry) -> World {
+        let mut chunks = Chunks::filled(1, 1, 1, AIR);
+        chunks.seed = seed;
+        for z in 0..CHUNK_D as isize {
+            for x in 0..CHUNK_W as isize {
+                chunks.set(x, 0, z, if z == 0 { GRASS } else { DIRT } as i32);
+            }
+        }
+        let mut world = World::new(chunks, WorldTime::new(600.0), Player::new(Vec3::new(14.5, 3.0, 14.5)));
+        world.relight(blocks);
+        world
+    }
+
+    fn run(world: &mut World, ticks: u64, blocks: &BlockRegistry) {
+        for _ in 0..ticks {
+            world.update(&TickInput::default(), blocks);
+        }
+    }
+
+    fn id(world: &World, [x, y, z]: [isize; 3]) -> u8 {
+        world.chunks.get_voxel(x, y, z).unwrap().id
+    }
+
+    #[test]
+    fn queue_orders_and_dedups() {
+        let mut queue = TickQueue::default();
+        queue.schedule([1, 0, 0], 5);
+        queue.schedule([2, 0, 0], 3);
+        queue.schedule([0, 0, 0], 3);
+        // Более поздний шаг не переносит клетку, более ранний - переносит
+        queue.schedule([1, 0, 0], 7);
+        queue.schedule([1, 0, 0], 2);
+        assert_eq!(queue.len(), 3);
+
+        assert_eq!(queue.pop_due(1), None);
+        assert_eq!(queue.pop_due(3), Some([1, 0, 0]));
+        assert_eq!(queue.pop_due(3), Some([0, 0, 0]));
+        assert_eq!(queue.pop_due(3), Some([2, 0, 0]));
+        assert_eq!(queue.pop_due(100), None);
+        assert_eq!(queue.len(), 0);
+    }
+
+    #[test]
+    fn sand_falls_to_the_floor() {
+        let blocks = blocks();
+        let mut world = world(1, &blocks);
+        world.set_block(8, 10, 8, SAND, &blocks);
+
+        // Клетка за `FALL_DELAY` шагов
+        run(&mut world, FALL_DELAY * 4, &blocks);
+        assert_eq!(id(&world, [8, 6, 8]), SAND);
+        assert_eq!(id(&world, [8, 10, 8]), AIR);
+
+        run(&mut world, 40, &blocks);
+        assert_eq!(id(&world, [8, 1, 8]), SAND);
+        assert!((2..=10).all(|y| id(&world, [8, y, 8]) == AIR));
+        assert_eq!(world.ticks.pending(), 0);
+    }
+
+    #[test]
+    fn toggles_follow_period() {
+        let blocks = blocks();
+        let mut world = world(1, &blocks);
+        world.set_block(4, 1, 4, LAMP_ON, &blocks);
+
+        run(&mut world, PERIOD - 1, &blocks);
+        assert_eq!(id(&world, [4, 1, 4]), LAMP_ON);
+        run(&mut world, 1, &blocks);
+        assert_eq!(id(&world, [4, 1, 4]), LAMP_OFF);
+        run(&mut world, PERIOD - 1, &blocks);
+        assert_eq!(id(&world, [4, 1, 4]), LAMP_OFF);
+        run(&mut world, 1, &blocks);
+        assert_eq!(id(&world, [4, 1, 4]), LAMP_ON);
+    }
+
+    #[test]
+    fn same_seed_same_world() {
+        let blocks = blocks();
+        let grass = |world: &World| world.chunks.chunks[0].voxels.iter().filter(|voxel| voxel.id == GRASS).count();
+        let simulate = |seed| {
+            let mut world = world(seed, &blocks);
+            world.set_block(8, 10, 8, SAND, &blocks);
+            world.set_block(4, 1, 4, LAMP_ON, &blocks);
+            run(&mut world, 3000, &blocks);
+            world
+        };
+
+        let first = simulate(7);
+        assert!(grass(&first) > CHUNK_W, "grass has not spread");
+        assert_eq!(first.checksum(), simulate(7).checksum());
+    }
+}
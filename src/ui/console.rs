@@ -0,0 +1,35 @@
+use glam::{Vec2, Vec4};
+
+use crate::console::Console;
+use super::*;
+
+
+const PANEL_COLOR: Vec4 = Vec4::new(0.0, 0.0, 0.0, 0.6);
+const OUTPUT_COLOR: Vec4 = Vec4::new(0.85, 0.85, 0.85, 1.0);
+const INPUT_COLOR: Vec4 = Vec4::new(1.0, 1.0, 1.0, 1.0);
+/// Сколько последних строк вывода видно
+const VISIBLE_LINES: usize = 12;
+const PADDING: f32 = 2.0;
+
+
+/// Консоль во всю ширину окна над панелью быстрого доступа
+pub fn draw_console(ui: &mut UiRenderer, console: &Console) {
+    let screen = ui.screen;
+    let rows = VISIBLE_LINES + 1;
+    let size = Vec2::new(screen.width / screen.scale, rows as f32 * LINE_HEIGHT + PADDING * 2.0);
+    // Над панелью и названием выбранного блока
+    let bottom = SLOT_SIZE + HOTBAR_MARGIN * 2.0 + LINE_HEIGHT + 2.0;
+    let panel = screen.place(Anchor::BottomLeft, Vec2::new(0.0, -bottom), size);
+    ui.rect(panel, PANEL_COLOR);
+
+    let x = panel.x + PADDING * screen.scale;
+    let line_height = LINE_HEIGHT * screen.scale;
+    let first = console.output.len().saturating_sub(VISIBLE_LINES);
+    // Вывод прижат к строке ввода
+    let mut y = panel.y + PADDING * screen.scale + (VISIBLE_LINES - (console.output.len() - first)) as f32 * line_height;
+    for line in &console.output[first..] {
+        ui.text_shadowed(x, y, line, OUTPUT_COLOR);
+        y += line_height;
+    }
+    ui.text_shadowed(x, y, &format!("> {}_", console.input), INPUT_COLOR);
+}
@@ -0,0 +1,21 @@
+use glam::{Vec2, Vec4};
+
+use crate::stats::DebugStats;
+use super::*;
+
+
+const PANEL_COLOR: Vec4 = Vec4::new(0.0, 0.0, 0.0, 0.4);
+const TEXT_COLOR: Vec4 = Vec4::new(0.9, 0.9, 0.9, 1.0);
+/// Отступ от края окна и поля вокруг текста
+const PADDING: f32 = 2.0;
+
+
+/// Отладочный экран в левом верхнем углу
+pub fn draw_debug(ui: &mut UiRenderer, stats: &DebugStats) {
+    let text = stats.lines().join("\n");
+    let scale = ui.screen.scale;
+    let size = ui.text_size(&text) / scale + Vec2::splat(PADDING * 2.0);
+    let panel = ui.screen.place(Anchor::TopLeft, Vec2::splat(PADDING), size);
+    ui.rect(panel, PANEL_COLOR);
+    ui.text_shadowed(panel.x + PADDING * scale, panel.y + PADDING * scale, &text, TEXT_COLOR);
+}
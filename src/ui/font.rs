@@ -0,0 +1,88 @@
+use crate::assets::UvRegion;
+
+
+/// Символов в строке и столбце сетки шрифта
+const GRID: usize = 16;
+/// Промежуток между символами в пикселях шрифта
+const SPACING: usize = 1;
+/// Пиксель считается частью символа, если он непрозрачнее этого
+const ALPHA_THRESHOLD: u8 = 32;
+
+
+/// Символ в картинке шрифта: левый край и ширина в пикселях
+#[derive(Clone, Copy, Debug, Default)]
+struct Glyph {
+    left: usize,
+    width: usize,
+}
+
+
+/// Растровый шрифт: картинка с сеткой 16x16 символов, код символа -
+/// `строка * 16 + столбец`. Ширина каждого символа находится по
+/// непрозрачным пикселям, так что шрифт может быть и непропорциональным
+pub struct Font {
+    pub cell_width: usize,
+    pub cell_height: usize,
+    width: usize,
+    height: usize,
+    glyphs: [Glyph; GRID * GRID],
+}
+
+
+impl Font {
+    /// `data` - RGBA со строками снизу вверх, как отдаёт `load_png`
+    pub fn from_image(data: &[u8], width: i32, height: i32) -> Self {
+        let (width, height) = (width as usize, height as usize);
+        let cell_width = width / GRID;
+        let cell_height = height / GRID;
+        let alpha = |x: usize, y: usize| data[((height - 1 - y) * width + x) * 4 + 3];
+
+        let mut glyphs = [Glyph::default(); GRID * GRID];
+        for (code, glyph) in glyphs.iter_mut().enumerate() {
+            let (cell_x, cell_y) = (code % GRID * cell_width, code / GRID * cell_height);
+            let filled: Vec<usize> = (0..cell_width)
+                .filter(|x| (0..cell_height).any(|y| alpha(cell_x + x, cell_y + y) > ALPHA_THRESHOLD))
+                .collect();
+            *glyph = match (filled.first(), filled.last()) {
+                (Some(left), Some(right)) => Glyph { left: *left, width: right - left + 1 },
+                // Пустые символы, например пробел, занимают полклетки
+                _ => Glyph { left: 0, width: cell_width / 2 },
+            };
+        }
+        Self { cell_width, cell_height, width, height, glyphs }
+    }
+
+
+    fn glyph(&self, c: char) -> (usize, Glyph) {
+        let code = if (c as usize) < GRID * GRID { c as usize } else { '?' as usize };
+        (code, self.glyphs[code])
+    }
+
+
+    /// Ширина символа с промежутком, в пикселях шрифта
+    pub fn advance(&self, c: char) -> usize {
+        self.glyph(c).1.width + SPACING
+    }
+
+
+    /// Ширина строки в пикселях шрифта; для нескольких строк - самой длинной
+    pub fn text_width(&self, text: &str) -> usize {
+        text.lines().map(|line| line.chars().map(|c| self.advance(c)).sum::<usize>()).max().unwrap_or(0)
+    }
+
+
+    /// Ширина символа без промежутка и его область в текстуре
+    pub fn glyph_uv(&self, c: char) -> (usize, UvRegion) {
+        let (code, glyph) = self.glyph(c);
+        let x = code % GRID * self.cell_width + glyph.left;
+        // Строки картинки хранятся снизу вверх
+        let bottom = self.height - (code / GRID + 1) * self.cell_height;
+        let uv = UvRegion {
+            u1: x as f32 / self.width as f32,
+            v1: bottom as f32 / self.height as f32,
+            u2: (x + glyph.width) as f32 / self.width as f32,
+            v2: (bottom + self.cell_height) as f32 / self.height as f32,
+        };
+        (glyph.width, uv)
+    }
+}
@@ -0,0 +1,57 @@
+use glam::{Vec2, Vec4};
+
+use crate::assets::Atlas;
+use crate::player::Hotbar;
+use crate::voxels::BlockRegistry;
+use super::*;
+
+
+const SLOT_COLOR: Vec4 = Vec4::new(0.0, 0.0, 0.0, 0.45);
+const BORDER_COLOR: Vec4 = Vec4::new(0.55, 0.55, 0.55, 0.9);
+const SELECTED_COLOR: Vec4 = Vec4::new(1.0, 1.0, 1.0, 1.0);
+const SHADE_COLOR: Vec4 = Vec4::new(0.0, 0.0, 0.0, 0.5);
+const CROSSHAIR_COLOR: Vec4 = Vec4::new(1.0, 1.0, 1.0, 0.8);
+const TEXT_COLOR: Vec4 = Vec4::new(1.0, 1.0, 1.0, 1.0);
+
+
+fn slot(ui: &mut UiRenderer, atlas: &Atlas, blocks: &BlockRegistry, cell: Rect, id: Option<u8>) {
+    ui.rect(cell, SLOT_COLOR);
+    ui.frame(cell, 1.0, BORDER_COLOR);
+    if let Some(block) = id.and_then(|id| blocks.get(id)) {
+        let inset = cell.w * 0.19;
+        let icon = Rect { x: cell.x + inset, y: cell.y + inset, w: cell.w - inset * 2.0, h: cell.h - inset * 2.0 };
+        ui.block_icon(atlas, block, icon);
+    }
+}
+
+
+/// Прицел, панель быстрого доступа и, если открыт, инвентарь
+pub fn draw_hud(ui: &mut UiRenderer, atlas: &Atlas, blocks: &BlockRegistry, hotbar: &Hotbar, inventory_open: bool) {
+    let screen = ui.screen;
+
+    if inventory_open {
+        ui.rect(Rect { x: 0.0, y: 0.0, w: screen.width, h: screen.height }, SHADE_COLOR);
+        let ids = inventory_blocks(blocks);
+        for (id, cell) in ids.iter().zip(inventory_cells(&screen, ids.len())) {
+            slot(ui, atlas, blocks, cell, Some(*id));
+        }
+    } else {
+        ui.rect(screen.place(Anchor::Center, Vec2::ZERO, Vec2::new(9.0, 1.0)), CROSSHAIR_COLOR);
+        ui.rect(screen.place(Anchor::Center, Vec2::ZERO, Vec2::new(1.0, 9.0)), CROSSHAIR_COLOR);
+    }
+
+    let slots = hotbar_slots(&screen);
+    for (cell, id) in slots.iter().zip(hotbar.slots) {
+        slot(ui, atlas, blocks, *cell, id);
+    }
+    let selected = slots[hotbar.selected];
+    let border = screen.scale;
+    let outline = Rect { x: selected.x - border, y: selected.y - border, w: selected.w + border * 2.0, h: selected.h + border * 2.0 };
+    ui.frame(outline, 2.0, SELECTED_COLOR);
+
+    // Имя выбранного блока над панелью
+    if let Some(block) = hotbar.selected_block().and_then(|id| blocks.get(id)) {
+        let offset = Vec2::new(0.0, -(SLOT_SIZE + LINE_HEIGHT + HOTBAR_MARGIN + 2.0));
+        ui.text_anchored(Anchor::Bottom, offset, &block.name, TEXT_COLOR);
+    }
+}
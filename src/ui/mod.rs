@@ -0,0 +1,212 @@
+#[cfg(feature = "graphics")]
+mod console;
+#[cfg(feature = "graphics")]
+mod debug_overlay;
+#[cfg(feature = "graphics")]
+mod hud;
+#[cfg(feature = "graphics")]
+mod renderer;
+#[cfg(feature = "graphics")]
+pub mod font;
+
+#[cfg(feature = "graphics")]
+pub use console::draw_console;
+#[cfg(feature = "graphics")]
+pub use debug_overlay::draw_debug;
+#[cfg(feature = "graphics")]
+pub use hud::draw_hud;
+#[cfg(feature = "graphics")]
+pub use renderer::UiRenderer;
+#[cfg(feature = "graphics")]
+pub use font::Font;
+
+use glam::Vec2;
+
+use crate::player::HOTBAR_SIZE;
+use crate::voxels::BlockRegistry;
+
+
+// Интерфейс задаётся в условных единицах, которые умножаются на масштаб
+// экрана: при высоте окна 720 одна единица - два пикселя.
+
+/// Высота окна, на которую приходится единичный масштаб
+const SCALE_HEIGHT: f32 = 360.0;
+/// Высота строки текста
+#[cfg(feature = "graphics")]
+pub const LINE_HEIGHT: f32 = 8.0;
+/// Сторона слота панели и ячейки инвентаря
+pub const SLOT_SIZE: f32 = 22.0;
+/// Отступ панели от нижнего края окна
+const HOTBAR_MARGIN: f32 = 4.0;
+/// Ячеек в строке инвентаря
+const INVENTORY_COLUMNS: usize = 9;
+
+
+/// Прямоугольник на экране в пикселях, начало - левый верхний угол окна
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+
+impl Rect {
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.w && y >= self.y && y < self.y + self.h
+    }
+
+
+    #[cfg(feature = "graphics")]
+    pub fn center(&self) -> Vec2 {
+        Vec2::new(self.x + self.w * 0.5, self.y + self.h * 0.5)
+    }
+}
+
+
+/// К какой точке окна привязан элемент. Углы нужны только отладочному
+/// экрану и консоли, которых без окна нет
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Anchor {
+    #[cfg(feature = "graphics")]
+    TopLeft,
+    Center,
+    #[cfg(feature = "graphics")]
+    BottomLeft,
+    Bottom,
+}
+
+
+impl Anchor {
+    /// Доли свободного места слева и сверху
+    fn factors(self) -> Vec2 {
+        match self {
+            #[cfg(feature = "graphics")]
+            Anchor::TopLeft => Vec2::new(0.0, 0.0),
+            Anchor::Center => Vec2::new(0.5, 0.5),
+            #[cfg(feature = "graphics")]
+            Anchor::BottomLeft => Vec2::new(0.0, 1.0),
+            Anchor::Bottom => Vec2::new(0.5, 1.0),
+        }
+    }
+}
+
+
+/// Размер окна и масштаб интерфейса
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Screen {
+    pub width: f32,
+    pub height: f32,
+    /// Пикселей в условной единице
+    pub scale: f32,
+}
+
+
+impl Screen {
+    /// `ui_scale` 0 подбирает целый масштаб по высоте окна
+    pub fn new(width: f32, height: f32, ui_scale: u32) -> Self {
+        let scale = match ui_scale {
+            0 => (height / SCALE_HEIGHT).floor().max(1.0),
+            scale => scale as f32,
+        };
+        Self { width, height, scale }
+    }
+
+
+    /// Прямоугольник размера `size`, привязанный к `anchor` и сдвинутый на `offset`.
+    /// Размер и сдвиг в условных единицах, сдвиг по `y` направлен вниз
+    pub fn place(&self, anchor: Anchor, offset: Vec2, size: Vec2) -> Rect {
+        let size = size * self.scale;
+        let free = Vec2::new(self.width, self.height) - size;
+        let position = free * anchor.factors() + offset * self.scale;
+        Rect { x: position.x, y: position.y, w: size.x, h: size.y }
+    }
+}
+
+
+/// Слоты панели быстрого доступа: по центру у нижнего края окна
+pub fn hotbar_slots(screen: &Screen) -> Vec<Rect> {
+    let size = Vec2::new(SLOT_SIZE * HOTBAR_SIZE as f32, SLOT_SIZE);
+    let bar = screen.place(Anchor::Bottom, Vec2::new(0.0, -HOTBAR_MARGIN), size);
+    (0..HOTBAR_SIZE)
+        .map(|slot| Rect { x: bar.x + slot as f32 * bar.h, y: bar.y, w: bar.h, h: bar.h })
+        .collect()
+}
+
+
+/// Блоки творческого инвентаря: все зарегистрированные, кроме воздуха
+pub fn inventory_blocks(blocks: &BlockRegistry) -> Vec<u8> {
+    blocks.iter().map(|block| block.id as u8).filter(|id| *id != 0).collect()
+}
+
+
+/// Ячейки инвентаря сеткой по центру окна, по одной на блок
+pub fn inventory_cells(screen: &Screen, count: usize) -> Vec<Rect> {
+    let rows = count.div_ceil(INVENTORY_COLUMNS);
+    let size = Vec2::new(INVENTORY_COLUMNS as f32, rows as f32) * SLOT_SIZE;
+    let grid = screen.place(Anchor::Center, Vec2::ZERO, size);
+    let cell = SLOT_SIZE * screen.scale;
+    (0..count)
+        .map(|index| Rect {
+            x: grid.x + (index % INVENTORY_COLUMNS) as f32 * cell,
+            y: grid.y + (index / INVENTORY_COLUMNS) as f32 * cell,
+            w: cell,
+            h: cell,
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f32, y: f32, w: f32, h: f32) -> Rect {
+        Rect { x, y, w, h }
+    }
+
+    #[test]
+    fn automatic_scale_follows_window_height() {
+        assert_eq!(Screen::new(1280.0, 720.0, 0).scale, 2.0);
+        assert_eq!(Screen::new(1920.0, 1079.0, 0).scale, 2.0);
+        assert_eq!(Screen::new(320.0, 200.0, 0).scale, 1.0);
+        assert_eq!(Screen::new(1280.0, 720.0, 3).scale, 3.0);
+    }
+
+    #[test]
+    fn anchors_place_rects_in_free_space() {
+        let screen = Screen::new(200.0, 100.0, 2);
+        let size = Vec2::new(10.0, 5.0);
+        assert_eq!(screen.place(Anchor::Center, Vec2::ZERO, size), rect(90.0, 45.0, 20.0, 10.0));
+        assert_eq!(screen.place(Anchor::Bottom, Vec2::new(0.0, -4.0), size), rect(90.0, 82.0, 20.0, 10.0));
+        #[cfg(feature = "graphics")]
+        {
+            assert_eq!(screen.place(Anchor::TopLeft, Vec2::new(1.0, 2.0), size), rect(2.0, 4.0, 20.0, 10.0));
+            assert_eq!(screen.place(Anchor::BottomLeft, Vec2::ZERO, size), rect(0.0, 90.0, 20.0, 10.0));
+        }
+    }
+
+    #[test]
+    fn hotbar_is_centered_above_bottom_edge() {
+        let screen = Screen::new(1280.0, 720.0, 0);
+        let slots = hotbar_slots(&screen);
+        assert_eq!(slots.len(), HOTBAR_SIZE);
+        assert_eq!(slots[0], rect(442.0, 668.0, 44.0, 44.0));
+        assert_eq!(slots[1].x, 486.0);
+        let last = slots[HOTBAR_SIZE - 1];
+        assert_eq!(last.x + last.w, 1280.0 - 442.0);
+        assert!(slots[4].contains(640.0, 690.0));
+    }
+
+    #[test]
+    fn inventory_wraps_into_rows() {
+        let screen = Screen::new(1280.0, 720.0, 0);
+        let cells = inventory_cells(&screen, 10);
+        assert_eq!(cells.len(), 10);
+        assert_eq!(cells[0], rect(442.0, 316.0, 44.0, 44.0));
+        assert_eq!(cells[8], rect(794.0, 316.0, 44.0, 44.0));
+        assert_eq!(cells[9], rect(442.0, 360.0, 44.0, 44.0));
+        assert!(inventory_cells(&screen, 0).is_empty());
+    }
+}
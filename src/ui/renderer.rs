@@ -0,0 +1,142 @@
+use glam::{Mat4, Vec2, Vec4};
+
+use crate::assets::{Assets, Atlas};
+use crate::graphics::{SpriteBatch, Texture};
+use crate::loaders::png_loading::{load_png, texture_from_data};
+use crate::voxels::Block;
+use super::*;
+
+
+/// Непосредственный режим: элементы заново добавляются каждый кадр между
+/// `begin` и `render`. Прямоугольники и картинки из атласа рисуются одним
+/// слоем, текст - вторым, поверх них
+pub struct UiRenderer {
+    pub screen: Screen,
+    pub font: Font,
+    font_texture: Texture,
+    sprites: SpriteBatch,
+    text: SpriteBatch,
+}
+
+
+impl UiRenderer {
+    /// `capacity` - число четырёхугольников каждого слоя за кадр
+    pub fn new(font_path: &str, capacity: usize) -> Result<Self, String> {
+        let (data, width, height) = load_png(font_path).map_err(|e| format!("can't load font '{}': {}", font_path, e))?;
+        let font = Font::from_image(&data, width, height);
+        Ok(Self {
+            screen: Screen::new(1.0, 1.0, 1),
+            font,
+            font_texture: texture_from_data(data, width, height),
+            sprites: SpriteBatch::new(capacity),
+            text: SpriteBatch::new(capacity),
+        })
+    }
+
+
+    pub fn begin(&mut self, screen: Screen) {
+        self.screen = screen;
+    }
+
+
+    pub fn rect(&mut self, rect: Rect, color: Vec4) {
+        self.sprites.rect(rect.x, rect.y, rect.w, rect.h, color);
+    }
+
+
+    /// Рамка толщиной `border` условных единиц внутрь прямоугольника
+    pub fn frame(&mut self, rect: Rect, border: f32, color: Vec4) {
+        self.sprites.frame(rect.x, rect.y, rect.w, rect.h, border * self.screen.scale, color);
+    }
+
+
+    /// Изометрическая иконка блока: верх и две боковые грани
+    pub fn block_icon(&mut self, atlas: &Atlas, block: &Block, rect: Rect) {
+        let size = rect.w.min(rect.h);
+        let center = rect.center();
+        let half_width = size * 0.433;
+        let quarter = size * 0.25;
+
+        let top = center - Vec2::new(0.0, size * 0.5);
+        let bottom = center + Vec2::new(0.0, size * 0.5);
+        let left_top = center + Vec2::new(-half_width, -quarter);
+        let left_bottom = center + Vec2::new(-half_width, quarter);
+        let right_top = center + Vec2::new(half_width, -quarter);
+        let right_bottom = center + Vec2::new(half_width, quarter);
+
+        let face = |index: usize| atlas.uv(block.texture_faces[index] as usize);
+        self.sprites.quad([left_top, top, right_top, center], face(3), Vec4::ONE);
+        self.sprites.quad([left_top, center, bottom, left_bottom], face(0), Vec4::new(0.8, 0.8, 0.8, 1.0));
+        self.sprites.quad([center, right_top, right_bottom, bottom], face(4), Vec4::new(0.6, 0.6, 0.6, 1.0));
+    }
+
+
+    /// Пикселей экрана в пикселе шрифта
+    fn text_scale(&self) -> f32 {
+        LINE_HEIGHT * self.screen.scale / self.font.cell_height as f32
+    }
+
+
+    /// Размер текста на экране в пикселях
+    pub fn text_size(&self, text: &str) -> Vec2 {
+        let lines = text.lines().count().max(1) as f32;
+        let scale = self.text_scale();
+        Vec2::new(self.font.text_width(text) as f32 * scale, lines * self.font.cell_height as f32 * scale)
+    }
+
+
+    /// Текст с левым верхним углом в `(x, y)` пикселей; `\n` переносит строку
+    pub fn text(&mut self, x: f32, y: f32, text: &str, color: Vec4) {
+        let scale = self.text_scale();
+        let line_height = self.font.cell_height as f32 * scale;
+        for (row, line) in text.lines().enumerate() {
+            let mut cursor = x;
+            let top = y + row as f32 * line_height;
+            for c in line.chars() {
+                let (width, uv) = self.font.glyph_uv(c);
+                if c != ' ' {
+                    self.text.sprite(cursor, top, width as f32 * scale, line_height, uv, color);
+                }
+                cursor += self.font.advance(c) as f32 * scale;
+            }
+        }
+    }
+
+
+    /// Текст с тенью, читаемый на любом фоне
+    pub fn text_shadowed(&mut self, x: f32, y: f32, text: &str, color: Vec4) {
+        let offset = self.screen.scale;
+        self.text(x + offset, y + offset, text, Vec4::new(0.0, 0.0, 0.0, color.w * 0.75));
+        self.text(x, y, text, color);
+    }
+
+
+    /// Текст, привязанный к точке окна; сдвиг в условных единицах
+    pub fn text_anchored(&mut self, anchor: Anchor, offset: Vec2, text: &str, color: Vec4) {
+        let size = self.text_size(text) / self.screen.scale;
+        let rect = self.screen.place(anchor, offset, size);
+        self.text_shadowed(rect.x, rect.y, text, color);
+    }
+
+
+    /// Рисует накопленное за кадр поверх мира
+    pub fn render(&mut self, assets: &Assets) {
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Disable(gl::CULL_FACE);
+        }
+        let projection = Mat4::orthographic_rh_gl(0.0, self.screen.width, self.screen.height, 0.0, -1.0, 1.0);
+        assets.ui_shader.use_shader();
+        assets.ui_shader.uniform_matrix("u_projection", projection);
+
+        assets.texture.bind();
+        self.sprites.render();
+        self.font_texture.bind();
+        self.text.render();
+
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+            gl::Enable(gl::CULL_FACE);
+        }
+    }
+}
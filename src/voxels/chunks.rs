@@ -10,17 +10,19 @@ pub struct Chunks {
     pub w: usize,
     pub h: usize,
     pub d: usize,
+    /// Зерно, из которого сгенерирован мир
+    pub seed: u32,
 }
 
 impl Chunks {
-    pub fn new(w: usize, h: usize, d: usize) -> Self {
+    pub fn new(w: usize, h: usize, d: usize, seed: u32) -> Self {
         let volume = w * h * d;
         let mut chunks = Vec::with_capacity(volume);
 
         for y in 0..h {
             for z in 0..d {
                 for x in 0..w {
-                    let chunk = Chunk::new(x as isize, y as isize, z as isize);
+                    let chunk = Chunk::new(x as isize, y as isize, z as isize, seed);
                     chunks.push(chunk);
                 }
             }
@@ -32,6 +34,7 @@ impl Chunks {
             w,
             h,
             d,
+            seed,
         }
     }
 
@@ -121,47 +124,96 @@ impl Chunks {
             Some((chunk_index, voxel_index, cx, cy, cz, lx, ly, lz)) => {
                 let chunk = self.chunks.get_mut(chunk_index);
                 if let Some(chunk) = chunk {
-                    chunk.voxels[voxel_index].id = id as u8;
-                    chunk.modified = true;
-
-                    if lx == 0 {
-                        if let Some(chunk) = self.get_mut_chunk(cx - 1, cy, cz) {
-                            chunk.modified = true;
-                        }
-                    }
-                    if ly == 0 {
-                        if let Some(chunk) = self.get_mut_chunk(cx, cy - 1, cz) {
-                            chunk.modified = true;
-                        }
-                    }
-                    if lz == 0 {
-                        if let Some(chunk) = self.get_mut_chunk(cx, cy, cz - 1) {
-                            chunk.modified = true;
-                        }
-                    }
-
-                    if lx == (CHUNK_W as isize) - 1 {
-                        if let Some(chunk) = self.get_mut_chunk(cx + 1, cy, cz) {
-                            chunk.modified = true;
-                        }
-                    }
-                    if ly == (CHUNK_H as isize) - 1 {
-                        if let Some(chunk) = self.get_mut_chunk(cx, cy + 1, cz) {
-                            chunk.modified = true;
-                        }
-                    }
-                    if lz == (CHUNK_D as isize) - 1 {
-                        if let Some(chunk) = self.get_mut_chunk(cx, cy, cz + 1) {
-                            chunk.modified = true;
-                        }
-                    }
+                    chunk.voxels[voxel_index] = Voxel { id: id as u8, states: 0 };
                 }
+                self.mark_modified(cx, cy, cz, lx, ly, lz);
             }
             None => {
                 return;
             }
         }
     }
+
+    /// Меняет состояние вокселя, не трогая блок
+    pub fn set_states(&mut self, x: isize, y: isize, z: isize, states: u8) {
+        if let Some((chunk_index, voxel_index, cx, cy, cz, lx, ly, lz)) = self.calculate_indices(x, y, z) {
+            self.chunks[chunk_index].voxels[voxel_index].states = states;
+            self.mark_modified(cx, cy, cz, lx, ly, lz);
+        }
+    }
+
+    /// Помечает чанк вокселя изменённым, а также соседние чанки,
+    /// если воксель лежит на границе (их меши зависят от него)
+    pub fn set_modified(&mut self, x: isize, y: isize, z: isize) {
+        if let Some((_, _, cx, cy, cz, lx, ly, lz)) = self.calculate_indices(x, y, z) {
+            self.mark_modified(cx, cy, cz, lx, ly, lz);
+        }
+    }
+
+    fn mark_modified(&mut self, cx: isize, cy: isize, cz: isize, lx: isize, ly: isize, lz: isize) {
+        if let Some(chunk) = self.get_mut_chunk(cx, cy, cz) {
+            chunk.modified = true;
+        }
+        if lx == 0 {
+            if let Some(chunk) = self.get_mut_chunk(cx - 1, cy, cz) {
+                chunk.modified = true;
+            }
+        }
+        if ly == 0 {
+            if let Some(chunk) = self.get_mut_chunk(cx, cy - 1, cz) {
+                chunk.modified = true;
+            }
+        }
+        if lz == 0 {
+            if let Some(chunk) = self.get_mut_chunk(cx, cy, cz - 1) {
+                chunk.modified = true;
+            }
+        }
+
+        if lx == (CHUNK_W as isize) - 1 {
+            if let Some(chunk) = self.get_mut_chunk(cx + 1, cy, cz) {
+                chunk.modified = true;
+            }
+        }
+        if ly == (CHUNK_H as isize) - 1 {
+            if let Some(chunk) = self.get_mut_chunk(cx, cy + 1, cz) {
+                chunk.modified = true;
+            }
+        }
+        if lz == (CHUNK_D as isize) - 1 {
+            if let Some(chunk) = self.get_mut_chunk(cx, cy, cz + 1) {
+                chunk.modified = true;
+            }
+        }
+    }
+
+    /// Заполняет `closes` (27 элементов) копиями соседей чанка `index`
+    /// в порядке, который ожидает `VoxelRenderer`
+    pub fn neighbours(&self, index: usize, closes: &mut [Option<Chunk>]) {
+        for elem in closes.iter_mut() {
+            *elem = None;
+        }
+
+        let chunk = &self.chunks[index];
+        for other in &self.chunks {
+            let ox = other.x - chunk.x;
+            let oy = other.y - chunk.y;
+            let oz = other.z - chunk.z;
+
+            if ox.abs() > 1 || oy.abs() > 1 || oz.abs() > 1 {
+                continue;
+            }
+
+            let index = ((oy + 1) * 3 + (oz + 1)) * 3 + (ox + 1);
+            closes[index as usize] = Some(other.clone());
+        }
+    }
+
+    /// Высота загруженного мира в вокселях
+    pub fn height_in_voxels(&self) -> isize {
+        (self.h * CHUNK_H) as isize
+    }
+
     pub fn ray_cast(
         &self,
         a: Vec3,
@@ -283,6 +335,21 @@ impl Chunks {
         }
     }
 
+    /// Состояния вокселей в том же порядке, что и `write`
+    pub fn write_states(&self, dest: &mut [u8]) {
+        let states = self.chunks.iter().flat_map(|chunk| chunk.voxels.iter().map(|voxel| voxel.states));
+        for (byte, state) in dest.iter_mut().zip(states) {
+            *byte = state;
+        }
+    }
+
+    pub fn read_states(&mut self, source: &[u8]) {
+        let voxels = self.chunks.iter_mut().flat_map(|chunk| chunk.voxels.iter_mut());
+        for (voxel, state) in voxels.zip(source) {
+            voxel.states = *state;
+        }
+    }
+
     pub fn read(&mut self, source: &[u8]) {
         let mut index = 0;
         for chunk in &mut self.chunks {
@@ -329,3 +396,15 @@ impl Chunks {
         Some((chunk_index, voxel_index, cx, cy, cz, lx, ly, lz))
     }
 }
+
+#[cfg(test)]
+impl Chunks {
+    /// Мир, целиком заполненный одним блоком, для тестов
+    pub fn filled(w: usize, h: usize, d: usize, id: u8) -> Self {
+        let mut chunks = Self::new(w, h, d, super::DEFAULT_SEED);
+        for chunk in &mut chunks.chunks {
+            chunk.voxels = [Voxel { id, states: 0 }; super::CHUNK_VOL];
+        }
+        chunks
+    }
+}
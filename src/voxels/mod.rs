@@ -2,19 +2,34 @@ use noise::{NoiseFn, OpenSimplex};
 
 pub use chunks::Chunks;
 
+use crate::fluids::{surface_height, Fluid};
 use crate::lighting::lightmap::Lightmap;
+use crate::ticks::Behaviour;
 
 pub mod chunks;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct Voxel {
     pub id: u8,
+    /// Состояние, смысл которого задаёт блок: у жидкостей - уровень,
+    /// у посевов - стадия роста.
+    /// Новый блок всегда ставится с нулевым состоянием
+    pub states: u8,
+}
+
+impl Voxel {
+    /// Блок с нулевым состоянием, как его ставит `Chunks::set`
+    pub const fn new(id: u8) -> Self {
+        Self { id, states: 0 }
+    }
 }
 
 pub const CHUNK_W: usize = 16;
 pub const CHUNK_H: usize = 16;
 pub const CHUNK_D: usize = 16;
 pub const CHUNK_VOL: usize = CHUNK_W * CHUNK_H * CHUNK_D;
+/// Зерно генератора, которым создавались миры до появления зерна в файле
+pub const DEFAULT_SEED: u32 = 1;
 
 #[derive(Clone)]
 pub struct Chunk {
@@ -27,16 +42,16 @@ pub struct Chunk {
 }
 
 impl Chunk {
-    pub fn new(x_pos: isize, y_pos: isize, z_pos: isize) -> Self {
-        let mut voxels = [Voxel {id: 0}; CHUNK_VOL];
-        let perlin = OpenSimplex::new(1);
+    pub fn new(x_pos: isize, y_pos: isize, z_pos: isize, seed: u32) -> Self {
+        let mut voxels = [Voxel { id: 0, states: 0 }; CHUNK_VOL];
+        let perlin = OpenSimplex::new(seed);
         for z in 0..CHUNK_D as isize {
             for x in 0..CHUNK_W as isize {
                 let real_x = x + x_pos * CHUNK_W as isize;
                 let real_z = z + z_pos * CHUNK_D as isize;
                 //let height = perlin.get([(x as f64) * 0.0125, (z as f64) * 0.0125]);
                 for y in 0..CHUNK_H as isize {
-                    let real_y = y as isize + y_pos * CHUNK_H as isize;
+                    let real_y = y + y_pos * CHUNK_H as isize;
                     let id = perlin.get([(real_x as f64) * 0.0125, (real_y as f64) * 0.0125, (real_z as f64) * 0.0125]) > 0.1;
                     let chunk_index = ((y * CHUNK_D as isize + z) * CHUNK_W as isize + x) as usize;
                     if real_y <= 2 {
@@ -59,10 +74,23 @@ const BLOCK_COUNT: usize = 256;
 #[derive(Clone)]
 pub struct Block {
     pub id: u32,
+    /// Имя, по которому блок выбирают в настройках и командах
+    pub name: String,
     pub texture_faces: [i32; 6],
     pub emission: [u8; 3],
     pub draw_group: u8,
     pub light_passing: bool,
+    /// Сколько уровней света поглощает блок по каналам R, G, B, S
+    /// сверх обычного затухания; 15 полностью отсекает канал.
+    /// Небесный свет одноцветный: цветное стекло его не окрашивает,
+    /// а только ослабляет на значение канала S
+    pub light_absorption: [u8; 4],
+    /// Блок мешает движению игрока
+    pub obstacle: bool,
+    /// Жидкость растекается и хранит уровень в `Voxel::states`
+    pub fluid: Option<Fluid>,
+    /// Что блок делает на шагах симуляции
+    pub behaviour: Option<Behaviour>,
 }
 
 pub struct BlockRegistry {
@@ -76,16 +104,41 @@ impl BlockRegistry {
     pub fn get(&self, id: u8) -> Option<&Block> {
         self.blocks[id as usize].as_ref()
     }
+
+    /// Id блока по имени
+    pub fn find(&self, name: &str) -> Option<u8> {
+        self.iter().find(|block| block.name == name).map(|block| block.id as u8)
+    }
+
+    /// Все зарегистрированные блоки по возрастанию id
+    pub fn iter(&self) -> impl Iterator<Item = &Block> {
+        self.blocks.iter().flatten()
+    }
 }
 
 impl Block {
-    pub fn new(id: u32, texture: i32) -> Self {
+    pub fn new(id: u32, name: &str, texture: i32) -> Self {
         Self {
             id,
+            name: name.to_string(),
             texture_faces: [texture; 6],
             emission: [0; 3],
             draw_group: 0,
             light_passing: false,
+            light_absorption: [0; 4],
+            obstacle: true,
+            fluid: None,
+            behaviour: None,
+        }
+    }
+
+
+    /// Высота блока в долях клетки: уровень жидкости или стадия роста
+    pub fn height(&self, states: u8) -> f32 {
+        match (self.fluid, self.behaviour) {
+            (Some(_), _) => surface_height(states),
+            (None, Some(Behaviour::Grows { stages })) => (states + 1).min(stages) as f32 / stages as f32,
+            _ => 1.0,
         }
     }
 }
\ No newline at end of file
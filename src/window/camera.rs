@@ -30,6 +30,15 @@ impl Camera {
         self.update_vectors();
     }
 
+    /// Поворачивает камеру к точке так же, как мышь: рысканье, затем наклон, без крена
+    pub fn look_at(&mut self, target: Vec3) {
+        let direction = (target - self.position).normalize_or(Vec3::Z);
+        let yaw = direction.x.atan2(direction.z);
+        let pitch = -direction.y.clamp(-1.0, 1.0).asin();
+        self.rotation = Quat::IDENTITY;
+        self.rotate(pitch, yaw, 0.0);
+    }
+
     pub fn get_projection(&self, width: f32, height: f32) -> Mat4 {
         let aspect = width / height;
         Mat4::perspective_rh(self.fov, aspect, 0.1, 1500.0)
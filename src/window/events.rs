@@ -1,4 +1,7 @@
+#[cfg(feature = "graphics")]
 use super::Window;
+use crate::input::gamepad::{GamepadState, PAD_INPUT_COUNT};
+use crate::input::recording::InputEvent;
 
 pub struct Events {
     keys: [bool;1032],
@@ -8,8 +11,18 @@ pub struct Events {
     pub delta_y: f32,
     x: f64,
     y: f64,
+    /// Прокрутка колеса мыши за кадр, вверх положительная
+    pub scroll: f32,
+    /// Символы, набранные за кадр, с учётом раскладки
+    pub text: String,
     pub cursor_locked: bool,
     cursor_started: bool,
+    /// Новый размер окна, если его изменили в этом кадре
+    pub resized: Option<(i32, i32)>,
+    pub gamepad: GamepadState,
+    pad_frames: [u32; PAD_INPUT_COUNT],
+    /// События текущего кадра, если идёт запись
+    log: Option<Vec<InputEvent>>,
 }
 
 impl Events {
@@ -24,23 +37,127 @@ impl Events {
             delta_y: 0.0,
             x: 0.0,
             y: 0.0,
+            scroll: 0.0,
+            text: String::new(),
             cursor_locked: false,
             cursor_started: false,
+            resized: None,
+            gamepad: GamepadState::default(),
+            pad_frames: [0; PAD_INPUT_COUNT],
+            log: None,
         }
     }
 
+    #[cfg(feature = "graphics")]
     pub fn initialize(&mut self, window: &mut Window) {
         window.window.set_key_polling(true);
         window.window.set_mouse_button_polling(true);
         window.window.set_cursor_pos_polling(true);
         window.window.set_cursor_enter_polling(true);
+        window.window.set_scroll_polling(true);
+        window.window.set_char_polling(true);
         window.window.set_size_polling(true);
     }
 
-    pub fn pull_events(&mut self, window: &mut Window) {
+    /// Начало нового кадра ввода: сбрасывает дельты курсора и колеса и набранный текст
+    pub fn begin_frame(&mut self) {
         self.current += 1;
         self.delta_x = 0.0;
         self.delta_y = 0.0;
+        self.scroll = 0.0;
+        self.text.clear();
+        self.resized = None;
+    }
+
+    /// Начинает записывать все события, приходящие в `Events`
+    #[cfg(feature = "graphics")]
+    pub fn start_recording(&mut self) {
+        self.log = Some(Vec::new());
+    }
+
+    /// Забирает события, записанные с прошлого вызова
+    #[cfg(feature = "graphics")]
+    pub fn take_recorded(&mut self) -> Vec<InputEvent> {
+        self.log.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    fn record(&mut self, event: InputEvent) {
+        if let Some(log) = &mut self.log {
+            log.push(event);
+        }
+    }
+
+    /// Подача записанного события так же, как оно пришло бы из окна
+    pub fn replay_event(&mut self, event: &InputEvent) {
+        match *event {
+            InputEvent::Key { index, pressed } => self.key_event(index, pressed),
+            InputEvent::Cursor { x, y } => self.cursor_event(x, y),
+            InputEvent::Gamepad(state) => self.gamepad_event(state),
+            InputEvent::Resize { width, height } => self.resize_event(width, height),
+            InputEvent::Scroll { y } => self.scroll_event(y),
+            InputEvent::Char(c) => self.char_event(c),
+        }
+    }
+
+    /// Нажатие или отпускание клавиши; кнопки мыши идут с индексами от 1024
+    pub fn key_event(&mut self, index: usize, pressed: bool) {
+        if index >= 1032 {
+            return;
+        }
+        self.record(InputEvent::Key { index, pressed });
+        self.keys[index] = pressed;
+        self.frames[index] = self.current;
+    }
+
+    pub fn resize_event(&mut self, width: i32, height: i32) {
+        self.record(InputEvent::Resize { width, height });
+        self.resized = Some((width, height));
+    }
+
+    pub fn cursor_event(&mut self, xpos: f64, ypos: f64) {
+        self.record(InputEvent::Cursor { x: xpos, y: ypos });
+        if self.cursor_started {
+            self.delta_x += (xpos - self.x) as f32;
+            self.delta_y += (ypos - self.y) as f32;
+        } else {
+            self.cursor_started = true;
+        }
+        self.x = xpos;
+        self.y = ypos;
+    }
+
+    pub fn scroll_event(&mut self, y: f32) {
+        self.record(InputEvent::Scroll { y });
+        self.scroll += y;
+    }
+
+    pub fn char_event(&mut self, c: char) {
+        self.record(InputEvent::Char(c));
+        self.text.push(c);
+    }
+
+    /// Позиция курсора в пикселях от левого верхнего угла окна
+    pub fn cursor(&self) -> (f32, f32) {
+        (self.x as f32, self.y as f32)
+    }
+
+    /// Новое состояние геймпада; кадр меняется у кнопок, чьё состояние изменилось
+    pub fn gamepad_event(&mut self, state: GamepadState) {
+        if state == self.gamepad {
+            return;
+        }
+        self.record(InputEvent::Gamepad(state));
+        for input in 0..PAD_INPUT_COUNT {
+            if state.pressed(input) != self.gamepad.pressed(input) {
+                self.pad_frames[input] = self.current;
+            }
+        }
+        self.gamepad = state;
+    }
+
+    #[cfg(feature = "graphics")]
+    pub fn pull_events(&mut self, window: &mut Window) {
+        self.begin_frame();
 
         for (_, event) in glfw::flush_messages(&window.receiver) {
             match event {
@@ -48,52 +165,30 @@ impl Events {
                     unsafe {
                         gl::Viewport(0, 0, w, h);
                     }
+                    self.resize_event(w, h);
                 }
                 glfw::WindowEvent::CursorPos(xpos, ypos) => {
-                    if self.cursor_started {
-                        self.delta_x += (xpos - self.x) as f32;
-                        self.delta_y += (ypos - self.y) as f32;
-                    } else {
-                        self.cursor_started = true;
-                    }
-                    self.x = xpos;
-                    self.y = ypos;
+                    self.cursor_event(xpos, ypos);
+                }
+                glfw::WindowEvent::Char(c) => {
+                    self.char_event(c);
+                }
+                glfw::WindowEvent::Scroll(_, y) => {
+                    self.scroll_event(y as f32);
                 }
                 glfw::WindowEvent::MouseButton(button, action, _) => {
-                    let button_index = match button {
-                        glfw::MouseButton::Button1 => 1024,
-                        glfw::MouseButton::Button2 => 1025,
-                        glfw::MouseButton::Button3 => 1026,
-                        glfw::MouseButton::Button4 => 1027,
-                        glfw::MouseButton::Button5 => 1028,
-                        glfw::MouseButton::Button6 => 1029,
-                        glfw::MouseButton::Button7 => 1030,
-                        glfw::MouseButton::Button8 => 1031,
-                    };
-
+                    let button_index = 1024 + button as usize;
                     match action {
-                        glfw::Action::Press => {
-                            self.keys[button_index] = true;
-                            self.frames[button_index] = self.current;
-                        }
-                        glfw::Action::Release => {
-                            self.keys[button_index] = false;
-                            self.frames[button_index] = self.current;
-                        }
+                        glfw::Action::Press => self.key_event(button_index, true),
+                        glfw::Action::Release => self.key_event(button_index, false),
                         _ => {}
                     }
                 }
                 glfw::WindowEvent::Key(key, _, action, _) => {
                     let key_index = key as usize;
                     match action {
-                        glfw::Action::Press => {
-                            self.keys[key_index] = true;
-                            self.frames[key_index] = self.current;
-                        }
-                        glfw::Action::Release => {
-                            self.keys[key_index] = false;
-                            self.frames[key_index] = self.current;
-                        }
+                        glfw::Action::Press => self.key_event(key_index, true),
+                        glfw::Action::Release => self.key_event(key_index, false),
                         _ => {}
                     }
                 }
@@ -118,7 +213,7 @@ impl Events {
         self.keys[keycode] && self.frames[keycode] == self.current
     }
 
-    pub fn _clicked(&self, button: i32) -> bool {
+    pub fn clicked(&self, button: i32) -> bool {
         let button_index = (button + 1024) as usize;
         self.keys[button_index]
     }
@@ -127,12 +222,21 @@ impl Events {
         let button_index = (button + 1024) as usize;
         self.keys[button_index] && self.frames[button_index] == self.current
     }
-    pub fn toggle_cursor(&mut self) -> glfw::CursorMode{
-            self.cursor_locked = !self.cursor_locked;
-            if self.cursor_locked {
-                glfw::CursorMode::Disabled
-            } else {
-                glfw::CursorMode::Normal
-            }
+
+    pub fn pad_pressed(&self, input: usize) -> bool {
+        input < PAD_INPUT_COUNT && self.gamepad.pressed(input)
+    }
+
+    pub fn pad_jpressed(&self, input: usize) -> bool {
+        self.pad_pressed(input) && self.pad_frames[input] == self.current
+    }
+
+    #[cfg(feature = "graphics")]
+    pub fn cursor_mode(&self) -> glfw::CursorMode {
+        if self.cursor_locked {
+            glfw::CursorMode::Disabled
+        } else {
+            glfw::CursorMode::Normal
+        }
     }
 }
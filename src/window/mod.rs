@@ -1,8 +1,10 @@
+#[cfg(feature = "graphics")]
 mod window;
 mod events;
 mod camera;
 
 
+#[cfg(feature = "graphics")]
 pub use window::Window;
 pub use events::Events;
 pub use camera::Camera;
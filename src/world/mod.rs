@@ -1,3 +1,10 @@
+mod world_state;
+#[cfg(feature = "graphics")]
 mod world_render;
+mod world_time;
+pub mod world_file;
 
-pub use world_render::draw_world;
\ No newline at end of file
+pub use world_state::{World, BlockChange, TickInput, TICK_DELTA, REACH_DISTANCE};
+#[cfg(feature = "graphics")]
+pub use world_render::draw_world;
+pub use world_time::WorldTime;
@@ -0,0 +1,239 @@
+use std::fs;
+use std::io;
+
+use crate::files::write_binary_file;
+use crate::voxels::{Chunks, CHUNK_VOL, DEFAULT_SEED};
+
+
+/// Сигнатура файла мира
+pub const MAGIC: &[u8; 4] = b"VXCW";
+/// Текущая версия формата. Во второй версии появились состояния вокселей
+pub const VERSION: u16 = 2;
+/// Флаг: после вокселей записаны карты освещения
+pub const FLAG_LIGHT: u16 = 1;
+
+/// Наибольший размер мира по каждой оси в чанках
+pub const MAX_CHUNKS: usize = 1024;
+
+const HEADER_SIZE: usize = 4 + 2 + 2 + 4 * 4;
+
+
+/// Заголовок файла мира. Все числа little-endian:
+/// `MAGIC`, версия u16, флаги u16, размер в чанках w, h, d u32, зерно u32.
+/// Дальше идут id вокселей по чанкам (байт на воксель), с версии 2 - их
+/// состояния (тоже байт на воксель), а при `FLAG_LIGHT` ещё и карты освещения
+/// (u16 на воксель)
+#[derive(Clone, Copy, Debug)]
+pub struct WorldHeader {
+    pub version: u16,
+    pub flags: u16,
+    pub w: usize,
+    pub h: usize,
+    pub d: usize,
+    pub seed: u32,
+}
+
+
+impl WorldHeader {
+    pub fn new(chunks: &Chunks, flags: u16) -> Self {
+        Self { version: VERSION, flags, w: chunks.w, h: chunks.h, d: chunks.d, seed: chunks.seed }
+    }
+
+
+    pub fn has_light(&self) -> bool {
+        self.flags & FLAG_LIGHT != 0
+    }
+
+
+    /// Число чанков; `None`, если оно не помещается в `usize`
+    pub fn volume(&self) -> Option<usize> {
+        self.w.checked_mul(self.h)?.checked_mul(self.d)
+    }
+
+
+    pub fn has_states(&self) -> bool {
+        self.version >= 2
+    }
+
+
+    /// Ожидаемый размер файла с этим заголовком в байтах
+    pub fn file_size(&self) -> Option<usize> {
+        let voxels = self.volume()?.checked_mul(CHUNK_VOL)?;
+        let states = if self.has_states() { voxels } else { 0 };
+        let light = if self.has_light() { voxels.checked_mul(2)? } else { 0 };
+        HEADER_SIZE.checked_add(voxels)?.checked_add(states)?.checked_add(light)
+    }
+
+
+    fn write(&self, dest: &mut Vec<u8>) {
+        dest.extend_from_slice(MAGIC);
+        dest.extend_from_slice(&self.version.to_le_bytes());
+        dest.extend_from_slice(&self.flags.to_le_bytes());
+        for value in [self.w, self.h, self.d] {
+            dest.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        dest.extend_from_slice(&self.seed.to_le_bytes());
+    }
+
+
+    fn read(source: &[u8]) -> io::Result<Self> {
+        if source.len() < HEADER_SIZE || &source[0..4] != MAGIC {
+            return Err(invalid("not a world file"));
+        }
+        let u16_at = |i: usize| u16::from_le_bytes([source[i], source[i + 1]]);
+        let u32_at = |i: usize| u32::from_le_bytes([source[i], source[i + 1], source[i + 2], source[i + 3]]);
+
+        let header = Self {
+            version: u16_at(4),
+            flags: u16_at(6),
+            w: u32_at(8) as usize,
+            h: u32_at(12) as usize,
+            d: u32_at(16) as usize,
+            seed: u32_at(20),
+        };
+        if header.version > VERSION {
+            return Err(invalid(&format!("unsupported world version {}", header.version)));
+        }
+        if [header.w, header.h, header.d].iter().any(|&size| size == 0 || size > MAX_CHUNKS) {
+            return Err(invalid(&format!(
+                "world size {}x{}x{} chunks is out of 1..={} per axis",
+                header.w, header.h, header.d, MAX_CHUNKS,
+            )));
+        }
+        Ok(header)
+    }
+}
+
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+
+/// Начинается ли файл с сигнатуры формата. Старые миры - просто id вокселей
+pub fn is_world_file(data: &[u8]) -> bool {
+    data.len() >= 4 && &data[0..4] == MAGIC
+}
+
+
+/// Читает мир целиком. Воксели генератором не перезаписываются:
+/// чанки создаются по зерну из файла, а затем заполняются данными
+pub fn load(path: &str) -> io::Result<(WorldHeader, Chunks)> {
+    let data = fs::read(path)?;
+    if !is_world_file(&data) {
+        return Err(invalid(&format!(
+            "'{}' has no world header; an old world can be upgraded with `convert {} <output>`",
+            path, path,
+        )));
+    }
+    let header = WorldHeader::read(&data)?;
+    let file_size = header.file_size().ok_or_else(|| invalid("world size overflows"))?;
+    if data.len() < file_size {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("world file '{}' has {} of {} bytes", path, data.len(), file_size),
+        ));
+    }
+
+    let mut chunks = Chunks::new(header.w, header.h, header.d, header.seed);
+    let voxels = &data[HEADER_SIZE..];
+    chunks.read(voxels);
+    let mut rest = &voxels[chunks.volume * CHUNK_VOL..];
+    if header.has_states() {
+        chunks.read_states(rest);
+        rest = &rest[chunks.volume * CHUNK_VOL..];
+    }
+
+    if header.has_light() {
+        let light = rest;
+        let mut index = 0;
+        for chunk in &mut chunks.chunks {
+            for value in &mut chunk.lightmap.map {
+                *value = u16::from_le_bytes([light[index], light[index + 1]]);
+                index += 2;
+            }
+        }
+    }
+    Ok((header, chunks))
+}
+
+
+/// Читает мир в старом формате без заголовка; размер и зерно в нём не хранятся
+pub fn load_legacy(path: &str, w: usize, h: usize, d: usize) -> io::Result<Chunks> {
+    let data = fs::read(path)?;
+    let size = w
+        .checked_mul(h)
+        .and_then(|size| size.checked_mul(d))
+        .and_then(|size| size.checked_mul(CHUNK_VOL))
+        .ok_or_else(|| invalid(&format!("legacy world size {}x{}x{} overflows", w, h, d)))?;
+    if data.len() != size {
+        return Err(invalid(&format!(
+            "legacy world '{}' has {} bytes, expected {} for {}x{}x{} chunks",
+            path, data.len(), size, w, h, d,
+        )));
+    }
+    let mut chunks = Chunks::new(w, h, d, DEFAULT_SEED);
+    chunks.read(&data);
+    Ok(chunks)
+}
+
+
+/// Записывает мир; `with_light` сохраняет и текущие карты освещения
+pub fn save(path: &str, chunks: &Chunks, with_light: bool) -> io::Result<()> {
+    let header = WorldHeader::new(chunks, if with_light { FLAG_LIGHT } else { 0 });
+    let mut data = Vec::with_capacity(header.file_size().unwrap_or(0));
+    header.write(&mut data);
+
+    let start = data.len();
+    data.resize(start + chunks.volume * CHUNK_VOL * 2, 0);
+    let (ids, states) = data[start..].split_at_mut(chunks.volume * CHUNK_VOL);
+    chunks.write(ids);
+    chunks.write_states(states);
+
+    if with_light {
+        for chunk in &chunks.chunks {
+            for value in &chunk.lightmap.map {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+    write_binary_file(path, &data)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header_bytes(w: u32, h: u32, d: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&VERSION.to_le_bytes());
+        data.extend_from_slice(&FLAG_LIGHT.to_le_bytes());
+        for value in [w, h, d, DEFAULT_SEED] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn reads_header() {
+        let header = WorldHeader::read(&header_bytes(2, 3, 4)).unwrap();
+        assert_eq!((header.w, header.h, header.d), (2, 3, 4));
+        assert_eq!(header.file_size(), Some(HEADER_SIZE + 24 * CHUNK_VOL * 4));
+    }
+
+    #[test]
+    fn rejects_oversized_header() {
+        assert!(WorldHeader::read(&header_bytes(u32::MAX, u32::MAX, u32::MAX)).is_err());
+        assert!(WorldHeader::read(&header_bytes(MAX_CHUNKS as u32 + 1, 1, 1)).is_err());
+        assert!(WorldHeader::read(&header_bytes(0, 1, 1)).is_err());
+    }
+
+    #[test]
+    fn file_size_overflow_is_none() {
+        let header = WorldHeader { version: VERSION, flags: FLAG_LIGHT, w: usize::MAX, h: 2, d: 1, seed: 0 };
+        assert_eq!(header.volume(), None);
+        assert_eq!(header.file_size(), None);
+    }
+}
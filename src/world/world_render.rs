@@ -1,16 +1,20 @@
-use gl::{DEPTH_BUFFER_BIT, LINES};
+use gl::DEPTH_BUFFER_BIT;
 use glam::{vec3, Mat4};
 use crate::assets::Assets;
 use crate::graphics::linebatch::LineBatch;
-use crate::graphics::mesh::Mesh;
+use crate::graphics::Mesh;
 use crate::settings::TRIANGLES;
 use crate::voxels::{CHUNK_D, CHUNK_H, CHUNK_W};
-use crate::voxels::chunks::Chunks;
 use crate::window::{Camera, Window};
+use super::World;
 use std::borrow::Borrow;
 
-pub fn draw_world(assets: &Assets, camera: &Camera, window: &Window, chunks: &Chunks, meshes: &Vec<Option<Mesh>>, crosshair: &Mesh, line_batch: &mut LineBatch ) {
+/// Рисует чанки и линии; возвращает число нарисованных чанков
+pub fn draw_world(assets: &Assets, camera: &Camera, window: &Window, world: &World, meshes: &Vec<Option<Mesh>>, line_batch: &mut LineBatch) -> usize {
+    let chunks = &world.chunks;
+    let clear_color = world.time.clear_color();
     unsafe {
+        gl::ClearColor(clear_color.x, clear_color.y, clear_color.z, 1.0);
         gl::Clear(gl::COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT);
     }
 
@@ -21,11 +25,13 @@ pub fn draw_world(assets: &Assets, camera: &Camera, window: &Window, chunks: &Ch
         "projview",
         camera.get_projection(window.width() as f32, window.height() as f32) * camera.get_view()
     );
+    assets.shader.uniform_vec3("u_skyLight", world.time.sky_light());
 
     // Привязываем текстуру
     assets.texture.bind();
 
     let mut _model = Mat4::IDENTITY;
+    let mut drawn = 0;
     for i in 0..chunks.volume {
         let chunk = &chunks.chunks[i];
         let mesh = meshes[i].borrow();
@@ -39,14 +45,12 @@ pub fn draw_world(assets: &Assets, camera: &Camera, window: &Window, chunks: &Ch
                     )
                 );
         assets.shader.uniform_matrix("model", _model);
-        if let Some(mesh) = mesh {
+        if let Some(mesh) = mesh.as_ref().filter(|mesh| mesh.vertex_count() > 0) {
             mesh.draw(TRIANGLES);
+            drawn += 1;
         }
     }
 
-    assets.crosshair_shader.use_shader();
-    crosshair.draw(LINES);
-
     assets.lines_shader.use_shader();
     assets.shader.uniform_matrix(
         "projview",
@@ -58,4 +62,5 @@ pub fn draw_world(assets: &Assets, camera: &Camera, window: &Window, chunks: &Ch
         gl::LineWidth(2.0);
     }
     line_batch.render();
+    drawn
 }
\ No newline at end of file
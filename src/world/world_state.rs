@@ -0,0 +1,252 @@
+use std::io;
+
+use glam::Vec3;
+
+use crate::fluids::Fluids;
+use crate::lighting::Lighting;
+use crate::player::{Player, PlayerInput};
+use crate::ticks::BlockTicks;
+use crate::voxels::{BlockRegistry, Chunks, Voxel};
+use super::world_file;
+use super::WorldTime;
+
+
+/// Частота шагов симуляции в секунду
+pub const TICK_RATE: u32 = 60;
+/// Длительность одного шага симуляции в секундах
+pub const TICK_DELTA: f32 = 1.0 / TICK_RATE as f32;
+/// Дальность, на которой игрок может ставить и ломать блоки
+pub const REACH_DISTANCE: f32 = 10.0;
+
+
+/// Ввод, накопленный за кадр и применяемый на шаге симуляции.
+///
+/// Одноразовые действия (`toggle_mode`, `break_block`, `place_block`)
+/// выполняются только на первом шаге после нажатия.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TickInput {
+    pub player: PlayerInput,
+    /// Направление взгляда игрока
+    pub look: Vec3,
+    /// Скорость перемотки времени суток, долей суток в секунду
+    pub time_shift: f32,
+    pub toggle_mode: bool,
+    pub break_block: bool,
+    pub place_block: Option<u8>,
+}
+
+
+impl TickInput {
+    /// Добавляет ввод нового кадра к ещё не обработанному
+    pub fn merge(&mut self, frame: &TickInput) {
+        self.player = frame.player;
+        self.look = frame.look;
+        self.time_shift = frame.time_shift;
+        self.toggle_mode |= frame.toggle_mode;
+        self.break_block |= frame.break_block;
+        self.place_block = frame.place_block.or(self.place_block);
+    }
+
+
+    pub fn clear_actions(&mut self) {
+        self.toggle_mode = false;
+        self.break_block = false;
+        self.place_block = None;
+    }
+}
+
+
+/// Блок под прицелом и клетка перед его гранью, куда ставится новый блок
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Target {
+    pub block: [isize; 3],
+    pub front: [isize; 3],
+}
+
+
+/// Замена одного блока вместе с состоянием, по которой её можно отменить
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockChange {
+    pub position: [isize; 3],
+    pub from: Voxel,
+    pub to: Voxel,
+}
+
+
+/// Состояние мира, которое меняется только шагами симуляции
+pub struct World {
+    pub chunks: Chunks,
+    pub lighting: Lighting,
+    pub fluids: Fluids,
+    pub ticks: BlockTicks,
+    pub time: WorldTime,
+    pub player: Player,
+    pub tick: u64,
+    previous_eye: Vec3,
+}
+
+
+impl World {
+    pub fn new(chunks: Chunks, time: WorldTime, player: Player) -> Self {
+        let previous_eye = player.eye_position();
+        Self {
+            chunks,
+            lighting: Lighting::new(),
+            fluids: Fluids::default(),
+            ticks: BlockTicks::default(),
+            time,
+            player,
+            tick: 0,
+            previous_eye,
+        }
+    }
+
+
+    /// Сохраняет мир вместе с освещением
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        world_file::save(path, &self.chunks, true)
+    }
+
+
+    /// Пересчитывает освещение всего мира с нуля
+    pub fn relight(&mut self, blocks: &BlockRegistry) {
+        self.lighting.clear(&mut self.chunks);
+        self.lighting.on_world_loaded(blocks, &mut self.chunks);
+    }
+
+
+    /// Один шаг симуляции длительностью `TICK_DELTA`. Не требует окна
+    pub fn update(&mut self, input: &TickInput, blocks: &BlockRegistry) {
+        self.previous_eye = self.player.eye_position();
+        self.tick += 1;
+
+        self.time.update(TICK_DELTA);
+        if input.time_shift != 0.0 {
+            let time = self.time.time_of_day() + input.time_shift * TICK_DELTA;
+            self.time.set_time_of_day(time);
+        }
+
+        if input.toggle_mode {
+            self.player.toggle_mode();
+        }
+        self.player.update(&input.player, &self.chunks, blocks, TICK_DELTA);
+
+        if input.break_block || input.place_block.is_some() {
+            self.edit(input, blocks);
+        }
+
+        let mut changed = self.fluids.update(self.tick, &mut self.chunks, blocks);
+        changed.extend(self.ticks.update(self.tick, &mut self.chunks, blocks));
+        if !changed.is_empty() {
+            self.lighting.on_blocks_set(&changed, blocks, &mut self.chunks);
+            self.schedule_around(&changed, blocks);
+        }
+    }
+
+
+    fn edit(&mut self, input: &TickInput, blocks: &BlockRegistry) {
+        let Some(target) = self.target(input.look) else {
+            return;
+        };
+
+        if input.break_block {
+            let [x, y, z] = target.block;
+            self.set_block(x, y, z, 0, blocks);
+        }
+
+        if let Some(id) = input.place_block {
+            let [x, y, z] = target.front;
+            self.set_block(x, y, z, id, blocks);
+        }
+    }
+
+
+    /// Блок, на который игрок смотрит в направлении `look`, в пределах досягаемости
+    pub fn target(&self, look: Vec3) -> Option<Target> {
+        let mut end = Vec3::default();
+        let mut norm = Vec3::default();
+        let mut iend = Vec3::default();
+
+        let eye = self.player.eye_position();
+        self.chunks.ray_cast(eye, look, REACH_DISTANCE, &mut end, &mut norm, &mut iend)?;
+        let front = iend + norm;
+        Some(Target {
+            block: [iend.x as isize, iend.y as isize, iend.z as isize],
+            front: [front.x as isize, front.y as isize, front.z as isize],
+        })
+    }
+
+
+    pub fn set_block(&mut self, x: isize, y: isize, z: isize, id: u8, blocks: &BlockRegistry) {
+        self.chunks.set(x, y, z, id as i32);
+        self.lighting.on_block_set(x, y, z, id, blocks, &mut self.chunks);
+        self.schedule_around(&[[x, y, z]], blocks);
+    }
+
+
+    /// Ставит много блоков сразу и пересчитывает свет один раз на всю пачку.
+    /// Клетки вне мира и совпадающие блоки с тем же состоянием пропускаются;
+    /// возвращает то, что действительно изменилось
+    pub fn set_blocks(&mut self, blocks_to_set: impl IntoIterator<Item = ([isize; 3], Voxel)>, blocks: &BlockRegistry) -> Vec<BlockChange> {
+        let mut changes = Vec::new();
+        for (position, to) in blocks_to_set {
+            let [x, y, z] = position;
+            let Some(&from) = self.chunks.get_voxel(x, y, z) else {
+                continue;
+            };
+            if from != to {
+                self.chunks.set(x, y, z, to.id as i32);
+                self.chunks.set_states(x, y, z, to.states);
+                changes.push(BlockChange { position, from, to });
+            }
+        }
+        let positions: Vec<[isize; 3]> = changes.iter().map(|change| change.position).collect();
+        self.lighting.on_blocks_set(&positions, blocks, &mut self.chunks);
+        self.schedule_around(&positions, blocks);
+        changes
+    }
+
+
+    /// Будит жидкости и блоки с поведением рядом с изменившимися клетками
+    fn schedule_around(&mut self, positions: &[[isize; 3]], blocks: &BlockRegistry) {
+        for position in positions {
+            self.fluids.schedule_around(*position, self.tick, &self.chunks, blocks);
+            self.ticks.schedule_around(*position, self.tick, &self.chunks, blocks);
+        }
+    }
+
+
+    /// Хеш вокселей и освещения: одинаковые прогоны дают одинаковое значение
+    pub fn checksum(&self) -> u64 {
+        // FNV-1a
+        let mut hash: u64 = 0xcbf29ce484222325;
+        let mut add = |byte: u8| {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        };
+        for chunk in &self.chunks.chunks {
+            for voxel in &chunk.voxels {
+                add(voxel.id);
+                add(voxel.states);
+            }
+            for light in &chunk.lightmap.map {
+                add(*light as u8);
+                add((*light >> 8) as u8);
+            }
+        }
+        hash
+    }
+
+
+    /// Мгновенное перемещение игрока, без сглаживания между шагами
+    pub fn teleport(&mut self, eye: Vec3) {
+        self.player.teleport(eye);
+        self.previous_eye = eye;
+    }
+
+
+    /// Позиция глаз игрока между двумя последними шагами, `alpha` в [0, 1]
+    pub fn eye_position(&self, alpha: f32) -> Vec3 {
+        self.previous_eye.lerp(self.player.eye_position(), alpha)
+    }
+}
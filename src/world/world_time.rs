@@ -0,0 +1,151 @@
+use std::f32::consts::PI;
+use glam::Vec3;
+
+
+/// Минимальная яркость неба ночью
+const NIGHT_LIGHT: f32 = 0.15;
+
+const DAY_SKY: Vec3 = Vec3::new(0.53, 0.72, 0.95);
+const NIGHT_SKY: Vec3 = Vec3::new(0.02, 0.03, 0.08);
+const SUNSET_SKY: Vec3 = Vec3::new(0.95, 0.45, 0.2);
+
+const DAY_TINT: Vec3 = Vec3::new(1.0, 1.0, 1.0);
+const NIGHT_TINT: Vec3 = Vec3::new(0.6, 0.7, 1.0);
+const SUNSET_TINT: Vec3 = Vec3::new(1.0, 0.6, 0.35);
+
+
+/// Игровые часы мира.
+///
+/// Время суток хранится в долях дня: 0.0 - полночь, 0.25 - рассвет,
+/// 0.5 - полдень, 0.75 - закат.
+pub struct WorldTime {
+    time: f32,
+    elapsed: f64,
+    pub day_length: f32,
+}
+
+
+impl WorldTime {
+    /// `day_length` - длина суток в секундах
+    pub fn new(day_length: f32) -> Self {
+        Self { time: 0.5, elapsed: 0.0, day_length }
+    }
+
+
+    pub fn update(&mut self, delta: f32) {
+        self.elapsed += delta as f64;
+        if self.day_length <= 0.0 {
+            return;
+        }
+        self.time = (self.time + delta / self.day_length).rem_euclid(1.0);
+    }
+
+
+    /// Игровое время в секундах с запуска, не зависит от смены суток
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+
+    pub fn time_of_day(&self) -> f32 {
+        self.time
+    }
+
+
+    pub fn set_time_of_day(&mut self, time: f32) {
+        self.time = time.rem_euclid(1.0);
+    }
+
+
+    /// Высота солнца: 1.0 в полдень, -1.0 в полночь
+    pub fn sun_height(&self) -> f32 {
+        ((self.time - 0.25) * 2.0 * PI).sin()
+    }
+
+
+    /// Яркость небесного света в диапазоне [NIGHT_LIGHT, 1.0]
+    pub fn sky_intensity(&self) -> f32 {
+        let day = smoothstep(-0.2, 0.3, self.sun_height());
+        NIGHT_LIGHT + (1.0 - NIGHT_LIGHT) * day
+    }
+
+
+    /// Насколько сейчас рассвет/закат: 1.0 когда солнце у горизонта
+    fn twilight(&self) -> f32 {
+        1.0 - smoothstep(0.0, 0.35, self.sun_height().abs())
+    }
+
+
+    /// Цвет небесного света для униформы `u_skyLight`
+    pub fn sky_light(&self) -> Vec3 {
+        let day = smoothstep(-0.2, 0.3, self.sun_height());
+        let tint = NIGHT_TINT.lerp(DAY_TINT, day).lerp(SUNSET_TINT, self.twilight() * 0.7);
+        tint * self.sky_intensity()
+    }
+
+
+    /// Цвет очистки экрана (цвет неба)
+    pub fn clear_color(&self) -> Vec3 {
+        let day = smoothstep(-0.2, 0.3, self.sun_height());
+        let color = NIGHT_SKY.lerp(DAY_SKY, day);
+        let sunset = self.twilight() * smoothstep(-0.3, 0.0, self.sun_height());
+        color.lerp(SUNSET_SKY, sunset * 0.6)
+    }
+}
+
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn at(time: f32) -> WorldTime {
+        let mut world_time = WorldTime::new(600.0);
+        world_time.set_time_of_day(time);
+        world_time
+    }
+
+    #[test]
+    fn time_wraps_around() {
+        let mut world_time = at(0.9);
+        world_time.update(120.0);
+        assert!((world_time.time_of_day() - 0.1).abs() < EPSILON);
+        assert!((world_time.elapsed() - 120.0).abs() < 1e-9);
+
+        assert!((at(1.25).time_of_day() - 0.25).abs() < EPSILON);
+        assert!((at(-0.25).time_of_day() - 0.75).abs() < EPSILON);
+    }
+
+    #[test]
+    fn frozen_day_keeps_time() {
+        let mut world_time = WorldTime::new(0.0);
+        world_time.update(10.0);
+        assert_eq!(world_time.time_of_day(), 0.5);
+        assert_eq!(world_time.elapsed(), 10.0);
+    }
+
+    #[test]
+    fn sun_is_high_at_noon_and_low_at_midnight() {
+        let (noon, midnight) = (at(0.5), at(0.0));
+        assert!((noon.sun_height() - 1.0).abs() < EPSILON);
+        assert!((midnight.sun_height() + 1.0).abs() < EPSILON);
+        assert!((noon.sky_intensity() - 1.0).abs() < EPSILON);
+        assert!((midnight.sky_intensity() - NIGHT_LIGHT).abs() < EPSILON);
+    }
+
+    #[test]
+    fn colors_reach_day_and_night_endpoints() {
+        let (noon, midnight) = (at(0.5), at(0.0));
+        assert!(noon.clear_color().abs_diff_eq(DAY_SKY, EPSILON));
+        assert!(midnight.clear_color().abs_diff_eq(NIGHT_SKY, EPSILON));
+        assert!(noon.sky_light().abs_diff_eq(DAY_TINT, EPSILON));
+        assert!(midnight.sky_light().abs_diff_eq(NIGHT_TINT * NIGHT_LIGHT, EPSILON));
+    }
+}